@@ -0,0 +1,61 @@
+use crate::{ConversionError, PngCompression, RenderedPage};
+use image::{Rgba, RgbaImage, imageops};
+
+/// Every page of a document as the frames of one animated PNG, named `{stem}.apng`, showing
+/// each page for `delay_millis` and looping forever. Unlike a GIF every frame keeps its full
+/// color, so the file is about as large as all the pages as PNGs together. APNG frames all have
+/// the size of the largest page, smaller pages are centered on transparency.
+pub fn apng(
+    stem: &str,
+    pages: &[RenderedPage],
+    delay_millis: u32,
+    compression: PngCompression,
+) -> Result<(String, Vec<u8>), ConversionError> {
+    let frames = pages
+        .iter()
+        .filter_map(RenderedPage::first_encoded)
+        .map(|(format, bytes)| {
+            Ok(image::load_from_memory_with_format(bytes, format.image_format())?.to_rgba8())
+        })
+        .collect::<Result<Vec<_>, ConversionError>>()?;
+    if frames.is_empty() {
+        return Err(ConversionError::EncodeFailed(
+            "there are no pages to animate".to_string(),
+        ));
+    }
+    let width = frames.iter().map(RgbaImage::width).max().unwrap_or(1);
+    let height = frames.iter().map(RgbaImage::height).max().unwrap_or(1);
+    // APNG delays are a fraction of 16-bit numbers of seconds
+    let delay = u16::try_from(delay_millis).unwrap_or(u16::MAX);
+
+    let encode_failed = |err: png::EncodingError| ConversionError::EncodeFailed(err.to_string());
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(compression.png());
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(encode_failed)?;
+    encoder
+        .set_frame_delay(delay, 1000)
+        .map_err(encode_failed)?;
+    let mut writer = encoder.write_header().map_err(encode_failed)?;
+    for frame in frames {
+        let frame = if frame.dimensions() == (width, height) {
+            frame
+        } else {
+            let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+            imageops::overlay(
+                &mut canvas,
+                &frame,
+                i64::from((width - frame.width()) / 2),
+                i64::from((height - frame.height()) / 2),
+            );
+            canvas
+        };
+        writer.write_image_data(&frame).map_err(encode_failed)?;
+    }
+    writer.finish().map_err(encode_failed)?;
+    Ok((format!("{stem}.apng"), bytes))
+}
@@ -0,0 +1,874 @@
+use crate::tile::layout_json;
+use crate::{
+    Attachment, ConversionError, LinkTarget, OutputFormat, RenderedPage, Settings, contact_sheet,
+    index_html,
+};
+use humansize::format_size;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io::{Cursor, Read, Write};
+use std::path::Component;
+use std::rc::Rc;
+use zip::write::{SimpleFileOptions, StreamWriter};
+use zip::{ZipArchive, ZipWriter};
+
+/// Largest file that is read at all, uploaded or unpacked from a zip. Reading a file copies all
+/// of it into memory, and converting and zipping its pages takes several times more, so larger
+/// files would crash the page instead of failing.
+pub const MAX_FILE_SIZE: u64 = 500 * 1024 * 1024;
+/// Most bytes all PDFs unpacked from one zip may add up to.
+const MAX_UNZIPPED_SIZE: u64 = 2 * MAX_FILE_SIZE;
+/// Most entries a zip may have for its PDFs to be unpacked.
+const MAX_ZIP_ENTRIES: usize = 10_000;
+
+static STORED_OPTIONS: Lazy<SimpleFileOptions> =
+    Lazy::new(|| SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored));
+static DEFLATED_OPTIONS: Lazy<SimpleFileOptions> =
+    Lazy::new(|| SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated));
+
+/// How to add `filename` to a zip: deflated if it is an image in one of
+/// `settings.deflated_formats`, stored as it is otherwise.
+fn file_options(filename: &str, settings: &Settings) -> SimpleFileOptions {
+    let deflated = settings.deflated_formats.iter().any(|format| {
+        filename
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| extension == format.extension())
+    });
+    if deflated {
+        *DEFLATED_OPTIONS
+    } else {
+        *STORED_OPTIONS
+    }
+}
+
+/// Write the given `(filename, bytes)` entries into a zip archive, in order, or sorted by
+/// filename with `settings.sorted_entries`. Every entry is dated 1980-01-01, so with sorting
+/// the same files always make the same archive.
+pub fn zip_entries<B: AsRef<[u8]>>(
+    entries: impl IntoIterator<Item = (String, B)>,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    if settings.sorted_entries {
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    write_zip(entries, settings)
+}
+
+/// Write `entries` into a zip archive in the order given.
+fn write_zip<B: AsRef<[u8]>>(
+    entries: Vec<(String, B)>,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut zip_writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (filename, bytes) in entries {
+        let options = file_options(&filename, settings);
+        zip_writer.start_file(filename, options)?;
+        zip_writer.write_all(bytes.as_ref())?;
+    }
+    Ok(zip_writer.finish()?.into_inner())
+}
+
+/// Every file making up the output of a single document: its page images in `format`, with
+/// pages not rendered in `format` left out. Tiled pages are replaced by their tiles and a
+/// `{stem}-tiles.json` layout. If the pages carry checksums, a `{stem}.sha256` listing is
+/// added. With `settings.manifest` a `{stem}.csv` manifest is added, and with
+/// `settings.json_manifest` a `manifest.json`. With `settings.attachments` the document's
+/// `attachments` go into an `attachments/` folder. With `settings.diagnostics` a
+/// `{stem}-diagnostics.txt` is added. With `settings.links` a `{stem}-links.json` is added.
+/// With `settings.text_positions` a `{stem}-text.json` is added. With
+/// `settings.include_source` the `source` PDF is added as `source/{stem}.pdf`. With web
+/// copies of the pages, those go into `web/` and everything else into `master/`.
+pub fn document_entries<'a>(
+    stem: &str,
+    pages: &'a [RenderedPage],
+    attachments: &'a [Attachment],
+    source: &'a [u8],
+    format: OutputFormat,
+    settings: &Settings,
+) -> Vec<(String, Cow<'a, [u8]>)> {
+    let folder = master_folder(pages);
+    format_entries(stem, pages, format, settings)
+        .into_iter()
+        .map(|(filename, bytes)| (format!("{folder}{filename}"), bytes))
+        .chain(extra_entries(stem, pages, attachments, source, settings))
+        .collect()
+}
+
+/// The files of a document in `format`, see [`document_entries`].
+fn format_entries<'a>(
+    stem: &str,
+    pages: &'a [RenderedPage],
+    format: OutputFormat,
+    settings: &Settings,
+) -> Vec<(String, Cow<'a, [u8]>)> {
+    let mut entries = Vec::new();
+    let mut manifest = String::from("file,page,width,height,channels,has_alpha\n");
+    let mut manifest_pages = Vec::new();
+    // filenames of the tiles of every page, for the layout
+    let mut tile_files = Vec::new();
+    for (index, page) in (1..).zip(pages) {
+        let mut files = Vec::new();
+        for (filename, (width, height), bytes) in page_images(page, format) {
+            manifest += &manifest_row(&filename, page, (width, height), format);
+            manifest_pages.push(ManifestPage {
+                index,
+                page: page.number,
+                file: filename.clone(),
+                width,
+                height,
+            });
+            if page.is_tiled() {
+                files.push(filename.clone());
+            }
+            entries.push((filename, Cow::Borrowed(bytes)));
+        }
+        tile_files.push(files);
+    }
+    if pages.iter().any(RenderedPage::is_tiled) {
+        let layout = layout_json(pages, &tile_files);
+        entries.push((
+            format!("{stem}-tiles.json"),
+            Cow::Owned(layout.into_bytes()),
+        ));
+    }
+    if let Some(listing) = checksum_listing(pages, format) {
+        entries.push((format!("{stem}.sha256"), Cow::Owned(listing.into_bytes())));
+    }
+    if settings.manifest {
+        entries.push((format!("{stem}.csv"), Cow::Owned(manifest.into_bytes())));
+    }
+    if settings.json_manifest {
+        let manifest = JsonManifest {
+            stem,
+            image_count: manifest_pages.len(),
+            pages: manifest_pages,
+        };
+        let json = serde_json::to_string_pretty(&manifest).expect("manifest serializes to JSON");
+        entries.push(("manifest.json".to_string(), Cow::Owned(json.into_bytes())));
+    }
+    entries
+}
+
+/// The images of a page in `format` as `(filename, dimensions, bytes)`: its tiles if it was
+/// split, otherwise itself at every export scale.
+fn page_images(page: &RenderedPage, format: OutputFormat) -> Vec<(String, (u32, u32), &[u8])> {
+    if page.is_tiled() {
+        page.tiles()
+            .iter()
+            .filter_map(|tile| {
+                let bytes = tile.encoded.get(&format)?;
+                let filename = page.tile_filename(tile, format);
+                Some((filename, (tile.width, tile.height), bytes.as_slice()))
+            })
+            .collect()
+    } else {
+        page.scaled_images(format)
+    }
+}
+
+/// Where the page images go, `master/` when there are web copies next to them.
+fn master_folder(pages: &[RenderedPage]) -> &'static str {
+    if pages.iter().any(|page| page.web_copy().is_some()) {
+        "master/"
+    } else {
+        ""
+    }
+}
+
+/// The files of a document that are the same in every format: web copies of its pages, its
+/// attachments and its diagnostics, as far as `settings` ask for them.
+fn extra_entries<'a>(
+    stem: &str,
+    pages: &'a [RenderedPage],
+    attachments: &'a [Attachment],
+    source: &'a [u8],
+    settings: &Settings,
+) -> impl Iterator<Item = (String, Cow<'a, [u8]>)> {
+    let web = pages
+        .iter()
+        .filter_map(RenderedPage::web_copy)
+        .map(|(filename, bytes)| (format!("web/{filename}"), Cow::Borrowed(bytes)));
+    let attachments = attachments
+        .iter()
+        .filter(|_| settings.attachments)
+        .map(|attachment| {
+            (
+                format!("attachments/{}", attachment.name),
+                Cow::Borrowed(attachment.data.as_slice()),
+            )
+        });
+    let diagnostics = settings.diagnostics.then(|| {
+        (
+            format!("{stem}-diagnostics.txt"),
+            Cow::Owned(diagnostics(pages).into_bytes()),
+        )
+    });
+    let links = settings.links.then(|| {
+        (
+            format!("{stem}-links.json"),
+            Cow::Owned(links_json(pages).into_bytes()),
+        )
+    });
+    let text = settings.text_positions.then(|| {
+        (
+            format!("{stem}-text.json"),
+            Cow::Owned(text_json(pages).into_bytes()),
+        )
+    });
+    let source = settings
+        .include_source
+        .then(|| (format!("source/{stem}.pdf"), Cow::Borrowed(source)));
+    web.chain(attachments)
+        .chain(source)
+        .chain(diagnostics)
+        .chain(links)
+        .chain(text)
+}
+
+#[derive(Serialize)]
+struct PageText {
+    page: usize,
+    /// The runs one after another, with a line break before every newly positioned one.
+    text: String,
+    runs: Vec<RunEntry>,
+    /// Characters in `text` so far.
+    #[serde(skip)]
+    length: usize,
+}
+
+#[derive(Serialize)]
+struct RunEntry {
+    /// Where the run is in `text`, in characters.
+    offset: usize,
+    length: usize,
+    x: f32,
+    y: f32,
+    size: f32,
+}
+
+/// The text of every page with its runs, pages of spreads listed separately.
+fn text_json(pages: &[RenderedPage]) -> String {
+    let mut listed: Vec<PageText> = Vec::new();
+    for run in pages.iter().flat_map(|page| &page.text) {
+        let page = match listed.last_mut() {
+            Some(last) if last.page == run.page => last,
+            _ => {
+                listed.push(PageText {
+                    page: run.page,
+                    text: String::new(),
+                    runs: Vec::new(),
+                    length: 0,
+                });
+                listed.last_mut().expect("a page was just added")
+            }
+        };
+        if run.new_line && page.length > 0 {
+            page.text.push('\n');
+            page.length += 1;
+        }
+        let length = run.text.chars().count();
+        page.runs.push(RunEntry {
+            offset: page.length,
+            length,
+            x: run.x,
+            y: run.y,
+            size: run.size,
+        });
+        page.text += &run.text;
+        page.length += length;
+    }
+    serde_json::to_string_pretty(&listed).expect("text serializes to JSON")
+}
+
+#[derive(Serialize)]
+struct PageLinks {
+    page: usize,
+    links: Vec<LinkEntry>,
+}
+
+#[derive(Serialize)]
+struct LinkEntry {
+    /// `external` for URIs, `internal` for pages of the same document.
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    /// `null` for internal links whose destination can't be found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_page: Option<Option<usize>>,
+    /// `[left, bottom, right, top]` in PDF points.
+    rect: [f32; 4],
+}
+
+/// The links of every page that has any, with the two pages of a spread listed separately.
+fn links_json(pages: &[RenderedPage]) -> String {
+    let mut listed: Vec<PageLinks> = Vec::new();
+    for link in pages.iter().flat_map(|page| &page.links) {
+        let entry = match &link.target {
+            LinkTarget::External(url) => LinkEntry {
+                kind: "external",
+                url: Some(url.clone()),
+                target_page: None,
+                rect: link.rect,
+            },
+            LinkTarget::Internal(page) => LinkEntry {
+                kind: "internal",
+                url: None,
+                target_page: Some(*page),
+                rect: link.rect,
+            },
+        };
+        match listed.last_mut() {
+            Some(last) if last.page == link.page => last.links.push(entry),
+            _ => listed.push(PageLinks {
+                page: link.page,
+                links: vec![entry],
+            }),
+        }
+    }
+    serde_json::to_string_pretty(&listed).expect("links serialize to JSON")
+}
+
+/// A line for every problem with every page, or saying there were none.
+fn diagnostics(pages: &[RenderedPage]) -> String {
+    let mut text = String::new();
+    for page in pages {
+        let label = page.label();
+        if let Some((width, height)) = page.downscaled_from {
+            let (actual_width, actual_height) = page.dimensions;
+            text += &format!(
+                "{label}: too large at {width}x{height}, rendered at {actual_width}x{actual_height}\n"
+            );
+        }
+        for warning in &page.warnings {
+            text += &format!("{label}: {warning}\n");
+        }
+        if page.downscaled_from.is_none() && page.warnings.is_empty() {
+            text += &format!("{label}: no problems reported\n");
+        }
+    }
+    text
+}
+
+#[derive(Serialize)]
+struct JsonManifest<'a> {
+    stem: &'a str,
+    /// Entries in `pages`, one per image, so every export scale and tile of a page counts.
+    image_count: usize,
+    pages: Vec<ManifestPage>,
+}
+
+#[derive(Serialize)]
+struct ManifestPage {
+    /// Position of the page in the archive, from 1.
+    index: usize,
+    /// Number of the page in the PDF, the first of a spread.
+    page: usize,
+    file: String,
+    width: u32,
+    height: u32,
+}
+
+fn manifest_row(
+    filename: &str,
+    page: &RenderedPage,
+    (width, height): (u32, u32),
+    format: OutputFormat,
+) -> String {
+    // filenames come from the template and can contain anything, quote them as CSV requires
+    let filename = format!("\"{}\"", filename.replace('"', "\"\""));
+    format!(
+        "{filename},{},{width},{height},{},{}\n",
+        page.number,
+        format.channels(),
+        page.has_alpha
+    )
+}
+
+/// A zip archive written a page at a time, handing out its bytes as it goes so they can be
+/// written to disk instead of held in memory. Only the names and offsets of the files are kept
+/// until the end. Entries stay in the order they are written, whatever `settings` say.
+pub struct ZipStream {
+    writer: ZipWriter<StreamWriter<SharedBuffer>>,
+    buffer: SharedBuffer,
+}
+
+/// What a [`ZipStream`] has written and not handed out yet.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Default for ZipStream {
+    fn default() -> Self {
+        let buffer = SharedBuffer::default();
+        Self {
+            writer: ZipWriter::new_stream(buffer.clone()),
+            buffer,
+        }
+    }
+}
+
+impl ZipStream {
+    /// Add the images of `page` in `format`, compressed as `settings` ask, and return the bytes
+    /// to append to the file. The manifest, checksums and other files about the whole document
+    /// are left out.
+    pub fn write_page(
+        &mut self,
+        page: &RenderedPage,
+        format: OutputFormat,
+        settings: &Settings,
+    ) -> Result<Vec<u8>, ConversionError> {
+        for (filename, _, bytes) in page_images(page, format) {
+            let options = file_options(&filename, settings);
+            self.writer.start_file(filename, options)?;
+            self.writer.write_all(bytes)?;
+        }
+        Ok(self.buffer.0.take())
+    }
+
+    /// Close the archive, returning the last bytes of the file.
+    pub fn finish(self) -> Result<Vec<u8>, ConversionError> {
+        self.writer.finish()?;
+        Ok(self.buffer.0.take())
+    }
+}
+
+/// Zip every file of a single document, see [`document_entries`].
+pub fn zip_document(
+    stem: &str,
+    pages: &[RenderedPage],
+    attachments: &[Attachment],
+    source: &[u8],
+    format: OutputFormat,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    zip_entries(
+        document_entries(stem, pages, attachments, source, format, settings),
+        settings,
+    )
+}
+
+/// Zip every file of a single document in each of `settings.formats`, see [`formats_entries`].
+pub fn zip_formats(
+    stem: &str,
+    pages: &[RenderedPage],
+    attachments: &[Attachment],
+    source: &[u8],
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    zip_entries(
+        formats_entries(stem, pages, attachments, source, settings),
+        settings,
+    )
+}
+
+/// Every file of a single document in each of `settings.formats`. With several formats each
+/// gets a folder named after its extension, so `png/` and `jpeg/` end up in the same archive.
+/// Web copies and attachments go into single `web/` and `attachments/` folders next to them,
+/// with the format folders inside `master/` if there are web copies.
+pub fn formats_entries<'a>(
+    stem: &str,
+    pages: &'a [RenderedPage],
+    attachments: &'a [Attachment],
+    source: &'a [u8],
+    settings: &Settings,
+) -> Vec<(String, Cow<'a, [u8]>)> {
+    let formats = &settings.formats;
+    let master = master_folder(pages);
+    formats
+        .iter()
+        .flat_map(|format| {
+            let folder = if formats.len() > 1 {
+                format!("{master}{}/", format.extension())
+            } else {
+                master.to_string()
+            };
+            format_entries(stem, pages, *format, settings)
+                .into_iter()
+                .map(move |(filename, bytes)| (format!("{folder}{filename}"), bytes))
+        })
+        .chain(extra_entries(stem, pages, attachments, source, settings))
+        .collect()
+}
+
+/// Zip the files of the document `stem` as `{stem}.zip`, or with `settings.split_size_mb` into
+/// `{stem}-part-1.zip` and so on once they are larger than that, as `(filename, bytes)`. A part
+/// is started whenever the next file would take the one before past the size, so a file larger
+/// than that on its own gets a part to itself.
+pub fn zip_parts<B: AsRef<[u8]>>(
+    stem: &str,
+    entries: impl IntoIterator<Item = (String, B)>,
+    settings: &Settings,
+) -> Result<Vec<(String, Vec<u8>)>, ConversionError> {
+    let mut entries: Vec<_> = entries.into_iter().collect();
+    let Some(limit) = settings.split_size_mb.map(|mb| mb as usize * 1024 * 1024) else {
+        return Ok(vec![(
+            format!("{stem}.zip"),
+            zip_entries(entries, settings)?,
+        )]);
+    };
+    // sort before splitting, so the parts follow on from each other
+    if settings.sorted_entries {
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    let mut parts: Vec<Vec<_>> = Vec::new();
+    // size of the files in the last part
+    let mut size = 0;
+    for (filename, bytes) in entries {
+        let length = bytes.as_ref().len();
+        match parts.last_mut() {
+            Some(part) if size + length <= limit => part.push((filename, bytes)),
+            _ => {
+                parts.push(vec![(filename, bytes)]);
+                size = 0;
+            }
+        }
+        size += length;
+    }
+    if parts.len() <= 1 {
+        let entries = parts.pop().unwrap_or_default();
+        return Ok(vec![(
+            format!("{stem}.zip"),
+            zip_entries(entries, settings)?,
+        )]);
+    }
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, entries)| {
+            let zip = zip_entries(entries, settings)?;
+            Ok((format!("{stem}-part-{}.zip", index + 1), zip))
+        })
+        .collect()
+}
+
+/// A listing in the format of `sha256sum`, so it can be verified with `sha256sum -c`.
+fn checksum_listing(pages: &[RenderedPage], format: OutputFormat) -> Option<String> {
+    let mut listing = String::new();
+    for page in pages {
+        let checksum = page.checksum(format)?;
+        if page.is_tiled() {
+            // tiles are only hashed when listed, most archives don't have any
+            for tile in page.tiles() {
+                let Some(bytes) = tile.encoded.get(&format) else {
+                    continue;
+                };
+                let filename = page.tile_filename(tile, format);
+                listing += &format!("{:x}  {filename}\n", Sha256::digest(bytes));
+            }
+        } else {
+            // only the main image was hashed during conversion
+            for (index, (filename, _, bytes)) in page.scaled_images(format).into_iter().enumerate()
+            {
+                if index == 0 {
+                    listing += &format!("{checksum}  {filename}\n");
+                } else {
+                    listing += &format!("{:x}  {filename}\n", Sha256::digest(bytes));
+                }
+            }
+        }
+    }
+    (!pages.is_empty()).then_some(listing)
+}
+
+/// Zip the pages of several documents interleaved by page number: page 1 of every document,
+/// then page 2 of every document, and so on. Documents that run out of pages, or pages not
+/// rendered in `format`, are skipped. With `settings.sorted_entries` the documents of every
+/// page are sorted by name, so the archive doesn't depend on the order they were given in,
+/// but the interleaving is kept.
+pub fn zip_interleaved(
+    documents: &[(&str, &[RenderedPage])],
+    format: OutputFormat,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut documents = documents.to_vec();
+    if settings.sorted_entries {
+        documents.sort_by_key(|(stem, _)| *stem);
+    }
+    let documents = documents.as_slice();
+    let longest = documents
+        .iter()
+        .map(|(_, pages)| pages.len())
+        .max()
+        .unwrap_or(0);
+    let mut entries: Vec<_> = (0..longest)
+        .flat_map(|index| {
+            documents.iter().filter_map(move |(_, pages)| {
+                let page = pages.get(index)?;
+                Some((page.filename(format), Cow::Borrowed(page.bytes(format)?)))
+            })
+        })
+        .collect();
+    if settings.index_page {
+        let index = index_html(documents)?;
+        entries.push(("index.html".to_string(), Cow::Owned(index.into_bytes())));
+    }
+    if settings.zip_preview {
+        let covers: Vec<_> = documents
+            .iter()
+            .filter_map(|(_, pages)| pages.first())
+            .collect();
+        entries.insert(0, preview_entry(&covers)?);
+    }
+    write_zip(entries, settings)
+}
+
+/// A `_preview.png` contact sheet of `covers`, put first in archives spanning several documents
+/// so file managers that preview the first image of an archive show what it holds.
+pub fn preview_entry<'a>(
+    covers: &[&RenderedPage],
+) -> Result<(String, Cow<'a, [u8]>), ConversionError> {
+    Ok((
+        "_preview.png".to_string(),
+        Cow::Owned(contact_sheet(covers)?),
+    ))
+}
+
+/// Every PDF inside the zip archive in `data`, as `(stem, bytes)`. Entries in nested folders
+/// get the folder names prefixed to their stem, everything that isn't a PDF is skipped. Fails
+/// without unpacking everything for archives of more than [`MAX_ZIP_ENTRIES`] entries, PDFs
+/// larger than [`MAX_FILE_SIZE`] or more than [`MAX_UNZIPPED_SIZE`] of PDFs in total, so a
+/// small zip can't fill all memory.
+pub fn unzip_pdfs(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, ConversionError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    if archive.len() > MAX_ZIP_ENTRIES {
+        return Err(ConversionError::ArchiveFailed(format!(
+            "{} entries, more than the limit of {MAX_ZIP_ENTRIES}",
+            archive.len()
+        )));
+    }
+    let mut pdfs = Vec::new();
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let Some(path) = file.enclosed_name() else {
+            continue;
+        };
+        let is_pdf = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"));
+        // macOS puts resource forks of every file into `__MACOSX/`, with the same names
+        let is_metadata = path
+            .components()
+            .any(|component| component == Component::Normal("__MACOSX".as_ref()));
+        if file.is_dir() || !is_pdf || is_metadata {
+            continue;
+        }
+        let stem = path
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("-");
+        let file_too_large = || {
+            ConversionError::ArchiveFailed(format!(
+                "{} unpacks to more than {}",
+                path.display(),
+                format_size(MAX_FILE_SIZE, humansize::BINARY)
+            ))
+        };
+        if file.size() > MAX_FILE_SIZE {
+            return Err(file_too_large());
+        }
+        // the sizes in the archive can lie, so what is read is limited too
+        let limit = MAX_FILE_SIZE.min(MAX_UNZIPPED_SIZE - total);
+        let mut bytes = Vec::new();
+        (&mut file).take(limit + 1).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > limit {
+            return Err(if limit == MAX_FILE_SIZE {
+                file_too_large()
+            } else {
+                ConversionError::ArchiveFailed(format!(
+                    "its PDFs unpack to more than {} in total",
+                    format_size(MAX_UNZIPPED_SIZE, humansize::BINARY)
+                ))
+            });
+        }
+        total += bytes.len() as u64;
+        pdfs.push((stem, bytes));
+    }
+    Ok(pdfs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert, testing};
+    use std::sync::Arc;
+
+    fn pages(stem: &str, count: usize) -> Vec<RenderedPage> {
+        let data = Arc::new(testing::blank_pdf(count));
+        convert(data, stem, &Settings::default()).unwrap().pages
+    }
+
+    /// Filenames of the entries of `zip`, in order.
+    fn names(zip: Vec<u8>) -> Vec<String> {
+        let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        (0..archive.len())
+            .map(|index| archive.by_index(index).unwrap().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn entries_are_sorted_when_asked() {
+        let entries =
+            [("b.png", b"b"), ("a.png", b"a")].map(|(name, bytes)| (name.to_string(), bytes));
+        let sorted = Settings {
+            sorted_entries: true,
+            ..Settings::default()
+        };
+        assert_eq!(
+            names(zip_entries(entries.clone(), &Settings::default()).unwrap()),
+            ["b.png", "a.png"]
+        );
+        assert_eq!(
+            names(zip_entries(entries, &sorted).unwrap()),
+            ["a.png", "b.png"]
+        );
+    }
+
+    #[test]
+    fn sorting_keeps_documents_interleaved() {
+        let (b, a) = (pages("b", 2), pages("a", 2));
+        let documents = [("b", b.as_slice()), ("a", a.as_slice())];
+        let zip =
+            |settings| names(zip_interleaved(&documents, OutputFormat::Png, &settings).unwrap());
+        assert_eq!(
+            zip(Settings::default()),
+            [
+                "b-page-001.png",
+                "a-page-001.png",
+                "b-page-002.png",
+                "a-page-002.png"
+            ]
+        );
+        let sorted = Settings {
+            sorted_entries: true,
+            ..Settings::default()
+        };
+        assert_eq!(
+            zip(sorted),
+            [
+                "a-page-001.png",
+                "b-page-001.png",
+                "a-page-002.png",
+                "b-page-002.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn zips_are_split_once_larger_than_the_limit() {
+        const KIB: usize = 1024;
+        let entries = [
+            ("1.png", 400),
+            ("2.png", 400),
+            ("3.png", 400),
+            ("4.png", 2048),
+        ]
+        .map(|(name, size)| (name.to_string(), vec![0; size * KIB]));
+        let settings = Settings {
+            split_size_mb: Some(1),
+            ..Settings::default()
+        };
+        let (filenames, zips): (Vec<_>, Vec<_>) = zip_parts("doc", entries, &settings)
+            .unwrap()
+            .into_iter()
+            .unzip();
+        assert_eq!(
+            filenames,
+            ["doc-part-1.zip", "doc-part-2.zip", "doc-part-3.zip"]
+        );
+        let contents: Vec<_> = zips.into_iter().map(names).collect();
+        assert_eq!(
+            contents,
+            [vec!["1.png", "2.png"], vec!["3.png"], vec!["4.png"]]
+        );
+    }
+
+    #[test]
+    fn zips_under_the_limit_are_not_split() {
+        let entries = [("1.png".to_string(), vec![0; 1024])];
+        let settings = Settings {
+            split_size_mb: Some(1),
+            ..Settings::default()
+        };
+        let parts = zip_parts("doc", entries, &settings).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].0, "doc.zip");
+    }
+
+    fn json_manifest(entries: &[(String, Cow<[u8]>)]) -> serde_json::Value {
+        let (_, json) = entries
+            .iter()
+            .find(|(filename, _)| filename == "manifest.json")
+            .unwrap();
+        serde_json::from_slice(json).unwrap()
+    }
+
+    #[test]
+    fn json_manifest_lists_every_page() {
+        let pages = pages("doc", 2);
+        let settings = Settings {
+            json_manifest: true,
+            ..Settings::default()
+        };
+        let entries = document_entries("doc", &pages, &[], &[], OutputFormat::Png, &settings);
+        let manifest = json_manifest(&entries);
+        assert_eq!(manifest["stem"], "doc");
+        assert_eq!(manifest["image_count"], 2);
+        let page = &manifest["pages"][1];
+        assert_eq!(page["index"], 2);
+        assert_eq!(page["page"], 2);
+        assert_eq!(page["file"], "doc-page-002.png");
+        assert!(page["width"].as_u64().unwrap() > 0);
+        assert!(page["height"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn json_manifest_counts_every_image() {
+        let settings = Settings {
+            json_manifest: true,
+            scales: "1,2".to_string(),
+            ..Settings::default()
+        };
+        let data = Arc::new(testing::blank_pdf(2));
+        let pages = convert(data, "doc", &settings).unwrap().pages;
+        let entries = document_entries("doc", &pages, &[], &[], OutputFormat::Png, &settings);
+        let manifest = json_manifest(&entries);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(manifest["image_count"], 4);
+        assert_eq!(manifest["pages"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn json_manifest_is_left_out_by_default() {
+        let pages = pages("doc", 1);
+        let entries = document_entries(
+            "doc",
+            &pages,
+            &[],
+            &[],
+            OutputFormat::Png,
+            &Settings::default(),
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|(filename, _)| filename != "manifest.json")
+        );
+    }
+}
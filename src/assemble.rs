@@ -0,0 +1,158 @@
+use crate::convert::encode_with_quality;
+use crate::pdfa::{POINTS_PER_INCH, Writer, deflate};
+use crate::{ConversionError, OutputFormat, PngCompression, RenderedPage, Settings};
+use image::codecs::jpeg::JpegDecoder;
+use image::{GenericImageView, ImageDecoder};
+use std::borrow::Cow;
+use std::io::Cursor;
+
+/// A PDF with each of `images`, PNGs, JPEGs or anything else `image` reads, on a page of its
+/// own in order. Every page is exactly as large as its image, one point per pixel, and the
+/// images are embedded losslessly, keeping transparency.
+pub fn assemble_pdf<B: AsRef<[u8]>>(images: &[B]) -> Result<Vec<u8>, ConversionError> {
+    let mut pdf = Writer::default();
+    // both are written last, once the pages are known
+    let catalog = pdf.reserve();
+    let tree = pdf.reserve();
+
+    let mut kids = Vec::new();
+    for bytes in images {
+        let image = image::load_from_memory(bytes.as_ref())?;
+        let (width, height) = image.dimensions();
+        let rgb = deflate(image.to_rgb8().as_raw())?;
+        let mask = if image.color().has_alpha() {
+            let alpha: Vec<u8> = image.to_rgba8().pixels().map(|pixel| pixel[3]).collect();
+            let alpha = deflate(&alpha)?;
+            let id = pdf.add(
+                format!(
+                    "<< /Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                     /ColorSpace /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>",
+                    alpha.len()
+                ),
+                Some(&alpha),
+            );
+            format!(" /SMask {id} 0 R")
+        } else {
+            String::new()
+        };
+        let image_id = pdf.add(
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode{mask} /Length {} >>",
+                rgb.len()
+            ),
+            Some(&rgb),
+        );
+        let content = format!("q {width} 0 0 {height} 0 0 cm /Im0 Do Q");
+        let content_id = pdf.add(
+            format!("<< /Length {} >>", content.len()),
+            Some(content.as_bytes()),
+        );
+        kids.push(pdf.add(
+            format!(
+                "<< /Type /Page /Parent {tree} 0 R /MediaBox [0 0 {width} {height}] \
+                 /Resources << /XObject << /Im0 {image_id} 0 R >> >> /Contents {content_id} 0 R >>"
+            ),
+            None,
+        ));
+    }
+
+    let kids: Vec<_> = kids.iter().map(|id| format!("{id} 0 R")).collect();
+    pdf.set(
+        tree,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+        None,
+    );
+    pdf.set(
+        catalog,
+        format!("<< /Type /Catalog /Pages {tree} 0 R >>"),
+        None,
+    );
+    Ok(pdf.finish(catalog))
+}
+
+/// A PDF of `pages` as JPEGs, one per page at its physical size, for a flattened copy of a
+/// document without its text, forms or scripts. The JPEGs the pages were rendered as are
+/// embedded as they are, pages rendered in other formats are encoded at
+/// `settings.jpeg_quality`.
+pub fn rasterized_pdf(
+    pages: &[RenderedPage],
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut pdf = Writer::default();
+    // both are written last, once the pages are known
+    let catalog = pdf.reserve();
+    let tree = pdf.reserve();
+
+    let mut kids = Vec::new();
+    for page in pages {
+        let jpeg = match page.bytes(OutputFormat::Jpeg) {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None => {
+                let Some((format, bytes)) = page.first_encoded() else {
+                    continue;
+                };
+                let image = image::load_from_memory_with_format(bytes, format.image_format())?;
+                Cow::Owned(encode_with_quality(
+                    &image,
+                    OutputFormat::Jpeg,
+                    None,
+                    None,
+                    PngCompression::default(),
+                    None,
+                    settings.jpeg_quality.clamp(1, 100),
+                )?)
+            }
+        };
+        let decoder = JpegDecoder::new(Cursor::new(jpeg.as_ref()))?;
+        let (width, height) = decoder.dimensions();
+        let color_space = if decoder.color_type().has_color() {
+            "DeviceRGB"
+        } else {
+            "DeviceGray"
+        };
+        let image_id = pdf.add(
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                 /ColorSpace /{color_space} /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>",
+                jpeg.len()
+            ),
+            Some(&jpeg),
+        );
+        let points = |pixels: u32| pixels as f32 * POINTS_PER_INCH / page.dpi;
+        let (width, height) = (points(width), points(height));
+        let content = format!("q {width:.2} 0 0 {height:.2} 0 0 cm /Im0 Do Q");
+        let content_id = pdf.add(
+            format!("<< /Length {} >>", content.len()),
+            Some(content.as_bytes()),
+        );
+        kids.push(pdf.add(
+            format!(
+                "<< /Type /Page /Parent {tree} 0 R /MediaBox [0 0 {width:.2} {height:.2}] \
+                 /Resources << /XObject << /Im0 {image_id} 0 R >> >> /Contents {content_id} 0 R >>"
+            ),
+            None,
+        ));
+    }
+
+    let kids: Vec<_> = kids.iter().map(|id| format!("{id} 0 R")).collect();
+    pdf.set(
+        tree,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+        None,
+    );
+    pdf.set(
+        catalog,
+        format!("<< /Type /Catalog /Pages {tree} 0 R >>"),
+        None,
+    );
+    Ok(pdf.finish(catalog))
+}
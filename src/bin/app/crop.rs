@@ -0,0 +1,118 @@
+use crate::{App, Msg, object_url, screen_pixels};
+use pdf_to_whatever::{ConversionError, Crop, OutputFormat};
+use std::sync::Arc;
+use web_sys::{Element, MouseEvent, Url};
+use yew::html::TargetCast;
+use yew::{Context, Html, html};
+
+/// Widest page 1 is shown to pick from, in CSS pixels.
+const MAX_WIDTH: f64 = 600.0;
+
+/// What the pointer does over page 1, in fractions of its width and height.
+pub enum Drag {
+    Start(f32, f32),
+    Move(f32, f32),
+    End,
+}
+
+/// Page 1 of a document to drag the region to crop every page to over.
+pub struct CropPicker {
+    pub stem: String,
+    url: String,
+    /// Where the drag started and what it selects so far, while the button is held.
+    drag: Option<((f32, f32), Option<Crop>)>,
+}
+
+impl CropPicker {
+    /// Render page 1 of the document in `data`.
+    pub fn new(stem: String, data: Arc<Vec<u8>>) -> Result<Self, ConversionError> {
+        let bytes = pdf_to_whatever::render_preview(data, 1, screen_pixels(MAX_WIDTH))?;
+        Ok(Self {
+            stem,
+            url: object_url(&bytes, OutputFormat::Png.mime_type()),
+            drag: None,
+        })
+    }
+
+    /// Follow the pointer, returning the selection once the drag ends. A click without
+    /// dragging selects the whole page.
+    pub fn drag(&mut self, drag: Drag) -> Option<Option<Crop>> {
+        match drag {
+            Drag::Start(x, y) => {
+                self.drag = Some(((x, y), None));
+                None
+            }
+            Drag::Move(x, y) => {
+                let (start, selection) = self.drag.as_mut()?;
+                *selection = Crop::between(*start, (x, y));
+                None
+            }
+            Drag::End => self.drag.take().map(|(_, selection)| selection),
+        }
+    }
+
+    /// The picker, showing the region being dragged or else the `current` one.
+    pub fn view(&self, ctx: &Context<App>, current: Option<Crop>) -> Html {
+        let selection = match self.drag {
+            Some((_, selection)) => selection,
+            None => current,
+        };
+        let selection = selection.map(|crop| {
+            format!(
+                "left: {}%; top: {}%; width: {}%; height: {}%",
+                crop.x * 100.0,
+                crop.y * 100.0,
+                crop.width * 100.0,
+                crop.height * 100.0
+            )
+        });
+        html! {
+            <section id="crop" aria-label={format!("Crop region for {}", self.stem)}>
+                <div class="controls">
+                    <span>{"Drag over the page to crop every page to that region, then convert again"}</span>
+                    <button onclick={ctx.link().callback(|_| Msg::CropTo(None))}>
+                        {"Whole page"}
+                    </button>
+                    <button onclick={ctx.link().callback(|_| Msg::CloseCrop)}>{"Close"}</button>
+                </div>
+                <div class="page">
+                    <img
+                        src={self.url.clone()}
+                        alt={format!("{}, page 1", self.stem)}
+                        draggable="false"
+                        onmousedown={ctx.link().callback(|e: MouseEvent| {
+                            e.prevent_default();
+                            let (x, y) = position(&e);
+                            Msg::CropDrag(Drag::Start(x, y))
+                        })}
+                        onmousemove={ctx.link().callback(|e: MouseEvent| {
+                            let (x, y) = position(&e);
+                            Msg::CropDrag(Drag::Move(x, y))
+                        })}
+                        onmouseup={ctx.link().callback(|_| Msg::CropDrag(Drag::End))}
+                        onmouseleave={ctx.link().callback(|_| Msg::CropDrag(Drag::End))}
+                    />
+                    if let Some(style) = selection {
+                        <div class="selection" {style} />
+                    }
+                </div>
+            </section>
+        }
+    }
+}
+
+impl Drop for CropPicker {
+    fn drop(&mut self) {
+        let _ = Url::revoke_object_url(&self.url);
+    }
+}
+
+/// Where `e` is over the page it targets, in fractions of its size.
+fn position(e: &MouseEvent) -> (f32, f32) {
+    let page: Element = e.target_unchecked_into();
+    let (width, height) = (page.client_width().max(1), page.client_height().max(1));
+    (
+        e.offset_x() as f32 / width as f32,
+        e.offset_y() as f32 / height as f32,
+    )
+}
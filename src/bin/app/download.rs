@@ -0,0 +1,139 @@
+use gloo::file::Blob;
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetDirectoryOptions,
+    FileSystemGetFileOptions, FileSystemWritableFileStream, HtmlAnchorElement, Url,
+};
+
+/// Offer `bytes` to the user as a file download named `filename`.
+pub fn save(bytes: &[u8], filename: &str) {
+    let blob = Blob::new(bytes);
+    let url = Url::create_object_url_with_blob(&blob.into()).expect("failed creating url");
+    let anchor: HtmlAnchorElement = gloo::utils::document()
+        .create_element("a")
+        .expect("failed creating anchor")
+        .unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    // the download has started once `click` returns, only then is it safe to free the blob
+    Url::revoke_object_url(&url).expect("failed revoking url");
+}
+
+/// Whether the browser can write into a user-chosen folder through the File System Access API.
+/// Only Chromium-based browsers support it at the moment.
+pub fn folder_access_supported() -> bool {
+    Reflect::has(&gloo::utils::window(), &"showDirectoryPicker".into()).unwrap_or(false)
+}
+
+/// Whether the browser can write into a user-chosen file through the File System Access API.
+pub fn save_file_supported() -> bool {
+    Reflect::has(&gloo::utils::window(), &"showSaveFilePicker".into()).unwrap_or(false)
+}
+
+/// Ask the user where to save a zip named `filename`. Browsers only show the picker in
+/// response to a click or key press, so this has to be called while handling one, and the
+/// returned promise awaited with [`open_file`] later.
+pub fn pick_zip_file(filename: &str) -> Result<Promise, JsValue> {
+    let window = gloo::utils::window();
+    // `showSaveFilePicker` is only exposed by web-sys behind `web_sys_unstable_apis`
+    let picker: Function = Reflect::get(&window, &"showSaveFilePicker".into())?.dyn_into()?;
+    let options = Object::new();
+    Reflect::set(&options, &"suggestedName".into(), &filename.into())?;
+    let zip_type = Object::new();
+    let accept = Object::new();
+    Reflect::set(
+        &accept,
+        &"application/zip".into(),
+        &Array::of1(&".zip".into()),
+    )?;
+    Reflect::set(&zip_type, &"accept".into(), &accept)?;
+    Reflect::set(&options, &"types".into(), &Array::of1(&zip_type))?;
+    picker.call1(&window, &options)?.dyn_into()
+}
+
+/// The name and an emptied stream for writing of the file picked with [`pick_zip_file`].
+pub async fn open_file(picked: Promise) -> Result<(String, FileSystemWritableFileStream), JsValue> {
+    let file: FileSystemFileHandle = JsFuture::from(picked).await?.dyn_into()?;
+    let writable = JsFuture::from(file.create_writable()).await?.dyn_into()?;
+    Ok((file.name(), writable))
+}
+
+/// Append `bytes` to a file opened with [`open_file`].
+pub async fn write_to_file(
+    file: &FileSystemWritableFileStream,
+    bytes: &[u8],
+) -> Result<(), JsValue> {
+    JsFuture::from(file.write_with_u8_array(bytes)?).await?;
+    Ok(())
+}
+
+/// Ask the user for a folder and write every `(filename, bytes)` entry into it. Filenames
+/// containing `/` are written into subfolders, which are created as needed.
+pub async fn save_to_folder(entries: Vec<(String, Vec<u8>)>) -> Result<(), JsValue> {
+    let window = gloo::utils::window();
+    // `showDirectoryPicker` is only exposed by web-sys behind `web_sys_unstable_apis`
+    let picker: Function = Reflect::get(&window, &"showDirectoryPicker".into())?.dyn_into()?;
+    let picked: Promise = picker.call0(&window)?.dyn_into()?;
+    let directory: FileSystemDirectoryHandle = JsFuture::from(picked).await?.dyn_into()?;
+
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(true);
+    let folder_options = FileSystemGetDirectoryOptions::new();
+    folder_options.set_create(true);
+    for (filename, bytes) in entries {
+        let mut folder = directory.clone();
+        let mut parts: Vec<_> = filename.split('/').collect();
+        let name = parts.pop().unwrap_or_default();
+        for part in parts {
+            folder =
+                JsFuture::from(folder.get_directory_handle_with_options(part, &folder_options))
+                    .await?
+                    .dyn_into()?;
+        }
+        let file: FileSystemFileHandle =
+            JsFuture::from(folder.get_file_handle_with_options(name, &options))
+                .await?
+                .dyn_into()?;
+        let writable: FileSystemWritableFileStream =
+            JsFuture::from(file.create_writable()).await?.dyn_into()?;
+        JsFuture::from(writable.write_with_u8_array(&bytes)?).await?;
+        JsFuture::from(writable.close()).await?;
+    }
+    Ok(())
+}
+
+/// Put `text` on the clipboard.
+pub async fn copy_text(text: &str) -> Result<(), JsValue> {
+    let navigator = Reflect::get(&gloo::utils::window(), &"navigator".into())?;
+    // `navigator.clipboard` is only exposed by web-sys behind `web_sys_unstable_apis`
+    let clipboard = Reflect::get(&navigator, &"clipboard".into())?;
+    let write_text: Function = Reflect::get(&clipboard, &"writeText".into())?.dyn_into()?;
+    let written: Promise = write_text.call1(&clipboard, &text.into())?.dyn_into()?;
+    JsFuture::from(written).await?;
+    Ok(())
+}
+
+/// Put one PNG image per entry of `images` on the clipboard. Most browsers only accept a
+/// single image at a time and reject the write otherwise.
+pub async fn copy_images(images: &[Vec<u8>]) -> Result<(), JsValue> {
+    let window = gloo::utils::window();
+    // `ClipboardItem` and `navigator.clipboard` are only exposed by web-sys behind
+    // `web_sys_unstable_apis`
+    let item_class: Function = Reflect::get(&window, &"ClipboardItem".into())?.dyn_into()?;
+    let items = Array::new();
+    for image in images {
+        let blob = Blob::new_with_options(image.as_slice(), Some("image/png"));
+        let data = Object::new();
+        Reflect::set(&data, &"image/png".into(), &web_sys::Blob::from(blob))?;
+        items.push(&Reflect::construct(&item_class, &Array::of1(&data))?);
+    }
+    let navigator = Reflect::get(&window, &"navigator".into())?;
+    let clipboard = Reflect::get(&navigator, &"clipboard".into())?;
+    let write: Function = Reflect::get(&clipboard, &"write".into())?.dyn_into()?;
+    let written: Promise = write.call1(&clipboard, &items)?.dyn_into()?;
+    JsFuture::from(written).await?;
+    Ok(())
+}
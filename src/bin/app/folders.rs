@@ -0,0 +1,79 @@
+use js_sys::{Array, Promise};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    DataTransfer, File, FileList, FilePropertyBag, FileSystemDirectoryEntry,
+    FileSystemDirectoryReader, FileSystemEntry, FileSystemFileEntry,
+};
+
+/// The dropped entries, if any of them is a folder. Without folders `DataTransfer::files`
+/// already has every file. Entries can only be taken while handling the drop event.
+pub fn dropped_folders(transfer: &DataTransfer) -> Option<Vec<FileSystemEntry>> {
+    let items = transfer.items();
+    let entries: Vec<_> = (0..items.length())
+        .filter_map(|index| items.get(index)?.webkit_get_as_entry().ok()?)
+        .collect();
+    entries
+        .iter()
+        .any(FileSystemEntry::is_directory)
+        .then_some(entries)
+}
+
+/// Every PDF and zip in `entries` and, recursively, the folders among them. With
+/// `folder_names`, files found in folders are named after their path, with its parts joined by
+/// `-`, so files of the same name in different folders don't clash.
+pub async fn collect(
+    entries: Vec<FileSystemEntry>,
+    folder_names: bool,
+) -> Result<FileList, JsValue> {
+    let transfer = DataTransfer::new()?;
+    let mut pending = entries;
+    while let Some(entry) = pending.pop() {
+        if entry.is_directory() {
+            let reader = entry
+                .unchecked_into::<FileSystemDirectoryEntry>()
+                .create_reader();
+            // `readEntries` hands out a directory in batches, until an empty one
+            loop {
+                let batch: Array = JsFuture::from(read_entries(&reader)).await?.dyn_into()?;
+                if batch.length() == 0 {
+                    break;
+                }
+                pending.extend(batch.iter().map(JsValue::unchecked_into));
+            }
+            continue;
+        }
+        let name = entry.name().to_lowercase();
+        if !entry.is_file() || !(name.ends_with(".pdf") || name.ends_with(".zip")) {
+            continue;
+        }
+        let file: File = JsFuture::from(file(entry.unchecked_ref()))
+            .await?
+            .dyn_into()?;
+        let name = if folder_names {
+            entry.full_path().trim_start_matches('/').replace('/', "-")
+        } else {
+            file.name()
+        };
+        let options = FilePropertyBag::new();
+        options.set_type(&file.type_());
+        let renamed =
+            File::new_with_blob_sequence_and_options(&Array::of1(&file), &name, &options)?;
+        transfer.items().add_with_file(&renamed)?;
+    }
+    transfer
+        .files()
+        .ok_or_else(|| JsValue::from_str("no file list to collect into"))
+}
+
+fn read_entries(reader: &FileSystemDirectoryReader) -> Promise {
+    Promise::new(&mut |resolve, reject| {
+        if let Err(err) = reader.read_entries_with_callback_and_callback(&resolve, &reject) {
+            let _ = reject.call1(&JsValue::NULL, &err);
+        }
+    })
+}
+
+fn file(entry: &FileSystemFileEntry) -> Promise {
+    Promise::new(&mut |resolve, reject| entry.file_with_callback_and_callback(&resolve, &reject))
+}
@@ -0,0 +1,74 @@
+use crate::{App, Msg};
+use gloo::file::callbacks::FileReader;
+use yew::{Context, Html, html};
+
+/// An image dropped to be put into a PDF, the other way round from every other file.
+pub struct DroppedImage {
+    pub id: u64,
+    pub name: String,
+    /// `None` until it has been read.
+    pub data: Option<Vec<u8>>,
+    /// Reading the image, cancelled when it is dropped.
+    pub reader: Option<FileReader>,
+}
+
+/// Whether files of `mime_type` are images to put into a PDF.
+pub fn is_image(mime_type: &str) -> bool {
+    matches!(mime_type, "image/png" | "image/jpeg")
+}
+
+/// The images to combine into a PDF in the order they will be in, to reorder and download.
+pub fn view_images(ctx: &Context<App>, images: &[DroppedImage]) -> Html {
+    if images.is_empty() {
+        return html! {};
+    }
+    let last = images.len() - 1;
+    html! {
+        <section id="images" aria-label="Images to combine into a PDF">
+            <h2>{"Images to PDF"}</h2>
+            <p class="note">{"Images are combined into one PDF, a page each, instead of being converted."}</p>
+            <ol>
+                { for images.iter().enumerate().map(|(index, image)| {
+                    let id = image.id;
+                    html! {
+                        <li>
+                            <span>{&image.name}</span>
+                            if image.data.is_none() {
+                                <span class="note">{"reading…"}</span>
+                            }
+                            <button
+                                disabled={index == 0}
+                                aria-label={format!("Move {} up", image.name)}
+                                onclick={ctx.link().callback(move |_| Msg::MoveImage(id, true))}
+                            >
+                                {"Up"}
+                            </button>
+                            <button
+                                disabled={index == last}
+                                aria-label={format!("Move {} down", image.name)}
+                                onclick={ctx.link().callback(move |_| Msg::MoveImage(id, false))}
+                            >
+                                {"Down"}
+                            </button>
+                            <button
+                                aria-label={format!("Remove {}", image.name)}
+                                onclick={ctx.link().callback(move |_| Msg::RemoveImage(id))}
+                            >
+                                {"Remove"}
+                            </button>
+                        </li>
+                    }
+                }) }
+            </ol>
+            <div class="controls">
+                <button
+                    disabled={images.iter().any(|image| image.data.is_none())}
+                    onclick={ctx.link().callback(|_| Msg::DownloadImagesPdf)}
+                >
+                    {"Download as PDF"}
+                </button>
+                <button onclick={ctx.link().callback(|_| Msg::ClearImages)}>{"Clear images"}</button>
+            </div>
+        </section>
+    }
+}
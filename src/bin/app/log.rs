@@ -0,0 +1,70 @@
+use gloo::console;
+use std::collections::VecDeque;
+use yew::{Html, html};
+
+/// Oldest entries are dropped beyond this, so a long session doesn't grow without bound.
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+struct Entry {
+    time: String,
+    level: Level,
+    message: String,
+}
+
+/// Key events of the session, mirrored to the browser console and shown in the log panel.
+#[derive(Default)]
+pub struct Log {
+    entries: VecDeque<Entry>,
+}
+
+impl Log {
+    pub fn push(&mut self, level: Level, message: String) {
+        match level {
+            Level::Info => console::log!(&message),
+            Level::Error => console::error!(&message),
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        let now = js_sys::Date::new_0();
+        self.entries.push_back(Entry {
+            time: format!(
+                "{:02}:{:02}:{:02}",
+                now.get_hours(),
+                now.get_minutes(),
+                now.get_seconds()
+            ),
+            level,
+            message,
+        });
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message.into());
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message.into());
+    }
+
+    pub fn view(&self) -> Html {
+        html! {
+            <details id="log">
+                <summary>{format!("Log ({})", self.entries.len())}</summary>
+                <ol>
+                    { for self.entries.iter().rev().map(|entry| html! {
+                        <li class={(entry.level == Level::Error).then_some("error")}>
+                            <time>{&entry.time}</time>{" "}{&entry.message}
+                        </li>
+                    }) }
+                </ol>
+            </details>
+        }
+    }
+}
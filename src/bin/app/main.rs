@@ -0,0 +1,3315 @@
+use gloo::events::EventListener;
+use gloo::file::callbacks::FileReader;
+use gloo::file::{Blob, FileList};
+use gloo::timers::callback::Timeout;
+use gloo::worker::Spawnable;
+use humansize::format_size;
+use js_sys::{Array, Function, Promise};
+use pdf_to_whatever::{
+    Attachment, Conversion, ConversionError, ConvertWorker, Converted, Crop, DocumentKind,
+    Duplicates, Layer, OutputFormat, PngCompression, RenderedPage, SearchResult, SessionDocument,
+    Settings, SubstituteFont, WorkerRequest, WorkerUpdate, ZipStream,
+};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    ClipboardEvent, DragEvent, Element, Event, EventTarget, FileSystemWritableFileStream,
+    HtmlElement, HtmlInputElement, KeyboardEvent, MouseEvent, Node, Url,
+};
+use web_time::Instant;
+use yew::html::{Scope, TargetCast};
+use yew::{Callback, Component, Context, Html, NodeRef, html};
+
+mod crop;
+mod download;
+mod folders;
+mod images;
+mod log;
+mod memory;
+mod notify;
+mod overrides;
+mod settings;
+mod summary;
+mod viewer;
+
+const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const CARGO_PKG_DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
+/// How long "Clear all" can be undone.
+const UNDO_CLEAR_MILLIS: u32 = 8000;
+/// Data URIs longer than this get a confirmation prompt before being copied.
+const LARGE_DATA_URI: usize = 1024 * 1024;
+/// HTML exports larger than this get a confirmation prompt before being downloaded.
+const LARGE_HTML: usize = 20 * 1024 * 1024;
+/// How long page 1 stays available to a tab it was opened in. The tab has long loaded it by
+/// then, and keeps showing it after its URL is revoked.
+const NEW_TAB_MILLIS: u32 = 60_000;
+/// How many of the most recent pages of a document its time remaining is estimated from.
+const RATE_SAMPLES: usize = 5;
+/// How often a paused conversion checks whether it was resumed.
+const PAUSE_POLL_MILLIS: i32 = 250;
+/// The script of [`ConvertWorker`], next to the page.
+const WORKER_PATH: &str = "worker.js";
+/// Longest side of the page thumbnails, in pixels. Small enough for hundreds of them on a page.
+const THUMBNAIL_SIZE: u32 = 160;
+
+pub struct RenderedImage {
+    stem: String,
+    pdf_human_size: String,
+    data: Arc<Vec<u8>>,
+    pages: Vec<RenderedPage>,
+    page_count: usize,
+    render_secs: f32,
+    uses_cmyk: bool,
+    kind: DocumentKind,
+    has_form_values: bool,
+    /// Pages left out for being blank.
+    blank_pages: Vec<usize>,
+    layers: Vec<Layer>,
+    attachments: Vec<Attachment>,
+    search: Option<SearchResult>,
+    /// How many pages were selected, when only the first of them were rendered.
+    truncated_from: Option<usize>,
+    /// PNG compression the last pages fell back to for taking too long.
+    compression_fallback: Option<PngCompression>,
+    /// Standard fonts the substitute font was drawn in place of, for fonts the document doesn't
+    /// embed.
+    substituted_fonts: Vec<String>,
+    /// Converted with "Preview first page only".
+    first_page_only: bool,
+    /// Object URL of page 1, shown inline while only the first page has been rendered.
+    preview_url: Option<String>,
+    /// The first page as a PNG rendered for the screen's pixel ratio, made the first time a preview
+    /// needs it.
+    sharp_preview: Option<Vec<u8>>,
+    /// A data URI of a small PNG of every page, made the first time the thumbnails are shown.
+    thumbnails: Vec<String>,
+    /// Name of the file the pages were written to instead of being kept, and how many there
+    /// were, with "Write a single document straight to disk".
+    saved_to: Option<(String, usize)>,
+}
+
+impl RenderedImage {
+    fn new(
+        stem: String,
+        pdf_human_size: String,
+        data: Arc<Vec<u8>>,
+        converted: Converted,
+        render_secs: f32,
+        first_page_only: bool,
+    ) -> Self {
+        Self {
+            stem,
+            pdf_human_size,
+            data,
+            pages: converted.pages,
+            page_count: converted.page_count,
+            render_secs,
+            uses_cmyk: converted.uses_cmyk,
+            kind: converted.kind,
+            has_form_values: converted.has_form_values,
+            blank_pages: converted.blank_pages,
+            layers: converted.layers,
+            attachments: converted.attachments,
+            search: converted.search,
+            truncated_from: converted.truncated_from,
+            compression_fallback: converted.compression_fallback,
+            substituted_fonts: converted.substituted_fonts,
+            first_page_only,
+            preview_url: None,
+            sharp_preview: None,
+            thumbnails: Vec::new(),
+            saved_to: None,
+        }
+    }
+
+    /// How many pages have been rendered, fewer than `page_count` while only page 1 is shown
+    /// or when only some pages were selected.
+    fn rendered_pages(&self) -> usize {
+        let saved = self.saved_to.as_ref().map_or(0, |(_, pages)| *pages);
+        saved + self.pages.iter().map(page_span).sum::<usize>()
+    }
+
+    /// Total size of the page images of every rendered format, in the order of
+    /// [`OutputFormat::ALL`].
+    fn format_sizes(&self) -> Vec<(OutputFormat, usize)> {
+        OutputFormat::ALL
+            .into_iter()
+            .filter_map(|format| {
+                let sizes = self
+                    .pages
+                    .iter()
+                    .map(|page| page.bytes(format).map(<[u8]>::len));
+                Some((format, sizes.sum::<Option<usize>>()?))
+            })
+            .collect()
+    }
+
+    /// Only page 1 was rendered to preview the settings, not the pages that were selected.
+    fn is_partial(&self) -> bool {
+        self.first_page_only && self.page_count > 1
+    }
+
+    /// The first page as shown in previews, with its MIME type. With `sharp` it is rendered for the
+    /// screen's pixel ratio on high-DPI screens, otherwise it is the downloadable image.
+    fn preview_image(&mut self, sharp: bool) -> Option<(&'static str, &[u8])> {
+        if sharp
+            && self.sharp_preview.is_none()
+            && let Some(first) = self.pages.first()
+            && let Some(width) = sharp_preview_width()
+        {
+            self.sharp_preview =
+                pdf_to_whatever::render_preview(self.data.clone(), first.number, width).ok();
+        }
+        match &self.sharp_preview {
+            Some(bytes) if sharp => Some((OutputFormat::Png.mime_type(), bytes)),
+            _ => self
+                .pages
+                .first()
+                .and_then(RenderedPage::first_encoded)
+                .map(|(format, bytes)| (format.mime_type(), bytes)),
+        }
+    }
+
+    /// The only file of `entries` when the document has a single page and `settings` don't ask
+    /// for zips regardless, to download as is. Web copies or extra files make it a zip anyway.
+    fn loose_entry<'a>(
+        &self,
+        entries: &'a [(String, Cow<'_, [u8]>)],
+        settings: &Settings,
+    ) -> Option<(&'a str, &'a [u8])> {
+        match entries {
+            [(filename, bytes)] if self.page_count == 1 && !settings.always_zip => {
+                Some((filename, bytes))
+            }
+            _ => None,
+        }
+    }
+
+    /// How many pages were rendered and how large the first one is, e.g. "12 pages · 1654×2339 px",
+    /// to check the resolution against the settings.
+    fn dimensions(&self) -> String {
+        let count = self.rendered_pages();
+        let pages = format!("{count} {}", if count == 1 { "page" } else { "pages" });
+        match self.pages.first().map(|page| page.dimensions) {
+            Some((width, height)) => format!("{pages} · {width}×{height} px"),
+            None => pages,
+        }
+    }
+
+    /// How screen readers announce the file, e.g. "report, 12 pages".
+    fn description(&self) -> String {
+        let count = self.rendered_pages();
+        format!(
+            "{}, {count} {}",
+            self.stem,
+            if count == 1 { "page" } else { "pages" }
+        )
+    }
+}
+
+/// A file whose conversion failed. The source bytes are kept around so it can be retried.
+pub struct FailedFile {
+    stem: String,
+    pdf_human_size: String,
+    data: Arc<Vec<u8>>,
+    error: ConversionError,
+}
+
+/// An enlarged page 1 floating next to the mouse.
+pub struct Preview {
+    url: String,
+    x: i32,
+    y: i32,
+}
+
+/// The files being dragged over the drop zone, counted before they are dropped.
+pub struct DragSummary {
+    files: usize,
+    pdfs: usize,
+}
+
+impl DragSummary {
+    fn of(e: &DragEvent) -> Self {
+        let mut summary = Self { files: 0, pdfs: 0 };
+        let Some(items) = e.data_transfer().map(|transfer| transfer.items()) else {
+            return summary;
+        };
+        // only the kind and type of items can be read until they are dropped, so files that
+        // may be PDFs by their type count as PDFs, like they do when dropped
+        for item in (0..items.length()).filter_map(|index| items.get(index)) {
+            if item.kind() == "file" {
+                summary.files += 1;
+                if may_be_pdf_type(&item.type_()) {
+                    summary.pdfs += 1;
+                }
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for DragSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = if self.files == 1 { "file" } else { "files" };
+        write!(f, "{} {files} ({} PDF)", self.files, self.pdfs)
+    }
+}
+
+/// Results removed by "Clear all", kept around for a while so it can be undone.
+struct Cleared {
+    files: Vec<RenderedImage>,
+    failed: Vec<FailedFile>,
+    /// Forgets the results once it fires.
+    _timeout: Timeout,
+}
+
+/// A file that is still being read or converted.
+struct Task {
+    /// Reads the file, or `None` once its bytes are at hand, like when converting again.
+    _reader: Option<FileReader>,
+    /// Size of the file in bytes, to recognize it when it is dropped again.
+    size: u64,
+    /// Set when the task is cancelled, so its result is thrown away instead of delivered.
+    cancelled: Rc<Cell<bool>>,
+}
+
+/// Add `task` to `tasks` under `key`, cancelling the task it replaces, so the result of a file
+/// dropped again under the same name can't arrive after that of the newer one.
+fn track(tasks: &mut HashMap<String, Task>, key: String, task: Task) {
+    if let Some(previous) = tasks.insert(key, task) {
+        previous.cancelled.set(true);
+    }
+}
+
+/// `stem`, or the first of `stem-2`, `stem-3` and so on that isn't `taken`.
+fn first_free(stem: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(stem) {
+        return stem.to_string();
+    }
+    (2..)
+        .map(|number| format!("{stem}-{number}"))
+        .find(|candidate| !taken(candidate))
+        .expect("some suffix is free")
+}
+
+/// Where a dropped PDF goes, see [`placement`].
+#[derive(Debug, PartialEq, Eq)]
+enum Placement {
+    Skip,
+    /// In place of the earlier file of its name.
+    Replace,
+    /// Under its name, or a free one when that is taken, see [`App::unique_stem`].
+    Add,
+}
+
+/// Where a dropped PDF goes under `policy`, when it is a `duplicate` of a file already dropped
+/// or not.
+fn placement(policy: Duplicates, duplicate: bool) -> Placement {
+    match policy {
+        Duplicates::Skip if duplicate => Placement::Skip,
+        Duplicates::Replace if duplicate => Placement::Replace,
+        _ => Placement::Add,
+    }
+}
+
+/// How far the conversion of a file is, and how long its most recent pages took.
+struct Progress {
+    done: usize,
+    total: usize,
+    /// When the last page was done, or the first progress was reported.
+    last_page: Instant,
+    /// Seconds each of the last [`RATE_SAMPLES`] pages took.
+    page_secs: VecDeque<f64>,
+}
+
+impl Progress {
+    fn new(done: usize, total: usize) -> Self {
+        Self {
+            done,
+            total,
+            last_page: Instant::now(),
+            page_secs: VecDeque::new(),
+        }
+    }
+
+    /// Record that `done` of `total` pages are done, timing the pages done since the last call.
+    fn update(&mut self, done: usize, total: usize) {
+        if done > self.done {
+            let secs = self.last_page.elapsed().as_secs_f64() / (done - self.done) as f64;
+            if self.page_secs.len() == RATE_SAMPLES {
+                self.page_secs.pop_front();
+            }
+            self.page_secs.push_back(secs);
+            self.last_page = Instant::now();
+        }
+        self.done = done;
+        self.total = total;
+    }
+
+    /// Seconds the pages left are expected to take, from the average of the recent ones, once a
+    /// page is done.
+    fn remaining_secs(&self) -> Option<f64> {
+        if self.page_secs.is_empty() {
+            return None;
+        }
+        let per_page = self.page_secs.iter().sum::<f64>() / self.page_secs.len() as f64;
+        Some(per_page * self.total.saturating_sub(self.done) as f64)
+    }
+}
+
+pub enum Msg {
+    Render(Box<RenderedImage>),
+    Fail(FailedFile),
+    Upload(web_sys::FileList),
+    /// Folders were dropped, whose PDFs are collected before they are uploaded.
+    DropFolders(Vec<web_sys::FileSystemEntry>),
+    /// How many of its pages a file being converted has done, and how many it has in total.
+    Progress(String, usize, usize),
+    DragEnter(DragSummary),
+    DragLeave,
+    Retry(String),
+    RetryAll,
+    DismissBanner,
+    DismissMemoryWarning,
+    /// Nothing happened for `settings.idle_clear_minutes`, see [`Settings::keep_results`].
+    Idle,
+    /// The pages of the most recent upload passed this many pixels, so the rest are rendered
+    /// smaller.
+    OverPixelBudget(u64),
+    Download(String, OutputFormat),
+    /// Download every enabled format of a file in one zip.
+    DownloadAll(String),
+    DownloadInterleaved(OutputFormat),
+    DownloadStrip(OutputFormat),
+    /// Download what the most recent upload had converted before it was cancelled.
+    DownloadPartial(OutputFormat),
+    /// Download the pages of every converted file in one zip, a folder per file.
+    DownloadEverything(OutputFormat),
+    ShowPreview(String, i32, i32),
+    MovePreview(i32, i32),
+    HidePreview,
+    /// Stop converting the file with the stem and forget about it.
+    Cancel(String),
+    CancelAll,
+    /// Stop starting new pages, or carry on where conversions stopped.
+    Pause(bool),
+    ClearAll,
+    UndoClear,
+    /// The undo period of "Clear all" is over.
+    ForgetCleared,
+    Settings(Box<Settings>),
+    RenderAllPages(String),
+    /// Convert a processed file again with the current settings.
+    Reconvert(String),
+    /// Convert every processed file again with the current settings, and retry the failed ones.
+    ReconvertAll,
+    CopyDataUri(String, usize),
+    /// Make the page thumbnails of a file, if they haven't been already.
+    ShowThumbnails(String),
+    /// Download the page with the number of a file on its own.
+    DownloadPage(String, usize),
+    /// Download every page of a file as `<img>` tags with data URIs.
+    DownloadHtml(String),
+    /// Download a zip with a web page scrolling through every page of a file.
+    DownloadWebPage(String),
+    /// Download a zip with every page of a file and a IIIF manifest describing them.
+    DownloadIiif(String),
+    /// Download every page of a file as an archival PDF/A document.
+    DownloadPdfa(String),
+    /// Download every page of a file as JPEGs in a PDF, flattening it.
+    DownloadRasterized(String),
+    /// Download a zip with every page of a file wrapped in an SVG.
+    DownloadSvg(String),
+    DownloadSprites(String),
+    /// Download every page of a file as the frames of one animated PNG.
+    DownloadApng(String),
+    SelectPage(String, usize, bool),
+    /// Copy the selected pages of a file to the clipboard.
+    CopySelected(String),
+    /// A dropped zip archive has been read, with its file name and contents.
+    Unzipped(String, Vec<u8>),
+    /// The browser couldn't read a zip archive or session bundle with the file name. To try it
+    /// out, drop a large archive and delete or rename it before it has been read.
+    ReadFailed(String, String),
+    ExportSession,
+    /// Download a CSV with a row for every converted and failed file.
+    ExportSummary,
+    ImportSession(web_sys::FileList),
+    /// A session bundle has been read, with its file name and contents.
+    SessionRead(String, Vec<u8>),
+    /// Pick a font to draw missing fonts with.
+    PickFont(web_sys::FileList),
+    /// A picked font has been read, with its file name and contents.
+    FontRead(String, Vec<u8>),
+    /// Open page 1 of a file in a new browser tab.
+    OpenInNewTab(String),
+    /// Browse a processed file page by page in the viewer.
+    OpenViewer(String),
+    ViewPage(usize),
+    CloseViewer,
+    /// Pick the region to crop to on page 1 of a processed file.
+    OpenCrop(String),
+    CropDrag(crop::Drag),
+    /// Crop every page to the region, or not at all.
+    CropTo(Option<Crop>),
+    CloseCrop,
+    /// Replace the settings overridden for the file with the stem.
+    Override(String, overrides::Overrides),
+    /// A dropped image with the id has been read, or couldn't be.
+    ImageRead(u64, Result<Vec<u8>, String>),
+    /// Move the dropped image with the id one place up, or down.
+    MoveImage(u64, bool),
+    RemoveImage(u64),
+    ClearImages,
+    /// Download the dropped images combined into a PDF.
+    DownloadImagesPdf,
+    Log(log::Level, String),
+}
+
+pub struct App {
+    readers: HashMap<String, Task>,
+    /// How far the files being converted are, by stem.
+    progress: HashMap<String, Progress>,
+    files: Vec<RenderedImage>,
+    failed: Vec<FailedFile>,
+    /// Stems of the files dropped in the most recent upload, used for the summary banner.
+    batch: Vec<String>,
+    banner_dismissed: bool,
+    /// A notification was already shown for the most recent upload.
+    batch_notified: bool,
+    /// How many files of the most recent upload were cancelled before they were converted.
+    cancelled: usize,
+    /// Shown when the latest upload may need more memory than is available.
+    memory_warning: Option<String>,
+    /// Images dropped to be combined into a PDF, in order.
+    images: Vec<images::DroppedImage>,
+    next_image_id: u64,
+    /// Pixels rendered for the most recent upload.
+    pixel_budget: Rc<memory::PixelBudget>,
+    /// Conversions wait before their next page until this is unset, see [`wait_while_paused`].
+    paused: bool,
+    preview: Option<Preview>,
+    cleared: Option<Cleared>,
+    viewer: Option<viewer::Viewer>,
+    crop: Option<crop::CropPicker>,
+    /// `(stem, page number)` of pages ticked in the page lists.
+    selected_pages: HashSet<(String, usize)>,
+    /// What is being dragged over the drop zone, if anything.
+    dragging: Option<DragSummary>,
+    settings: Settings,
+    /// Settings changed for single documents, by stem.
+    overrides: HashMap<String, overrides::Overrides>,
+    log: log::Log,
+    file_input: NodeRef,
+    /// Stem of the most recently added result, which gets focus once it is on screen.
+    newest: Option<String>,
+    /// Attached to the most recently added result.
+    newest_ref: NodeRef,
+    focus_newest: bool,
+    /// Opens the file picker when "o" is pressed, for as long as the component lives.
+    _shortcut: EventListener,
+    /// Uploads files pasted anywhere on the page, like PDFs copied in a file manager.
+    _paste: EventListener,
+    /// Sends [`Msg::Idle`] unless another message comes first, when results aren't kept.
+    idle_timeout: Option<Timeout>,
+}
+
+impl Component for App {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let file_input = NodeRef::default();
+        let shortcut = {
+            let file_input = file_input.clone();
+            EventListener::new(&gloo::utils::document(), "keydown", move |e| {
+                let e: &KeyboardEvent = e.unchecked_ref();
+                if e.key() != "o" || e.repeat() || e.ctrl_key() || e.meta_key() || e.alt_key() {
+                    return;
+                }
+                if e.target().is_some_and(|target| accepts_typing(&target)) {
+                    return;
+                }
+                if let Some(input) = file_input.cast::<HtmlInputElement>() {
+                    e.prevent_default();
+                    input.click();
+                }
+            })
+        };
+        let paste = {
+            let link = ctx.link().clone();
+            EventListener::new(&gloo::utils::document(), "paste", move |e| {
+                let e: &ClipboardEvent = e.unchecked_ref();
+                // pasted text is left to whatever it was pasted into
+                let Some(files) = e
+                    .clipboard_data()
+                    .and_then(|data| data.files())
+                    .filter(|files| files.length() > 0)
+                else {
+                    return;
+                };
+                e.prevent_default();
+                link.send_message(Msg::Upload(files));
+            })
+        };
+        let settings = settings::load();
+        Self {
+            readers: HashMap::default(),
+            progress: HashMap::default(),
+            files: Vec::default(),
+            failed: Vec::default(),
+            batch: Vec::default(),
+            banner_dismissed: false,
+            batch_notified: false,
+            cancelled: 0,
+            memory_warning: None,
+            images: Vec::new(),
+            next_image_id: 0,
+            pixel_budget: Rc::new(memory::PixelBudget::new(&settings)),
+            paused: false,
+            preview: None,
+            cleared: None,
+            viewer: None,
+            crop: None,
+            selected_pages: HashSet::default(),
+            dragging: None,
+            settings,
+            overrides: HashMap::default(),
+            log: log::Log::default(),
+            file_input,
+            newest: None,
+            newest_ref: NodeRef::default(),
+            focus_newest: false,
+            _shortcut: shortcut,
+            _paste: paste,
+            idle_timeout: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        // everything the page does goes through here, so any other message is activity
+        if !matches!(msg, Msg::Idle) {
+            self.restart_idle_timeout(ctx);
+        }
+        match msg {
+            Msg::Render(file) => {
+                let mut file = *file;
+                self.readers.remove(&file.stem);
+                self.progress.remove(&file.stem);
+                self.log.info(format!(
+                    "converted {}: {} of {} pages in {:.2} s",
+                    file.stem,
+                    file.rendered_pages(),
+                    file.page_count,
+                    file.render_secs
+                ));
+                if file.uses_cmyk {
+                    self.log
+                        .info(format!("{} has CMYK content, converted to RGB", file.stem));
+                }
+                if !file.substituted_fonts.is_empty() {
+                    self.log.info(format!(
+                        "drew the substitute font in place of {} in {}",
+                        file.substituted_fonts.join(", "),
+                        file.stem
+                    ));
+                }
+                if file.has_form_values {
+                    self.log.error(format!(
+                        "{} has filled form fields, their values are not rendered",
+                        file.stem
+                    ));
+                }
+                if !file.blank_pages.is_empty() {
+                    let numbers: Vec<_> = file.blank_pages.iter().map(usize::to_string).collect();
+                    self.log.info(format!(
+                        "left out blank pages {} of {}",
+                        numbers.join(", "),
+                        file.stem
+                    ));
+                }
+                match file.search {
+                    Some(SearchResult::Matched(count)) => self.log.info(format!(
+                        "{count} {} of {} matched the search",
+                        if count == 1 { "page" } else { "pages" },
+                        file.stem
+                    )),
+                    Some(SearchResult::NoText) => self.log.error(format!(
+                        "{} has no text to search, no pages were rendered",
+                        file.stem
+                    )),
+                    None => {}
+                }
+                if !file.attachments.is_empty() {
+                    let count = file.attachments.len();
+                    self.log.info(format!(
+                        "{} has {count} {}",
+                        file.stem,
+                        if count == 1 {
+                            "attachment"
+                        } else {
+                            "attachments"
+                        }
+                    ));
+                }
+                // snapped scales are whole numbers
+                let snapped: BTreeSet<_> = file
+                    .pages
+                    .iter()
+                    .filter_map(|page| Some(page.snapped_scale? as u32))
+                    .collect();
+                if !snapped.is_empty() {
+                    let snapped: Vec<_> = snapped.iter().map(|scale| format!("{scale}x")).collect();
+                    self.log.info(format!(
+                        "snapped the render scale of {} to {}",
+                        file.stem,
+                        snapped.join(", ")
+                    ));
+                }
+                let qualities: BTreeSet<_> = file
+                    .pages
+                    .iter()
+                    .filter_map(|page| page.jpeg_quality)
+                    .collect();
+                if let (Some(lowest), Some(highest)) = (qualities.first(), qualities.last()) {
+                    let sizes: Vec<_> = file
+                        .pages
+                        .iter()
+                        .filter_map(|page| page.bytes(OutputFormat::Jpeg))
+                        .map(<[u8]>::len)
+                        .collect();
+                    let largest = sizes.iter().max().copied().unwrap_or(0);
+                    self.log.info(format!(
+                        "encoded the JPEGs of {} at quality {}, the largest is {}",
+                        file.stem,
+                        if lowest == highest {
+                            lowest.to_string()
+                        } else {
+                            format!("{lowest} to {highest}")
+                        },
+                        format_size(largest, humansize::DECIMAL)
+                    ));
+                }
+                if let Some(compression) = file.compression_fallback {
+                    self.log.info(format!(
+                        "compressing {} took too long, later pages used {} PNG compression",
+                        file.stem,
+                        compression.label().to_lowercase()
+                    ));
+                }
+                let jpeg_target = self.settings_for(&file.stem).jpeg_target_kb as usize * 1000;
+                for page in &file.pages {
+                    for warning in &page.warnings {
+                        self.log
+                            .error(format!("page {} of {}: {warning}", page.number, file.stem));
+                    }
+                    if page.jpeg_quality.is_some()
+                        && let Some(bytes) = page.bytes(OutputFormat::Jpeg)
+                        && bytes.len() > jpeg_target
+                    {
+                        self.log.error(format!(
+                            "page {} of {} is {} even at the lowest JPEG quality",
+                            page.number,
+                            file.stem,
+                            format_size(bytes.len(), humansize::DECIMAL)
+                        ));
+                    }
+                    if let Some((width, height)) = page.downscaled_from {
+                        let (clamped_width, clamped_height) = page.dimensions;
+                        self.log.info(format!(
+                            "page {} of {} is too large at {width}x{height}, rendered at {clamped_width}x{clamped_height}",
+                            page.number, file.stem
+                        ));
+                    }
+                }
+                file.preview_url = first_page_url(&mut file, self.settings.sharp_previews);
+                // a converted-again file takes the place of its previous result
+                match self
+                    .files
+                    .iter_mut()
+                    .find(|existing| existing.stem == file.stem)
+                {
+                    Some(existing) => {
+                        if let Some(url) = &existing.preview_url {
+                            let _ = Url::revoke_object_url(url);
+                        }
+                        *existing = file;
+                    }
+                    None => {
+                        self.newest = Some(file.stem.clone());
+                        self.focus_newest = true;
+                        self.files.push(file);
+                    }
+                }
+                self.notify_if_done();
+                true
+            }
+            Msg::RenderAllPages(stem) => {
+                let settings = Settings {
+                    first_page_only: false,
+                    ..self.settings_for(&stem)
+                };
+                self.reconvert(ctx, &stem, &settings)
+            }
+            Msg::Reconvert(stem) => self.reconvert(ctx, &stem, &self.settings_for(&stem)),
+            Msg::ReconvertAll => {
+                self.log.info(format!(
+                    "converting {} files again with the current settings",
+                    self.files.len()
+                ));
+                let budget = Rc::new(memory::PixelBudget::new(&self.settings));
+                let files: Vec<_> = self
+                    .files
+                    .iter()
+                    .map(|file| {
+                        let stem = file.stem.clone();
+                        (stem, file.pdf_human_size.clone(), file.data.clone())
+                    })
+                    .collect();
+                for (stem, pdf_human_size, data) in files {
+                    let settings = self.settings_for(&stem);
+                    self.start(ctx, stem, pdf_human_size, data, &settings, budget.clone());
+                }
+                ctx.link().send_message(Msg::RetryAll);
+                true
+            }
+            Msg::Fail(file) => {
+                self.log
+                    .error(format!("failed converting {}: {}", file.stem, file.error));
+                self.readers.remove(&file.stem);
+                self.progress.remove(&file.stem);
+                if let Some(index) = self
+                    .files
+                    .iter()
+                    .position(|existing| existing.stem == file.stem)
+                {
+                    let previous = self.files.remove(index);
+                    if let Some(url) = &previous.preview_url {
+                        let _ = Url::revoke_object_url(url);
+                    }
+                }
+                self.newest = Some(file.stem.clone());
+                self.focus_newest = true;
+                self.failed.push(file);
+                self.notify_if_done();
+                true
+            }
+            Msg::Upload(files) => {
+                self.dragging = None;
+                if self.readers.is_empty() {
+                    self.batch.clear();
+                    self.banner_dismissed = false;
+                    self.batch_notified = false;
+                    self.cancelled = 0;
+                    self.pixel_budget = Rc::new(memory::PixelBudget::new(&self.settings));
+                }
+                let files = FileList::from(files);
+                // turned away before they are read, reading them could already crash the page
+                let (too_large, files): (Vec<_>, Vec<_>) = files
+                    .iter()
+                    .partition(|file| file.size() > memory::MAX_FILE_SIZE);
+                let total_size = files.iter().map(|file| file.size()).sum();
+                let warnings: Vec<_> = too_large
+                    .iter()
+                    .map(|file| memory::too_large(&file.name(), file.size()))
+                    .chain(memory::warning(total_size))
+                    .collect();
+                for warning in &warnings {
+                    self.log.error(warning.clone());
+                }
+                self.memory_warning = (!warnings.is_empty()).then(|| warnings.join(" "));
+                for &file in &files {
+                    let mime_type = file.raw_mime_type();
+                    if is_zip(file) {
+                        self.log.info(format!("accepted archive {}", file.name()));
+                        let link = ctx.link().clone();
+                        let cancelled = Rc::new(Cell::new(false));
+                        let task_cancelled = cancelled.clone();
+                        let name = file.name();
+                        let reader = gloo::file::callbacks::read_as_bytes(file, move |res| {
+                            if task_cancelled.get() {
+                                return;
+                            }
+                            link.send_message(match res {
+                                Ok(data) => Msg::Unzipped(name, data),
+                                Err(err) => Msg::ReadFailed(name, err.to_string()),
+                            });
+                        });
+                        track(
+                            &mut self.readers,
+                            file.name(),
+                            Task {
+                                _reader: Some(reader),
+                                size: file.size(),
+                                cancelled,
+                            },
+                        );
+                        continue;
+                    }
+                    if images::is_image(&mime_type) {
+                        let id = self.next_image_id;
+                        self.next_image_id += 1;
+                        let link = ctx.link().clone();
+                        let reader = gloo::file::callbacks::read_as_bytes(file, move |res| {
+                            link.send_message(Msg::ImageRead(
+                                id,
+                                res.map_err(|err| err.to_string()),
+                            ));
+                        });
+                        self.images.push(images::DroppedImage {
+                            id,
+                            name: file.name(),
+                            data: None,
+                            reader: Some(reader),
+                        });
+                        continue;
+                    }
+                    if !is_pdf(file) {
+                        self.log.error(format!(
+                            "skipped {}: not a PDF but {mime_type:?}",
+                            file.name()
+                        ));
+                        continue;
+                    }
+                    let name = pdf_to_whatever::pdf_stem(&file.name()).to_string();
+                    let duplicate = self.is_listed(&name, file.size());
+                    let stem = match placement(self.settings.duplicates, duplicate) {
+                        Placement::Skip => {
+                            self.log
+                                .info(format!("skipped {}, it was already dropped", file.name()));
+                            continue;
+                        }
+                        Placement::Replace => {
+                            self.forget(&name);
+                            self.log
+                                .info(format!("replacing {name} with the file dropped again"));
+                            name
+                        }
+                        Placement::Add => self.unique_stem(&name),
+                    };
+                    let pdf_human_size = format_size(file.size(), humansize::BINARY);
+
+                    self.log.info(format!("accepted {}", file.name()));
+                    self.batch.push(stem.clone());
+                    let link = ctx.link().clone();
+                    let cancelled = Rc::new(Cell::new(false));
+                    let task_cancelled = cancelled.clone();
+                    let task_stem = stem.clone();
+                    let settings = self.settings_for(&stem);
+                    let budget = self.pixel_budget.clone();
+                    // the picker has to be shown while the upload is being handled
+                    let picked = if !settings.stream_to_disk || !download::save_file_supported() {
+                        None
+                    } else if files.len() > 1 {
+                        self.log.info(format!(
+                            "converting {stem} in memory, only single documents are written straight to disk"
+                        ));
+                        None
+                    } else {
+                        match download::pick_zip_file(&format!("{stem}.zip")) {
+                            Ok(picked) => Some(picked),
+                            Err(err) => {
+                                self.log
+                                    .error(format!("couldn't ask where to save {stem}: {err:?}"));
+                                None
+                            }
+                        }
+                    };
+                    let reader = gloo::file::callbacks::read_as_bytes(file, move |res| {
+                        let data = match res {
+                            Ok(data) => data,
+                            // like a document that failed to convert, so it isn't left waiting
+                            Err(err) => {
+                                if !task_cancelled.get() {
+                                    link.send_message(Msg::Fail(FailedFile {
+                                        stem: task_stem,
+                                        pdf_human_size,
+                                        data: Arc::default(),
+                                        error: ConversionError::ReadFailed(err.to_string()),
+                                    }));
+                                }
+                                return;
+                            }
+                        };
+                        match picked {
+                            Some(picked) => stream_to_file(
+                                &link,
+                                task_stem,
+                                pdf_human_size,
+                                Arc::new(data),
+                                &settings,
+                                task_cancelled,
+                                picked,
+                            ),
+                            None => process(
+                                &link,
+                                task_stem,
+                                pdf_human_size,
+                                Arc::new(data),
+                                &settings,
+                                task_cancelled,
+                                budget,
+                            ),
+                        }
+                    });
+                    track(
+                        &mut self.readers,
+                        stem,
+                        Task {
+                            _reader: Some(reader),
+                            size: file.size(),
+                            cancelled,
+                        },
+                    );
+                }
+                true
+            }
+            Msg::Progress(stem, done, total) => {
+                self.progress
+                    .entry(stem)
+                    .and_modify(|progress| progress.update(done, total))
+                    .or_insert_with(|| Progress::new(done, total));
+                true
+            }
+            Msg::DropFolders(entries) => {
+                self.dragging = None;
+                let link = ctx.link().clone();
+                let folder_names = self.settings.folder_names;
+                wasm_bindgen_futures::spawn_local(async move {
+                    match folders::collect(entries, folder_names).await {
+                        Ok(files) => link.send_message(Msg::Upload(files)),
+                        Err(err) => link.send_message(Msg::Log(
+                            log::Level::Error,
+                            format!("failed reading dropped folders: {err:?}"),
+                        )),
+                    }
+                });
+                true
+            }
+            Msg::DragEnter(summary) => {
+                self.dragging = Some(summary);
+                true
+            }
+            Msg::DragLeave => {
+                self.dragging = None;
+                true
+            }
+            Msg::Unzipped(name, data) => {
+                self.readers.remove(&name);
+                let pdfs = match pdf_to_whatever::unzip_pdfs(&data) {
+                    Ok(pdfs) => pdfs,
+                    Err(err) => {
+                        self.log.error(format!("failed reading {name}: {err}"));
+                        return true;
+                    }
+                };
+                self.log
+                    .info(format!("found {} PDFs in {name}", pdfs.len()));
+                for (stem, data) in pdfs {
+                    let stem = self.unique_stem(&stem);
+                    let pdf_human_size = format_size(data.len(), humansize::BINARY);
+                    self.batch.push(stem.clone());
+                    let settings = self.settings_for(&stem);
+                    let budget = self.pixel_budget.clone();
+                    self.start(ctx, stem, pdf_human_size, Arc::new(data), &settings, budget);
+                }
+                true
+            }
+            Msg::ExportSession => {
+                let documents: Vec<_> = self
+                    .files
+                    .iter()
+                    .map(|file| SessionDocument {
+                        stem: &file.stem,
+                        data: &file.data,
+                        pages: &file.pages,
+                    })
+                    .chain(self.failed.iter().map(|file| SessionDocument {
+                        stem: &file.stem,
+                        data: &file.data,
+                        pages: &[],
+                    }))
+                    .collect();
+                match pdf_to_whatever::export_session(&self.settings, &documents) {
+                    Ok(zip) => download::save(&zip, "session.zip"),
+                    Err(err) => self.log.error(format!("failed exporting session: {err}")),
+                }
+                false
+            }
+            Msg::ExportSummary => {
+                let csv = summary::summary_csv(&self.files, &self.failed);
+                download::save(csv.as_bytes(), "summary.csv");
+                false
+            }
+            Msg::ImportSession(files) => {
+                let Some(file) = FileList::from(files).first().cloned() else {
+                    return false;
+                };
+                let link = ctx.link().clone();
+                let cancelled = Rc::new(Cell::new(false));
+                let task_cancelled = cancelled.clone();
+                let name = file.name();
+                let reader = gloo::file::callbacks::read_as_bytes(&file, move |res| {
+                    if task_cancelled.get() {
+                        return;
+                    }
+                    link.send_message(match res {
+                        Ok(data) => Msg::SessionRead(name, data),
+                        Err(err) => Msg::ReadFailed(name, err.to_string()),
+                    });
+                });
+                track(
+                    &mut self.readers,
+                    file.name(),
+                    Task {
+                        _reader: Some(reader),
+                        size: file.size(),
+                        cancelled,
+                    },
+                );
+                true
+            }
+            Msg::PickFont(files) => {
+                let Some(file) = FileList::from(files).first().cloned() else {
+                    return false;
+                };
+                let link = ctx.link().clone();
+                let cancelled = Rc::new(Cell::new(false));
+                let task_cancelled = cancelled.clone();
+                let name = file.name();
+                let reader = gloo::file::callbacks::read_as_bytes(&file, move |res| {
+                    if task_cancelled.get() {
+                        return;
+                    }
+                    link.send_message(match res {
+                        Ok(data) => Msg::FontRead(name, data),
+                        Err(err) => Msg::ReadFailed(name, err.to_string()),
+                    });
+                });
+                track(
+                    &mut self.readers,
+                    file.name(),
+                    Task {
+                        _reader: Some(reader),
+                        size: file.size(),
+                        cancelled,
+                    },
+                );
+                true
+            }
+            Msg::FontRead(name, data) => {
+                self.readers.remove(&name);
+                let Some(font) = SubstituteFont::new(&name, data) else {
+                    self.log
+                        .error(format!("{name} is not a TrueType or OpenType font"));
+                    return true;
+                };
+                self.log
+                    .info(format!("drawing fonts missing from documents with {name}"));
+                self.settings.substitute_font = Some(font);
+                true
+            }
+            Msg::ReadFailed(name, reason) => {
+                self.readers.remove(&name);
+                self.log.error(format!("could not read {name}: {reason}"));
+                true
+            }
+            Msg::SessionRead(name, data) => {
+                self.readers.remove(&name);
+                let session = match pdf_to_whatever::import_session(&data) {
+                    Ok(session) => session,
+                    Err(err) => {
+                        self.log.error(format!("failed importing {name}: {err}"));
+                        return true;
+                    }
+                };
+                self.hide_preview();
+                for file in self.files.drain(..) {
+                    if let Some(url) = &file.preview_url {
+                        let _ = Url::revoke_object_url(url);
+                    }
+                }
+                self.failed.clear();
+                self.batch.clear();
+                self.banner_dismissed = false;
+                self.batch_notified = false;
+                settings::save(&session.settings);
+                self.settings = session.settings;
+                self.pixel_budget = Rc::new(memory::PixelBudget::new(&self.settings));
+                self.log.info(format!(
+                    "imported session {name} with {} documents",
+                    session.documents.len()
+                ));
+                for (stem, data) in session.documents {
+                    let pdf_human_size = format_size(data.len(), humansize::BINARY);
+                    self.batch.push(stem.clone());
+                    let settings = self.settings_for(&stem);
+                    let budget = self.pixel_budget.clone();
+                    self.start(ctx, stem, pdf_human_size, Arc::new(data), &settings, budget);
+                }
+                true
+            }
+            Msg::Retry(stem) => {
+                if let Some(index) = self.failed.iter().position(|file| file.stem == stem) {
+                    let file = self.failed.remove(index);
+                    let settings = self.settings_for(&file.stem);
+                    let budget = Rc::new(memory::PixelBudget::new(&settings));
+                    self.start(
+                        ctx,
+                        file.stem,
+                        file.pdf_human_size,
+                        file.data,
+                        &settings,
+                        budget,
+                    );
+                }
+                true
+            }
+            Msg::RetryAll => {
+                // files that couldn't be read have nothing to convert again
+                let (unread, failed) = std::mem::take(&mut self.failed)
+                    .into_iter()
+                    .partition(|file| matches!(file.error, ConversionError::ReadFailed(_)));
+                self.failed = unread;
+                let budget = Rc::new(memory::PixelBudget::new(&self.settings));
+                for file in failed {
+                    let settings = self.settings_for(&file.stem);
+                    self.start(
+                        ctx,
+                        file.stem,
+                        file.pdf_human_size,
+                        file.data,
+                        &settings,
+                        budget.clone(),
+                    );
+                }
+                true
+            }
+            Msg::Settings(settings) => {
+                let settings = *settings;
+                if settings != self.settings {
+                    settings::save(&settings);
+                }
+                if settings.notify_when_done
+                    && !self.settings.notify_when_done
+                    && notify::request_permission()
+                {
+                    self.log
+                        .error("notifications are blocked for this page in the browser settings");
+                }
+                self.settings = settings;
+                self.restart_idle_timeout(ctx);
+                true
+            }
+            Msg::Idle => {
+                // never pull results out from under a conversion, wait for it to be idle too
+                if !self.readers.is_empty() || !self.progress.is_empty() {
+                    self.restart_idle_timeout(ctx);
+                    return false;
+                }
+                if self.files.is_empty() && self.failed.is_empty() && self.cleared.is_none() {
+                    return false;
+                }
+                self.hide_preview();
+                for file in &mut self.files {
+                    if let Some(url) = file.preview_url.take() {
+                        let _ = Url::revoke_object_url(&url);
+                    }
+                }
+                self.log.info(format!(
+                    "cleared {} results after {} idle minutes",
+                    self.files.len() + self.failed.len(),
+                    self.settings.idle_clear_minutes
+                ));
+                self.files.clear();
+                self.failed.clear();
+                self.cleared = None;
+                self.viewer = None;
+                self.crop = None;
+                self.selected_pages.clear();
+                self.batch.clear();
+                true
+            }
+            Msg::Override(stem, overrides) => {
+                if overrides.is_empty() {
+                    self.overrides.remove(&stem);
+                } else {
+                    self.overrides.insert(stem, overrides);
+                }
+                true
+            }
+            Msg::ImageRead(id, result) => {
+                let Some(index) = self.images.iter().position(|image| image.id == id) else {
+                    return false;
+                };
+                match result {
+                    Ok(data) => {
+                        let image = &mut self.images[index];
+                        image.data = Some(data);
+                        image.reader = None;
+                    }
+                    Err(reason) => {
+                        let image = self.images.remove(index);
+                        self.log
+                            .error(format!("could not read {}: {reason}", image.name));
+                    }
+                }
+                true
+            }
+            Msg::MoveImage(id, up) => {
+                let Some(index) = self.images.iter().position(|image| image.id == id) else {
+                    return false;
+                };
+                let other = if up {
+                    index.checked_sub(1)
+                } else {
+                    Some(index + 1)
+                };
+                match other.filter(|&other| other < self.images.len()) {
+                    Some(other) => {
+                        self.images.swap(index, other);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Msg::RemoveImage(id) => {
+                self.images.retain(|image| image.id != id);
+                true
+            }
+            Msg::ClearImages => {
+                self.images.clear();
+                true
+            }
+            Msg::DownloadImagesPdf => {
+                let images: Vec<_> = self
+                    .images
+                    .iter()
+                    .filter_map(|image| image.data.as_ref())
+                    .collect();
+                match pdf_to_whatever::assemble_pdf(&images) {
+                    Ok(pdf) => {
+                        download::save(&pdf, "images.pdf");
+                        self.log.info(format!(
+                            "downloaded {} images as a {} PDF",
+                            images.len(),
+                            format_size(pdf.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed combining images into a PDF: {err}")),
+                }
+                true
+            }
+            Msg::Log(level, message) => {
+                self.log.push(level, message);
+                true
+            }
+            Msg::Cancel(stem) => {
+                self.log.info(format!("cancelled {stem}"));
+                self.forget(&stem);
+                self.notify_if_done();
+                true
+            }
+            Msg::CancelAll => {
+                for (stem, task) in self.readers.drain() {
+                    self.log.info(format!("cancelled {stem}"));
+                    task.cancelled.set(true);
+                    self.cancelled += 1;
+                }
+                self.progress.clear();
+                self.paused = false;
+                true
+            }
+            Msg::Pause(paused) => {
+                self.log.info(if paused {
+                    "paused converting, once the pages being rendered are done"
+                } else {
+                    "resumed converting"
+                });
+                self.paused = paused;
+                // time spent paused doesn't count towards the page being rendered
+                if !paused {
+                    for progress in self.progress.values_mut() {
+                        progress.last_page = Instant::now();
+                    }
+                }
+                true
+            }
+            Msg::ClearAll => {
+                self.hide_preview();
+                let mut files = std::mem::take(&mut self.files);
+                for file in &mut files {
+                    if let Some(url) = file.preview_url.take() {
+                        let _ = Url::revoke_object_url(&url);
+                    }
+                }
+                self.log.info(format!(
+                    "cleared {} results",
+                    files.len() + self.failed.len()
+                ));
+                let link = ctx.link().clone();
+                self.cleared = Some(Cleared {
+                    files,
+                    failed: std::mem::take(&mut self.failed),
+                    _timeout: Timeout::new(UNDO_CLEAR_MILLIS, move || {
+                        link.send_message(Msg::ForgetCleared)
+                    }),
+                });
+                self.batch.clear();
+                true
+            }
+            Msg::UndoClear => {
+                let Some(cleared) = self.cleared.take() else {
+                    return false;
+                };
+                // keep anything converted since, it is newer than what was cleared
+                let newer = std::mem::replace(&mut self.files, cleared.files);
+                for file in &mut self.files {
+                    file.preview_url = first_page_url(file, self.settings.sharp_previews);
+                }
+                self.files.extend(newer);
+                let newer = std::mem::replace(&mut self.failed, cleared.failed);
+                self.failed.extend(newer);
+                self.log.info("restored cleared results".to_string());
+                true
+            }
+            Msg::ForgetCleared => {
+                self.cleared = None;
+                true
+            }
+            Msg::OverPixelBudget(limit) => {
+                let warning = format!(
+                    "The pages of these files passed {} megapixels, so the rest are rendered at half \
+                     the scale to save memory. Drop fewer files at a time for full-size pages.",
+                    limit / 1_000_000
+                );
+                self.log.error(warning.clone());
+                self.memory_warning = Some(warning);
+                true
+            }
+            Msg::DismissMemoryWarning => {
+                self.memory_warning = None;
+                true
+            }
+            Msg::DismissBanner => {
+                self.banner_dismissed = true;
+                true
+            }
+            Msg::Download(stem, format) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                if self.settings.save_to_folder && download::folder_access_supported() {
+                    let entries = pdf_to_whatever::document_entries(
+                        &file.stem,
+                        &file.pages,
+                        &file.attachments,
+                        &file.data,
+                        format,
+                        &self.settings,
+                    )
+                    .into_iter()
+                    .map(|(filename, bytes)| (filename, bytes.into_owned()))
+                    .collect();
+                    let link = ctx.link().clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        if let Err(err) = download::save_to_folder(entries).await {
+                            let message = format!("failed saving to folder: {err:?}");
+                            link.send_message(Msg::Log(log::Level::Error, message));
+                        }
+                    });
+                    return false;
+                }
+                let entries = pdf_to_whatever::document_entries(
+                    &file.stem,
+                    &file.pages,
+                    &file.attachments,
+                    &file.data,
+                    format,
+                    &self.settings,
+                );
+                if let Some((filename, bytes)) = file.loose_entry(&entries, &self.settings) {
+                    download::save(bytes, filename);
+                    return false;
+                }
+                match pdf_to_whatever::zip_parts(&file.stem, entries, &self.settings) {
+                    Ok(parts) => save_parts(&parts),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating zip for {}: {err}", file.stem)),
+                }
+                false
+            }
+            Msg::DownloadHtml(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let html = pdf_to_whatever::pages_html(&stem, &file.pages);
+                let size = format_size(html.len(), humansize::BINARY);
+                if html.len() > LARGE_HTML
+                    && !gloo::dialogs::confirm(&format!(
+                        "The HTML for {stem} is {size}, which many editors and CMSs handle poorly. Download anyway?"
+                    ))
+                {
+                    return false;
+                }
+                download::save(html.as_bytes(), &format!("{stem}.html"));
+                self.log
+                    .info(format!("downloaded {stem} as {size} of HTML"));
+                true
+            }
+            Msg::DownloadWebPage(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let entries = pdf_to_whatever::web_page(&stem, &file.pages);
+                match pdf_to_whatever::zip_entries(entries, &self.settings) {
+                    Ok(zip) => {
+                        download::save(&zip, &format!("{stem}-web.zip"));
+                        self.log.info(format!(
+                            "downloaded {stem} as a {} web page",
+                            format_size(zip.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating web page of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadSvg(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let entries = pdf_to_whatever::svg_pages(&file.pages);
+                match pdf_to_whatever::zip_entries(entries, &self.settings) {
+                    Ok(zip) => {
+                        download::save(&zip, &format!("{stem}-svg.zip"));
+                        self.log.info(format!(
+                            "downloaded {stem} as {} of SVGs",
+                            format_size(zip.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating SVGs of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadIiif(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let entries =
+                    pdf_to_whatever::iiif(&stem, &file.pages, &self.settings.iiif_base_url);
+                match pdf_to_whatever::zip_entries(entries, &self.settings) {
+                    Ok(zip) => {
+                        download::save(&zip, &format!("{stem}-iiif.zip"));
+                        self.log.info(format!(
+                            "downloaded {stem} for IIIF, to be served at {}",
+                            self.settings.iiif_base_url
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating IIIF export of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadPdfa(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                match pdf_to_whatever::pdfa(&stem, &file.pages, &self.settings) {
+                    Ok(pdf) => {
+                        download::save(&pdf, &format!("{stem}-pdfa.pdf"));
+                        self.log.info(format!(
+                            "downloaded {stem} as a {} PDF/A document",
+                            format_size(pdf.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating PDF/A of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadRasterized(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                match pdf_to_whatever::rasterized_pdf(&file.pages, &self.settings) {
+                    Ok(pdf) => {
+                        download::save(&pdf, &format!("{stem}-rasterized.pdf"));
+                        self.log.info(format!(
+                            "downloaded {stem} as a {} rasterized PDF",
+                            format_size(pdf.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating rasterized PDF of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadSprites(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                match pdf_to_whatever::sprite_sheets(
+                    &stem,
+                    &file.pages,
+                    self.settings.sprite_max_size,
+                    self.settings.sprite_padding,
+                ) {
+                    Ok(entries) => {
+                        for (filename, bytes) in &entries {
+                            download::save(bytes, filename);
+                        }
+                        // the layout is the last entry, besides the sheets
+                        let sheets = entries.len().saturating_sub(1);
+                        self.log.info(format!(
+                            "downloaded {stem} as {sheets} sprite {}",
+                            if sheets == 1 { "sheet" } else { "sheets" }
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating sprite sheets of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadApng(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                match pdf_to_whatever::apng(
+                    &stem,
+                    &file.pages,
+                    self.settings.apng_delay_millis,
+                    self.settings.png_compression,
+                ) {
+                    Ok((filename, bytes)) => {
+                        download::save(&bytes, &filename);
+                        self.log.info(format!(
+                            "downloaded {stem} as an animated PNG of {}",
+                            format_size(bytes.len(), humansize::BINARY)
+                        ));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating an animated PNG of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::ShowThumbnails(stem) => {
+                let Some(file) = self.files.iter_mut().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                if !file.thumbnails.is_empty() {
+                    return false;
+                }
+                let thumbnails = file
+                    .pages
+                    .iter()
+                    .map(|page| pdf_to_whatever::thumbnail(page, THUMBNAIL_SIZE))
+                    .collect::<Result<Vec<_>, _>>();
+                match thumbnails {
+                    Ok(thumbnails) => {
+                        file.thumbnails = thumbnails
+                            .iter()
+                            .map(|bytes| OutputFormat::Png.data_uri(bytes))
+                            .collect();
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating thumbnails of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::DownloadPage(stem, number) => {
+                let Some(page) = self
+                    .files
+                    .iter()
+                    .find(|file| file.stem == stem)
+                    .and_then(|file| file.pages.iter().find(|page| page.number == number))
+                else {
+                    return false;
+                };
+                let Some((format, bytes)) = page.first_encoded() else {
+                    return false;
+                };
+                download::save(bytes, &page.filename(format));
+                false
+            }
+            Msg::CopyDataUri(stem, number) => {
+                let Some((format, bytes)) = self
+                    .files
+                    .iter()
+                    .find(|file| file.stem == stem)
+                    .and_then(|file| file.pages.iter().find(|page| page.number == number))
+                    .and_then(RenderedPage::first_encoded)
+                else {
+                    return false;
+                };
+                let uri = format.data_uri(bytes);
+                let size = format_size(uri.len(), humansize::BINARY);
+                if uri.len() > LARGE_DATA_URI
+                    && !gloo::dialogs::confirm(&format!(
+                        "The data URI for page {number} of {stem} is {size}, which many editors and browsers handle poorly. Copy anyway?"
+                    ))
+                {
+                    return false;
+                }
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let (level, message) = match download::copy_text(&uri).await {
+                        Ok(()) => (
+                            log::Level::Info,
+                            format!("copied page {number} of {stem} as a {size} data URI"),
+                        ),
+                        Err(err) => (
+                            log::Level::Error,
+                            format!("failed copying data URI: {err:?}"),
+                        ),
+                    };
+                    link.send_message(Msg::Log(level, message));
+                });
+                false
+            }
+            Msg::SelectPage(stem, number, selected) => {
+                if selected {
+                    self.selected_pages.insert((stem, number));
+                } else {
+                    self.selected_pages.remove(&(stem, number));
+                }
+                true
+            }
+            Msg::CopySelected(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let pages: Vec<_> = file
+                    .pages
+                    .iter()
+                    .filter(|page| self.selected_pages.contains(&(stem.clone(), page.number)))
+                    .collect();
+                // browsers only take PNG images from the clipboard API
+                let Some(images) = pages
+                    .iter()
+                    .map(|page| page.bytes(OutputFormat::Png).map(<[u8]>::to_vec))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    self.log
+                        .error("copying images needs PNG output enabled".to_string());
+                    return true;
+                };
+                let combined = (images.len() > 1)
+                    .then(|| pdf_to_whatever::stitch_pages(&pages, OutputFormat::Png).ok())
+                    .flatten();
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let count = images.len();
+                    let (level, message) = match download::copy_images(&images).await {
+                        Ok(()) => (log::Level::Info, format!("copied {count} pages of {stem}")),
+                        Err(err) => match combined {
+                            // most browsers hold a single image at a time
+                            Some(strip) => match download::copy_images(&[strip.bytes]).await {
+                                Ok(()) => (
+                                    log::Level::Info,
+                                    format!("copied {count} pages of {stem} as one combined image"),
+                                ),
+                                Err(err) => {
+                                    (log::Level::Error, format!("failed copying pages: {err:?}"))
+                                }
+                            },
+                            None => (log::Level::Error, format!("failed copying pages: {err:?}")),
+                        },
+                    };
+                    link.send_message(Msg::Log(level, message));
+                });
+                false
+            }
+            Msg::DownloadAll(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let entries = pdf_to_whatever::formats_entries(
+                    &file.stem,
+                    &file.pages,
+                    &file.attachments,
+                    &file.data,
+                    &self.settings,
+                );
+                if let Some((filename, bytes)) = file.loose_entry(&entries, &self.settings) {
+                    download::save(bytes, filename);
+                    return false;
+                }
+                match pdf_to_whatever::zip_parts(&file.stem, entries, &self.settings) {
+                    Ok(parts) => save_parts(&parts),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating zip for {}: {err}", file.stem)),
+                }
+                false
+            }
+            Msg::ShowPreview(stem, x, y) => {
+                self.hide_preview();
+                let sharp = self.settings.sharp_previews;
+                let Some((mime_type, bytes)) = self
+                    .files
+                    .iter_mut()
+                    .find(|file| file.stem == stem)
+                    .and_then(|file| file.preview_image(sharp))
+                else {
+                    return false;
+                };
+                let url = object_url(bytes, mime_type);
+                self.preview = Some(Preview { url, x, y });
+                true
+            }
+            Msg::OpenInNewTab(stem) => {
+                let Some((mime_type, bytes)) = self
+                    .files
+                    .iter_mut()
+                    .find(|file| file.stem == stem)
+                    .and_then(|file| file.preview_image(false))
+                else {
+                    return false;
+                };
+                let url = object_url(bytes, mime_type);
+                if let Err(err) = gloo::utils::window().open_with_url_and_target(&url, "_blank") {
+                    self.log
+                        .error(format!("failed opening page 1 of {stem}: {err:?}"));
+                }
+                Timeout::new(NEW_TAB_MILLIS, move || {
+                    let _ = Url::revoke_object_url(&url);
+                })
+                .forget();
+                true
+            }
+            Msg::OpenViewer(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                let viewer = viewer::Viewer::new(stem, file.data.clone(), file.page_count);
+                self.viewer = Some(viewer);
+                ctx.link().send_message(Msg::ViewPage(1));
+                true
+            }
+            Msg::ViewPage(number) => {
+                let Some(viewer) = &mut self.viewer else {
+                    return false;
+                };
+                if let Err(err) = viewer.show(number) {
+                    self.log.error(format!(
+                        "failed showing page {number} of {}: {err}",
+                        viewer.stem
+                    ));
+                }
+                true
+            }
+            Msg::CloseViewer => self.viewer.take().is_some(),
+            Msg::OpenCrop(stem) => {
+                let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+                    return false;
+                };
+                match crop::CropPicker::new(stem.clone(), file.data.clone()) {
+                    Ok(picker) => self.crop = Some(picker),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed showing page 1 of {stem}: {err}")),
+                }
+                true
+            }
+            Msg::CropDrag(drag) => {
+                let Some(picker) = &mut self.crop else {
+                    return false;
+                };
+                if let Some(selection) = picker.drag(drag) {
+                    ctx.link().send_message(Msg::CropTo(selection));
+                }
+                true
+            }
+            Msg::CropTo(crop) => {
+                let mut settings = self.settings.clone();
+                settings.crop = crop;
+                ctx.link().send_message(Msg::Settings(Box::new(settings)));
+                false
+            }
+            Msg::CloseCrop => self.crop.take().is_some(),
+            Msg::MovePreview(x, y) => match &mut self.preview {
+                Some(preview) => {
+                    preview.x = x;
+                    preview.y = y;
+                    true
+                }
+                None => false,
+            },
+            Msg::HidePreview => {
+                self.hide_preview();
+                true
+            }
+            Msg::DownloadStrip(format) => {
+                let documents: Vec<_> = self
+                    .files
+                    .iter()
+                    .map(|file| (file.stem.as_str(), file.pages.as_slice()))
+                    .collect();
+                match pdf_to_whatever::stitch_documents(&documents, format) {
+                    Ok(strip) => {
+                        if let Some((width, height)) = strip.downscaled_from {
+                            self.log.error(format!(
+                                "all pages together are too large at {width}x{height}, the combined image was scaled down"
+                            ));
+                        }
+                        download::save(&strip.bytes, &format!("all-pages.{}", format.extension()));
+                    }
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating combined image: {err}")),
+                }
+                true
+            }
+            Msg::DownloadPartial(format) => {
+                let files: Vec<_> = self
+                    .files
+                    .iter()
+                    .filter(|file| self.batch.contains(&file.stem))
+                    .collect();
+                match self.documents_zip(&files, format) {
+                    Ok(zip) => download::save(&zip, &format!("partial-{}.zip", format.extension())),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating partial zip: {err}")),
+                }
+                false
+            }
+            Msg::DownloadEverything(format) => {
+                let files: Vec<_> = self.files.iter().collect();
+                match self.documents_zip(&files, format) {
+                    Ok(zip) => download::save(&zip, &format!("all-{}.zip", format.extension())),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating zip of every document: {err}")),
+                }
+                false
+            }
+            Msg::DownloadInterleaved(format) => {
+                if !self.settings.formats.contains(&format) {
+                    return false;
+                }
+                let documents: Vec<_> = self
+                    .files
+                    .iter()
+                    .map(|file| (file.stem.as_str(), file.pages.as_slice()))
+                    .collect();
+                match pdf_to_whatever::zip_interleaved(&documents, format, &self.settings) {
+                    Ok(zip) => download::save(&zip, "interleaved.zip"),
+                    Err(err) => self
+                        .log
+                        .error(format!("failed creating interleaved zip: {err}")),
+                }
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+        <>
+        <main>
+            <h1>{CARGO_PKG_NAME}</h1>
+            <p>{CARGO_PKG_DESCRIPTION}</p>
+            { settings::view_settings(ctx, &self.settings) }
+            { self.view_banner(ctx) }
+            if let Some(warning) = &self.memory_warning {
+                <div id="memory-warning">
+                    <span>{warning}</span>
+                    <button onclick={ctx.link().callback(|_| Msg::DismissMemoryWarning)}>
+                        {"Dismiss"}
+                    </button>
+                </div>
+            }
+            <div
+                id="file-pick"
+                ondrop={ctx.link().callback(|e: DragEvent| {
+                    e.prevent_default();
+                    // a drop without files only ends the drag
+                    let Some(transfer) = e.data_transfer() else {
+                        return Msg::DragLeave;
+                    };
+                    match folders::dropped_folders(&transfer) {
+                        Some(entries) => Msg::DropFolders(entries),
+                        None => transfer.files().map_or(Msg::DragLeave, Msg::Upload),
+                    }
+                })}
+                class={self.dragging.is_some().then_some("hovered")}
+                ondragenter={ctx.link().callback(|e: DragEvent| {
+                    e.prevent_default();
+                    Msg::DragEnter(DragSummary::of(&e))
+                })}
+                ondragover={Callback::from(|e: DragEvent| {
+                    e.prevent_default();
+                })}
+                ondragleave={ctx.link().batch_callback(|e: DragEvent| {
+                    e.prevent_default();
+                    // moving onto a child of the drop zone fires a leave on the zone itself
+                    let zone: Element = e.current_target()?.dyn_into().ok()?;
+                    let Some(entered) = e.related_target() else {
+                        // Safari doesn't say what was entered, so go by where the pointer is
+                        let rect = zone.get_bounding_client_rect();
+                        let (x, y) = (f64::from(e.client_x()), f64::from(e.client_y()));
+                        let inside = (rect.left()..rect.right()).contains(&x)
+                            && (rect.top()..rect.bottom()).contains(&y);
+                        return (!inside).then_some(Msg::DragLeave);
+                    };
+                    let entered = entered.dyn_into::<Node>().ok();
+                    (!zone.contains(entered.as_ref())).then_some(Msg::DragLeave)
+                })}
+            >
+                <div style="margin-bottom: .5em">
+                    if let Some(summary) = &self.dragging {
+                        { summary.to_string() }
+                    } else {
+                        {"Drop your documents here, or images to combine into a PDF. Click to select or press O"}
+                    }
+                </div>
+                <input
+                    ref={self.file_input.clone()}
+                    type="file"
+                    accept="application/pdf,application/zip,.zip,image/png,image/jpeg"
+                    multiple=true
+                    onchange={ctx.link().callback(|e: Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Msg::Upload(input.files().expect("must be some files"))
+                    })}
+                />
+            </div>
+            if !self.readers.is_empty() || !self.progress.is_empty() {
+                <div id="in-progress">
+                    {format!("{} in progress", self.readers.len())}
+                    if self.paused {
+                        {", paused"}
+                    }
+                    { for self.progress.iter().map(|(stem, progress)| {
+                        let cancel_stem = stem.clone();
+                        html! {
+                            <span class="progress">
+                                <label>
+                                    {stem}
+                                    <progress
+                                        max={progress.total.to_string()}
+                                        value={progress.done.to_string()}
+                                    />
+                                </label>
+                                if let Some(secs) = progress.remaining_secs().filter(|_| !self.paused) {
+                                    <span>{format!("~{secs:.0} s remaining")}</span>
+                                }
+                                <button
+                                    aria-label={format!("Cancel {stem}")}
+                                    onclick={ctx.link().callback(move |_| Msg::Cancel(cancel_stem.clone()))}
+                                >
+                                    {"Cancel"}
+                                </button>
+                            </span>
+                        }
+                    }) }
+                    if self.paused {
+                        <button onclick={ctx.link().callback(|_| Msg::Pause(false))}>{"Resume"}</button>
+                    } else {
+                        <button onclick={ctx.link().callback(|_| Msg::Pause(true))}>{"Pause"}</button>
+                    }
+                    <button onclick={ctx.link().callback(|_| Msg::CancelAll)}>{"Cancel all"}</button>
+                </div>
+            }
+            { images::view_images(ctx, &self.images) }
+            { self.view_partial(ctx) }
+            { self.view_everything(ctx) }
+            { self.view_interleave(ctx) }
+            { self.view_strip(ctx) }
+            { self.view_session(ctx) }
+            if !self.files.is_empty() || !self.failed.is_empty() {
+                <button id="clear-all" onclick={ctx.link().callback(|_| Msg::ClearAll)}>
+                    {"Clear all"}
+                </button>
+            }
+            if let Some(cleared) = &self.cleared {
+                <div id="undo-clear">
+                    {format!("Cleared {} results.", cleared.files.len() + cleared.failed.len())}
+                    <button onclick={ctx.link().callback(|_| Msg::UndoClear)}>{"Undo"}</button>
+                </div>
+            }
+            <ul id="processed" aria-label="Results">
+                { for self.files.iter().map(|file| self.view_file(ctx, file)) }
+                { for self.failed.iter().map(|file| self.view_failed(ctx, file)) }
+            </ul>
+            if let Some(viewer) = &self.viewer {
+                { viewer.view(ctx) }
+            }
+            if let Some(picker) = &self.crop {
+                { picker.view(ctx, self.settings.crop) }
+            }
+            { self.log.view() }
+        </main>
+        if let Some(preview) = &self.preview {
+            <img
+                id="preview"
+                src={preview.url.clone()}
+                style={format!("left: {}px; top: {}px", preview.x + 16, preview.y + 16)}
+            />
+        }
+        <footer>
+            {"Created by "}
+            <a href="https://samake.se" target="_blank">
+                {"Samuel \"sermuns\" Åkesson"}
+            </a>
+        </footer>
+        </>
+        }
+    }
+
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if !std::mem::take(&mut self.focus_newest) {
+            return;
+        }
+        // don't pull focus away from someone typing, e.g. into a settings field
+        let typing = gloo::utils::document()
+            .active_element()
+            .is_some_and(|element| accepts_typing(&element));
+        if let Some(result) = self.newest_ref.cast::<HtmlElement>()
+            && !typing
+        {
+            let _ = result.focus();
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        // every other URL is revoked by its owner when it is dropped, previews are only
+        // revoked when their file is replaced or cleared
+        self.hide_preview();
+        for file in &mut self.files {
+            if let Some(url) = file.preview_url.take() {
+                let _ = Url::revoke_object_url(&url);
+            }
+        }
+    }
+}
+
+impl App {
+    /// `stem`, or if a file of that name is already being converted or has been, the first of
+    /// `stem-2`, `stem-3` and so on that is free, so neither replaces the other. Results that
+    /// were cleared but can still be restored keep their names too.
+    fn unique_stem(&mut self, stem: &str) -> String {
+        let unique = first_free(stem, |candidate| {
+            self.readers.contains_key(candidate)
+                || self.progress.contains_key(candidate)
+                || self.batch.iter().any(|other| other == candidate)
+                || self.files.iter().any(|file| file.stem == candidate)
+                || self.failed.iter().any(|file| file.stem == candidate)
+                || self
+                    .cleared
+                    .iter()
+                    .flat_map(|cleared| &cleared.files)
+                    .any(|file| file.stem == candidate)
+                || self
+                    .cleared
+                    .iter()
+                    .flat_map(|cleared| &cleared.failed)
+                    .any(|file| file.stem == candidate)
+        });
+        if unique == stem {
+            return unique;
+        }
+        self.log.info(format!(
+            "renamed {stem} to {unique}, another file has the same name"
+        ));
+        unique
+    }
+
+    /// Whether a file with `stem` and `size` bytes is being converted or has a result, which it
+    /// likely is again when dropped with the same name and size.
+    fn is_listed(&self, stem: &str, size: u64) -> bool {
+        let size = size as usize;
+        self.readers
+            .get(stem)
+            .is_some_and(|task| task.size as usize == size)
+            || self
+                .files
+                .iter()
+                .any(|file| file.stem == stem && file.data.len() == size)
+            || self
+                .failed
+                .iter()
+                .any(|file| file.stem == stem && file.data.len() == size)
+    }
+
+    /// Cancel the conversion of `stem` and drop its failure, so a file dropped in its place can
+    /// take its name. A converted result stays until the new one replaces it.
+    fn forget(&mut self, stem: &str) {
+        if let Some(task) = self.readers.remove(stem) {
+            task.cancelled.set(true);
+        }
+        self.progress.remove(stem);
+        self.failed.retain(|file| file.stem != stem);
+        self.batch.retain(|other| other != stem);
+    }
+
+    /// The settings the file with `stem` is converted with, the global ones unless some are
+    /// overridden for it.
+    fn settings_for(&self, stem: &str) -> Settings {
+        match self.overrides.get(stem) {
+            Some(overrides) => overrides.apply(&self.settings),
+            None => self.settings.clone(),
+        }
+    }
+
+    /// Notify about the most recent upload once every file of it has been converted or has
+    /// failed, with `settings.notify_when_done`.
+    fn notify_if_done(&mut self) {
+        let done = |stem: &String| {
+            self.files.iter().any(|file| &file.stem == stem)
+                || self.failed.iter().any(|file| &file.stem == stem)
+        };
+        if !self.settings.notify_when_done
+            || self.batch_notified
+            || self.batch.is_empty()
+            || !self.readers.is_empty()
+            || !self.batch.iter().all(done)
+        {
+            return;
+        }
+        self.batch_notified = true;
+        let failed = self
+            .batch
+            .iter()
+            .filter(|stem| self.failed.iter().any(|file| &file.stem == *stem))
+            .count();
+        let converted = self.batch.len() - failed;
+        let body = if failed == 0 {
+            format!(
+                "Converted {converted} {}.",
+                if converted == 1 { "file" } else { "files" }
+            )
+        } else {
+            format!("Converted {converted} files, {failed} failed.")
+        };
+        notify::notify("Conversion done", &body);
+    }
+
+    fn reconvert(&mut self, ctx: &Context<Self>, stem: &str, settings: &Settings) -> bool {
+        let Some(file) = self.files.iter().find(|file| file.stem == stem) else {
+            return false;
+        };
+        let (pdf_human_size, data) = (file.pdf_human_size.clone(), file.data.clone());
+        let budget = Rc::new(memory::PixelBudget::new(settings));
+        self.start(
+            ctx,
+            stem.to_string(),
+            pdf_human_size,
+            data,
+            settings,
+            budget,
+        );
+        true
+    }
+
+    /// Convert `data` as `stem` like [`process`], tracked with the files being read so it can
+    /// be cancelled and counts as in progress. Cancels a conversion of `stem` already running,
+    /// so only the newest result arrives.
+    fn start(
+        &mut self,
+        ctx: &Context<Self>,
+        stem: String,
+        pdf_human_size: String,
+        data: Arc<Vec<u8>>,
+        settings: &Settings,
+        budget: Rc<memory::PixelBudget>,
+    ) {
+        let cancelled = Rc::new(Cell::new(false));
+        let task = Task {
+            _reader: None,
+            size: data.len() as u64,
+            cancelled: cancelled.clone(),
+        };
+        track(&mut self.readers, stem.clone(), task);
+        process(
+            ctx.link(),
+            stem,
+            pdf_human_size,
+            data,
+            settings,
+            cancelled,
+            budget,
+        );
+    }
+
+    /// One zip of `files` in `format`, every file's own entries in a folder named after it, and
+    /// a contact sheet of them first with `settings.zip_preview`.
+    fn documents_zip(
+        &self,
+        files: &[&RenderedImage],
+        format: OutputFormat,
+    ) -> Result<Vec<u8>, ConversionError> {
+        let preview = if self.settings.zip_preview {
+            let covers: Vec<_> = files.iter().filter_map(|file| file.pages.first()).collect();
+            Some(pdf_to_whatever::preview_entry(&covers)?)
+        } else {
+            None
+        };
+        let entries = preview.into_iter().chain(files.iter().flat_map(|file| {
+            pdf_to_whatever::document_entries(
+                &file.stem,
+                &file.pages,
+                &file.attachments,
+                &file.data,
+                format,
+                &self.settings,
+            )
+            .into_iter()
+            .map(|(filename, bytes)| (format!("{}/{filename}", file.stem), bytes))
+        }));
+        pdf_to_whatever::zip_entries(entries, &self.settings)
+    }
+
+    /// Start waiting `settings.idle_clear_minutes` for [`Msg::Idle`] again, or stop waiting
+    /// when results are kept.
+    fn restart_idle_timeout(&mut self, ctx: &Context<Self>) {
+        self.idle_timeout = (!self.settings.keep_results).then(|| {
+            let link = ctx.link().clone();
+            let millis = self.settings.idle_clear_minutes.saturating_mul(60_000);
+            Timeout::new(millis, move || link.send_message(Msg::Idle))
+        });
+    }
+
+    fn hide_preview(&mut self) {
+        if let Some(preview) = self.preview.take() {
+            let _ = Url::revoke_object_url(&preview.url);
+        }
+    }
+
+    fn view_banner(&self, ctx: &Context<Self>) -> Html {
+        if self.banner_dismissed || self.batch.is_empty() {
+            return html! {};
+        }
+        let in_batch = |stem: &String| self.batch.contains(stem);
+        let succeeded = self
+            .files
+            .iter()
+            .filter(|file| in_batch(&file.stem))
+            .count();
+        let failed = self
+            .failed
+            .iter()
+            .filter(|file| in_batch(&file.stem))
+            .count();
+        if succeeded + failed == 0 {
+            return html! {};
+        }
+        html! {
+            <div id="banner" class={if failed > 0 { "failed" } else { "" }}>
+                <span>{format!("{succeeded} succeeded, {failed} failed")}</span>
+                if failed > 0 {
+                    <button onclick={ctx.link().callback(|_| Msg::RetryAll)}>
+                        {"Retry all failed"}
+                    </button>
+                }
+                <button onclick={ctx.link().callback(|_| Msg::DismissBanner)}>{"Dismiss"}</button>
+            </div>
+        }
+    }
+
+    /// After cancelling, offer everything the upload had converted so far in one zip.
+    fn view_partial(&self, ctx: &Context<Self>) -> Html {
+        let converted = self
+            .files
+            .iter()
+            .filter(|file| self.batch.contains(&file.stem))
+            .count();
+        if self.cancelled == 0 || converted == 0 {
+            return html! {};
+        }
+        html! {
+            <div id="partial">
+                {format!(
+                    "Cancelled {} {}, {converted} converted before:",
+                    self.cancelled,
+                    if self.cancelled == 1 { "file" } else { "files" }
+                )}
+                { for self.settings.formats.iter().copied().map(|format| html! {
+                    <button class="download" onclick={ctx.link().callback(move |_| Msg::DownloadPartial(format))}>
+                        <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                        {format!("{} (partial)", format.label())}
+                    </button>
+                }) }
+            </div>
+        }
+    }
+
+    fn view_everything(&self, ctx: &Context<Self>) -> Html {
+        if self.files.len() < 2 {
+            return html! {};
+        }
+        html! {
+            <div id="everything">
+                {"All documents, a folder each:"}
+                { for self.settings.formats.iter().copied().map(|format| html! {
+                    <button class="download" onclick={ctx.link().callback(move |_| Msg::DownloadEverything(format))}>
+                        <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                        {format.label()}
+                    </button>
+                }) }
+                if self.settings.formats.is_empty() {
+                    <button disabled=true>{"Enable an output format to download"}</button>
+                }
+            </div>
+        }
+    }
+
+    fn view_interleave(&self, ctx: &Context<Self>) -> Html {
+        if self.files.len() < 2 {
+            return html! {};
+        }
+        html! {
+            <div id="interleave">
+                {"All documents, interleaved by page:"}
+                { for self.settings.formats.iter().copied().map(|format| html! {
+                    <button class="download" onclick={ctx.link().callback(move |_| Msg::DownloadInterleaved(format))}>
+                        <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                        {format.label()}
+                    </button>
+                }) }
+                if self.settings.formats.is_empty() {
+                    <button disabled=true>{"Enable an output format to download"}</button>
+                }
+            </div>
+        }
+    }
+
+    fn view_strip(&self, ctx: &Context<Self>) -> Html {
+        if self.files.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div id="strip">
+                {"All pages as one tall image:"}
+                { for self.settings.formats.iter().copied().map(|format| html! {
+                    <button class="download" onclick={ctx.link().callback(move |_| Msg::DownloadStrip(format))}>
+                        <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                        {format.label()}
+                    </button>
+                }) }
+            </div>
+        }
+    }
+
+    fn view_session(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div id="session">
+                <button
+                    disabled={self.files.is_empty() && self.failed.is_empty()}
+                    onclick={ctx.link().callback(|_| Msg::ExportSession)}
+                >
+                    {"Export session"}
+                </button>
+                <button
+                    disabled={self.files.is_empty() && self.failed.is_empty()}
+                    onclick={ctx.link().callback(|_| Msg::ExportSummary)}
+                >
+                    {"Export summary"}
+                </button>
+                <button
+                    disabled={self.files.is_empty() && self.failed.is_empty()}
+                    onclick={ctx.link().callback(|_| Msg::ReconvertAll)}
+                >
+                    {"Convert all again"}
+                </button>
+                <label>
+                    {"Import session "}
+                    <input
+                        type="file"
+                        accept="application/zip,.zip"
+                        onchange={ctx.link().callback(|e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            Msg::ImportSession(input.files().expect("must be some files"))
+                        })}
+                    />
+                </label>
+            </div>
+        }
+    }
+
+    /// The ref for the result of `stem`, so focus can be moved to it if it is the newest.
+    fn result_ref(&self, stem: &str) -> NodeRef {
+        if self.newest.as_deref() == Some(stem) {
+            self.newest_ref.clone()
+        } else {
+            NodeRef::default()
+        }
+    }
+
+    fn view_failed(&self, ctx: &Context<Self>, file: &FailedFile) -> Html {
+        let stem = file.stem.clone();
+        html! {
+            <li
+                class="result"
+                ref={self.result_ref(&file.stem)}
+                tabindex="-1"
+                aria-label={format!("{}, failed", file.stem)}
+            >
+                <div>{ &file.stem }</div>
+                <div>{ &file.pdf_human_size }</div>
+                <div class="error" role="alert">{ file.error.to_string() }</div>
+                if !matches!(file.error, ConversionError::ReadFailed(_)) {
+                    <button
+                        aria-label={format!("Retry {}", file.stem)}
+                        onclick={ctx.link().callback(move |_| Msg::Retry(stem.clone()))}
+                    >
+                        {"Retry"}
+                    </button>
+                }
+            </li>
+        }
+    }
+
+    fn view_warnings(file: &RenderedImage) -> Html {
+        let count: usize = file.pages.iter().map(|page| page.warnings.len()).sum();
+        if count == 0 {
+            return html! {};
+        }
+        html! {
+            <details class="note">
+                <summary>
+                    {format!("{count} rendering {}", if count == 1 { "warning" } else { "warnings" })}
+                </summary>
+                <ul>
+                    { for file.pages.iter().flat_map(|page| page.warnings.iter().map(|warning| html! {
+                        <li>{format!("{}: {warning}", page.label())}</li>
+                    })) }
+                </ul>
+            </details>
+        }
+    }
+
+    /// The size of every rendered format side by side, the smallest emphasized, to help pick one.
+    fn view_format_sizes(file: &RenderedImage) -> Html {
+        let sizes = file.format_sizes();
+        if sizes.len() < 2 || file.pages.is_empty() {
+            return html! {};
+        }
+        let smallest = sizes.iter().map(|(_, size)| *size).min();
+        html! {
+            <div class="format-sizes">
+                { for sizes.iter().enumerate().map(|(index, &(format, size))| {
+                    let text = format!("{} {}", format.label(), format_size(size, humansize::BINARY));
+                    html! {
+                        <>
+                            if index > 0 {
+                                {" · "}
+                            }
+                            if Some(size) == smallest {
+                                <strong>{text}</strong>
+                            } else {
+                                {text}
+                            }
+                        </>
+                    }
+                }) }
+            </div>
+        }
+    }
+
+    fn view_file(&self, ctx: &Context<Self>, file: &RenderedImage) -> Html {
+        let stem = file.stem.clone();
+        let full_stem = file.stem.clone();
+        let reconvert_stem = file.stem.clone();
+        let copy_stem = file.stem.clone();
+        let all_stem = file.stem.clone();
+        let viewer_stem = file.stem.clone();
+        let crop_stem = file.stem.clone();
+        let html_stem = file.stem.clone();
+        let web_stem = file.stem.clone();
+        let iiif_stem = file.stem.clone();
+        let pdfa_stem = file.stem.clone();
+        let rasterized_stem = file.stem.clone();
+        let svg_stem = file.stem.clone();
+        let sprite_stem = file.stem.clone();
+        let apng_stem = file.stem.clone();
+        let tab_stem = file.stem.clone();
+        let thumbnails_stem = file.stem.clone();
+        let description = file.description();
+        let sizes = file.format_sizes();
+        html! {
+            <li
+                class="result"
+                ref={self.result_ref(&file.stem)}
+                tabindex="-1"
+                aria-label={description.clone()}
+            >
+                <div
+                    class="stem"
+                    onmouseenter={ctx.link().callback(move |e: MouseEvent| {
+                        Msg::ShowPreview(stem.clone(), e.client_x(), e.client_y())
+                    })}
+                    onmousemove={ctx.link().callback(|e: MouseEvent| {
+                        Msg::MovePreview(e.client_x(), e.client_y())
+                    })}
+                    onmouseleave={ctx.link().callback(|_| Msg::HidePreview)}
+                >
+                    { &file.stem }
+                </div>
+                <div>
+                    { &file.pdf_human_size }
+                    <span class="dimensions">{ file.dimensions() }</span>
+                </div>
+                if self.settings.single_archive {
+                    <button
+                        class="download"
+                        style={format!("grid-column: span {}", OutputFormat::ALL.len())}
+                        aria-label={format!("Download archive of all formats for {description}")}
+                        onclick={ctx.link().callback(move |_| Msg::DownloadAll(all_stem.clone()))}
+                    >
+                        <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                        {"All formats"}
+                    </button>
+                } else {
+                    { for OutputFormat::ALL.into_iter().map(|format| {
+                        let stem = file.stem.clone();
+                        let rendered = file.pages.first().is_some_and(|page| page.bytes(format).is_some());
+                        let size = sizes.iter().find(|(sized, _)| *sized == format).map(|(_, size)| *size);
+                        html! {
+                            // keep the grid aligned when a format wasn't rendered
+                            if rendered {
+                                <button
+                                    class="download"
+                                    title={size.map(|size| format!("{} of images", format_size(size, humansize::BINARY)))}
+                                    aria-label={format!("Download {} {} for {description}", format.label(), if file.page_count == 1 && !self.settings.always_zip { "image" } else { "archive" })}
+                                    onclick={ctx.link().callback(move |_| Msg::Download(stem.clone(), format))}
+                                >
+                                    <img src="download-1-svgrepo-com.svg" width="10" height="15" alt="" />
+                                    {format.label()}
+                                </button>
+                            } else {
+                                <div aria-hidden="true" />
+                            }
+                        }
+                    }) }
+                }
+                <button
+                    title="Convert again with the current settings"
+                    aria-label={format!("Convert {} again with the current settings", file.stem)}
+                    onclick={ctx.link().callback(move |_| Msg::Reconvert(reconvert_stem.clone()))}
+                >
+                    {"Convert again"}
+                </button>
+                <button
+                    aria-label={format!("View {} page by page", file.stem)}
+                    onclick={ctx.link().callback(move |_| Msg::OpenViewer(viewer_stem.clone()))}
+                >
+                    {"View"}
+                </button>
+                <button
+                    aria-label={format!("Pick a region of {} to crop every page to", file.stem)}
+                    onclick={ctx.link().callback(move |_| Msg::OpenCrop(crop_stem.clone()))}
+                >
+                    {"Crop"}
+                </button>
+                <button
+                    aria-label={format!("Open page 1 of {} in a new tab", file.stem)}
+                    onclick={ctx.link().callback(move |_| Msg::OpenInNewTab(tab_stem.clone()))}
+                >
+                    {"Open in new tab"}
+                </button>
+                { Self::view_format_sizes(file) }
+                if let Some((name, _)) = &file.saved_to {
+                    <div class="note">
+                        {format!("Written to {name} while converting. Its pages weren't kept, so they can't be viewed or downloaded here.")}
+                    </div>
+                }
+                <div class="note">
+                    { match file.kind {
+                        DocumentKind::Scanned => "Looks like a scan: JPEG at a higher DPI usually suits it best.",
+                        DocumentKind::BornDigital => "Looks born-digital: PNG keeps its text and lines crisp.",
+                    } }
+                </div>
+                if file.has_form_values {
+                    <div class="note error">
+                        {"Contains filled form fields. Their values can't be rendered and are missing from the images."}
+                    </div>
+                }
+                if file.uses_cmyk {
+                    <div class="note">
+                        {"Contains CMYK colors, which were converted to RGB. Colors may differ from print."}
+                    </div>
+                }
+                if !file.blank_pages.is_empty() {
+                    <div class="note">
+                        {format!("Left out {} blank {}.", file.blank_pages.len(), if file.blank_pages.len() == 1 { "page" } else { "pages" })}
+                    </div>
+                }
+                if !file.substituted_fonts.is_empty() {
+                    <div class="note">
+                        {format!("Fonts that aren't embedded were drawn with the substitute font instead of {}.", file.substituted_fonts.join(", "))}
+                    </div>
+                }
+                if !file.layers.is_empty() {
+                    <div class="note">
+                        {"Has layers, rendered as the document shows them by default: "}
+                        {file.layers.iter().map(|layer| if layer.visible {
+                            layer.name.clone()
+                        } else {
+                            format!("{} (hidden)", layer.name)
+                        }).collect::<Vec<_>>().join(", ")}
+                        {"."}
+                    </div>
+                }
+                if let Some(selected) = file.truncated_from {
+                    <div class="note">
+                        {format!("Showing the first {} of {selected} pages.", file.rendered_pages() + file.blank_pages.len())}
+                    </div>
+                }
+                { match file.search {
+                    Some(SearchResult::Matched(count)) => html! {
+                        <div class="note">
+                            {format!("{count} {} matched the search.", if count == 1 { "page" } else { "pages" })}
+                        </div>
+                    },
+                    Some(SearchResult::NoText) => html! {
+                        <div class="note error">
+                            {"Has no text to search, scanned pages need OCR first. No pages were rendered."}
+                        </div>
+                    },
+                    None => html! {},
+                } }
+                if !file.attachments.is_empty() {
+                    <div class="note">
+                        {format!(
+                            "Has {} {}: {}.",
+                            file.attachments.len(),
+                            if file.attachments.len() == 1 { "attachment" } else { "attachments" },
+                            file.attachments.iter().map(|attachment| attachment.name.as_str()).collect::<Vec<_>>().join(", ")
+                        )}
+                    </div>
+                }
+                if file.pages.iter().any(|page| page.downscaled_from.is_some()) {
+                    <div class="note">
+                        {"Some pages were too large to render at the chosen size and were scaled down."}
+                    </div>
+                }
+                { Self::view_warnings(file) }
+                <details
+                    class="thumbnails"
+                    ontoggle={ctx.link().callback(move |_| Msg::ShowThumbnails(thumbnails_stem.clone()))}
+                >
+                    <summary>{"Thumbnails"}</summary>
+                    <ol>
+                        { for file.pages.iter().zip(&file.thumbnails).map(|(page, uri)| {
+                            let stem = file.stem.clone();
+                            let number = page.number;
+                            html! {
+                                <li>
+                                    <button
+                                        class="download"
+                                        title={format!("Download {}", page.label().to_lowercase())}
+                                        onclick={ctx.link().callback(move |_| Msg::DownloadPage(stem.clone(), number))}
+                                    >
+                                        <img src={uri.clone()} alt={page.label()} loading="lazy" />
+                                    </button>
+                                </li>
+                            }
+                        }) }
+                    </ol>
+                </details>
+                <details class="pages">
+                    <summary>{"Pages"}</summary>
+                    <ol>
+                        { for file.pages.iter().map(|page| {
+                            let stem = file.stem.clone();
+                            let number = page.number;
+                            let selected = self.selected_pages.contains(&(stem.clone(), number));
+                            let onchange = {
+                                let stem = stem.clone();
+                                ctx.link().callback(move |e: Event| {
+                                    let input: HtmlInputElement = e.target_unchecked_into();
+                                    Msg::SelectPage(stem.clone(), number, input.checked())
+                                })
+                            };
+                            html! {
+                                <li>
+                                    <label>
+                                        <input type="checkbox" checked={selected} {onchange} />
+                                        {page.label()}
+                                        {format!(" ({}×{} px)", page.dimensions.0, page.dimensions.1)}
+                                    </label>
+                                    <button
+                                        aria-label={format!("Copy data URI of {} of {stem}", page.label().to_lowercase())}
+                                        onclick={ctx.link().callback(move |_| Msg::CopyDataUri(stem.clone(), number))}
+                                    >
+                                        {"Copy data URI"}
+                                    </button>
+                                </li>
+                            }
+                        }) }
+                    </ol>
+                    <button
+                        disabled={!file.pages.iter().any(|page| self.selected_pages.contains(&(file.stem.clone(), page.number)))}
+                        onclick={ctx.link().callback(move |_| Msg::CopySelected(copy_stem.clone()))}
+                    >
+                        {"Copy selected pages"}
+                    </button>
+                    <button
+                        title="Every page as <img> tags with the images inline"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadHtml(html_stem.clone()))}
+                    >
+                        {"Download as HTML"}
+                    </button>
+                    <button
+                        title="A zip with a web page scrolling through every page, loading the images as they come into view"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadWebPage(web_stem.clone()))}
+                    >
+                        {"Download as web page"}
+                    </button>
+                    <button
+                        title="A zip with every page and a IIIF Presentation manifest, to serve from the IIIF base URL"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadIiif(iiif_stem.clone()))}
+                    >
+                        {"Download for IIIF"}
+                    </button>
+                    <button
+                        title="Every page as an image in an archival PDF/A-2b document"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadPdfa(pdfa_stem.clone()))}
+                    >
+                        {"Download as PDF/A"}
+                    </button>
+                    <button
+                        title="Every page as a JPEG in a PDF, flattening away its text, forms and scripts"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadRasterized(rasterized_stem.clone()))}
+                    >
+                        {"Download as rasterized PDF"}
+                    </button>
+                    <button
+                        title="A zip with every page as an SVG at its printed size, wrapping the page image, so its text can't be selected"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadSvg(svg_stem.clone()))}
+                    >
+                        {"Download as SVG"}
+                    </button>
+                    <button
+                        title="Every page packed into PNG sprite sheets, with a JSON file locating each page"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadSprites(sprite_stem.clone()))}
+                    >
+                        {"Download sprite sheet"}
+                    </button>
+                    <button
+                        title="Every page as a frame of one animated PNG in full color, which gets large for documents with many pages"
+                        onclick={ctx.link().callback(move |_| Msg::DownloadApng(apng_stem.clone()))}
+                    >
+                        {"Download as animated PNG"}
+                    </button>
+                </details>
+                { overrides::view_overrides(
+                    ctx,
+                    &file.stem,
+                    &self.overrides.get(&file.stem).cloned().unwrap_or_default(),
+                    &self.settings,
+                ) }
+                if let Some(url) = &file.preview_url {
+                    <div class="first-page">
+                        <img src={url.clone()} alt={format!("{}, page 1", file.stem)} />
+                        <button onclick={ctx.link().callback(move |_| Msg::RenderAllPages(full_stem.clone()))}>
+                            {format!("Render all {} pages", file.page_count)}
+                        </button>
+                    </div>
+                }
+            </li>
+        }
+    }
+}
+
+/// Convert a file that has finished reading and report the outcome back to the component,
+/// unless the task was cancelled in the meantime. With `settings.responsive_rendering` the
+/// browser gets to paint and handle clicks between pages, and progress is reported after each.
+fn process(
+    link: &Scope<App>,
+    stem: String,
+    pdf_human_size: String,
+    data: Arc<Vec<u8>>,
+    settings: &Settings,
+    cancelled: Rc<Cell<bool>>,
+    budget: Rc<memory::PixelBudget>,
+) {
+    if cancelled.get() {
+        return;
+    }
+    let link = link.clone();
+    let settings = settings.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        // documents converted in one go can only be held back before they start
+        wait_while_paused(&link, &cancelled).await;
+        let now = Instant::now();
+        let result = if settings.background_workers {
+            convert_in_worker(&link, &stem, &data, &settings, &cancelled, &budget).await
+        } else if settings.responsive_rendering {
+            convert_between_frames(&link, &stem, data.clone(), &settings, &cancelled, &budget).await
+        } else {
+            pdf_to_whatever::convert(data.clone(), &stem, &settings).map(Some)
+        };
+        let render_secs = now.elapsed().as_secs_f32();
+        if cancelled.get() {
+            return;
+        }
+        match result {
+            // only cancelled conversions stop short
+            Ok(None) => {}
+            Ok(Some(converted)) => link.send_message(Msg::Render(Box::new(RenderedImage::new(
+                stem,
+                pdf_human_size,
+                data,
+                converted,
+                render_secs,
+                settings.first_page_only,
+            )))),
+            Err(error) => link.send_message(Msg::Fail(FailedFile {
+                stem,
+                pdf_human_size,
+                data,
+                error,
+            })),
+        }
+    });
+}
+
+/// Convert a file like [`process`], but write its zip into the file the user is picking with
+/// `picked` a page at a time, instead of keeping the pages. Converts in memory after all if the
+/// file can't be opened, like when the picker was closed.
+fn stream_to_file(
+    link: &Scope<App>,
+    stem: String,
+    pdf_human_size: String,
+    data: Arc<Vec<u8>>,
+    settings: &Settings,
+    cancelled: Rc<Cell<bool>>,
+    picked: Promise,
+) {
+    let link = link.clone();
+    let settings = settings.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let (name, file) = match download::open_file(picked).await {
+            Ok(opened) => opened,
+            Err(err) => {
+                let message =
+                    format!("couldn't write {stem} to disk, converting it in memory: {err:?}");
+                link.send_message(Msg::Log(log::Level::Error, message));
+                // a document written to disk is uploaded on its own, so it has the budget to itself
+                let budget = Rc::new(memory::PixelBudget::new(&settings));
+                process(
+                    &link,
+                    stem,
+                    pdf_human_size,
+                    data,
+                    &settings,
+                    cancelled,
+                    budget,
+                );
+                return;
+            }
+        };
+        let now = Instant::now();
+        let result =
+            write_between_frames(&link, &stem, data.clone(), &settings, &cancelled, &file).await;
+        let render_secs = now.elapsed().as_secs_f32();
+        match result {
+            Ok(Some((converted, pages))) => {
+                link.send_message(Msg::Render(Box::new(RenderedImage {
+                    saved_to: Some((name, pages)),
+                    ..RenderedImage::new(
+                        stem,
+                        pdf_human_size,
+                        data,
+                        converted,
+                        render_secs,
+                        settings.first_page_only,
+                    )
+                })));
+            }
+            result => {
+                // discards what was written so far
+                let _ = JsFuture::from(file.abort()).await;
+                if let Err(error) = result
+                    && !cancelled.get()
+                {
+                    link.send_message(Msg::Fail(FailedFile {
+                        stem,
+                        pdf_human_size,
+                        data,
+                        error,
+                    }));
+                }
+            }
+        }
+    });
+}
+
+/// Convert a document like [`convert_between_frames`], appending every finished page to a zip
+/// in `file` and dropping it. Returns what is left of the conversion and how many pages were
+/// written, and closes the file.
+async fn write_between_frames(
+    link: &Scope<App>,
+    stem: &str,
+    data: Arc<Vec<u8>>,
+    settings: &Settings,
+    cancelled: &Cell<bool>,
+    file: &FileSystemWritableFileStream,
+) -> Result<Option<(Converted, usize)>, ConversionError> {
+    let format = settings
+        .formats
+        .first()
+        .copied()
+        .unwrap_or(OutputFormat::Png);
+    let failed = |err: JsValue| ConversionError::ArchiveFailed(format!("writing failed: {err:?}"));
+    let mut zip = ZipStream::default();
+    let mut written = 0;
+    let mut conversion = Conversion::new(data, stem, settings)?;
+    while !conversion.is_done() {
+        let (done, total) = conversion.progress();
+        link.send_message(Msg::Progress(stem.to_string(), done, total));
+        yield_to_browser().await;
+        wait_while_paused(link, cancelled).await;
+        if cancelled.get() {
+            return Ok(None);
+        }
+        conversion.render_next()?;
+        for page in conversion.take_pages() {
+            let bytes = zip.write_page(&page, format, settings)?;
+            download::write_to_file(file, &bytes)
+                .await
+                .map_err(failed)?;
+            written += page_span(&page);
+        }
+    }
+    let mut converted = conversion.finish()?;
+    // an odd page out of a spread is only finished at the end
+    for page in std::mem::take(&mut converted.pages) {
+        let bytes = zip.write_page(&page, format, settings)?;
+        download::write_to_file(file, &bytes)
+            .await
+            .map_err(failed)?;
+        written += page_span(&page);
+    }
+    download::write_to_file(file, &zip.finish()?)
+        .await
+        .map_err(failed)?;
+    JsFuture::from(file.close()).await.map_err(failed)?;
+    Ok(Some((converted, written)))
+}
+
+/// Download every `(filename, bytes)` zip of a document split into parts.
+fn save_parts(parts: &[(String, Vec<u8>)]) {
+    for (filename, zip) in parts {
+        download::save(zip, filename);
+    }
+}
+
+/// How many pages of the document `page` shows, 2 for spreads.
+fn page_span(page: &RenderedPage) -> usize {
+    if page.spread_with.is_some() { 2 } else { 1 }
+}
+
+/// Convert a document a page at a time, giving control back to the browser before every page.
+/// Stops early with `None` once `cancelled` is set.
+async fn convert_between_frames(
+    link: &Scope<App>,
+    stem: &str,
+    data: Arc<Vec<u8>>,
+    settings: &Settings,
+    cancelled: &Cell<bool>,
+    budget: &memory::PixelBudget,
+) -> Result<Option<Converted>, ConversionError> {
+    let mut conversion = Conversion::new(data, stem, settings)?;
+    // how long the page was unresponsive at most, the time the slowest page took
+    let mut longest_pause = Duration::ZERO;
+    while !conversion.is_done() {
+        let (done, total) = conversion.progress();
+        link.send_message(Msg::Progress(stem.to_string(), done, total));
+        yield_to_browser().await;
+        wait_while_paused(link, cancelled).await;
+        if cancelled.get() {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        conversion.set_scale_factor(budget.scale_factor());
+        let before = conversion.rendered_pixels();
+        conversion.render_next()?;
+        if let Some(limit) = budget.spend(conversion.rendered_pixels() - before) {
+            link.send_message(Msg::OverPixelBudget(limit));
+        }
+        longest_pause = longest_pause.max(started.elapsed());
+    }
+    let converted = conversion.finish()?;
+    link.send_message(Msg::Log(
+        log::Level::Info,
+        format!(
+            "converting {stem} paused the page for at most {} ms",
+            longest_pause.as_millis()
+        ),
+    ));
+    Ok(Some(converted))
+}
+
+/// Convert a document like [`convert_between_frames`], but in a [`ConvertWorker`] of its own, so
+/// rendering never holds up the page. Stops early with `None` once `cancelled` is set, which
+/// drops the worker along with its conversion.
+async fn convert_in_worker(
+    link: &Scope<App>,
+    stem: &str,
+    data: &[u8],
+    settings: &Settings,
+    cancelled: &Cell<bool>,
+    budget: &memory::PixelBudget,
+) -> Result<Option<Converted>, ConversionError> {
+    let updates = Rc::new(RefCell::new(VecDeque::new()));
+    // resolves the promise the loop below waits on, once an update comes in
+    let wake: Rc<RefCell<Option<Function>>> = Rc::default();
+    let worker = ConvertWorker::spawner()
+        .callback({
+            let updates = updates.clone();
+            let wake = wake.clone();
+            move |update| {
+                updates.borrow_mut().push_back(update);
+                if let Some(resolve) = wake.borrow_mut().take() {
+                    let _ = resolve.call0(&JsValue::NULL);
+                }
+            }
+        })
+        .spawn(WORKER_PATH);
+    worker.send(WorkerRequest::convert(stem, data.to_vec(), settings));
+    let mut paused = false;
+    let mut scale_factor = 1.0;
+    let mut pixels = 0;
+    loop {
+        if updates.borrow().is_empty() {
+            // pausing and cancelling don't send updates, so they are checked for every so often
+            let update = Promise::new(&mut |resolve, _| *wake.borrow_mut() = Some(resolve));
+            let _ = JsFuture::from(Promise::race(&Array::of2(&update, &pause_poll()))).await;
+        }
+        if cancelled.get() {
+            return Ok(None);
+        }
+        let now_paused = link.get_component().is_some_and(|app| app.paused);
+        if now_paused != paused {
+            paused = now_paused;
+            worker.send(WorkerRequest::Pause(paused));
+        }
+        let Some(update) = updates.borrow_mut().pop_front() else {
+            continue;
+        };
+        match update {
+            WorkerUpdate::Progress(done, total, rendered) => {
+                link.send_message(Msg::Progress(stem.to_string(), done, total));
+                if let Some(limit) = budget.spend(rendered - pixels) {
+                    link.send_message(Msg::OverPixelBudget(limit));
+                }
+                pixels = rendered;
+                if budget.scale_factor() != scale_factor {
+                    scale_factor = budget.scale_factor();
+                    worker.send(WorkerRequest::ScaleFactor(scale_factor));
+                }
+            }
+            WorkerUpdate::Done(result) => return result.map(Some),
+        }
+    }
+}
+
+/// Let the browser paint and handle input before carrying on, by continuing in a new task.
+async fn yield_to_browser() {
+    let next_task = Promise::new(&mut |resolve, _| {
+        let _ = gloo::utils::window().set_timeout_with_callback(&resolve);
+    });
+    let _ = JsFuture::from(next_task).await;
+}
+
+/// Wait for as long as the app is paused and the conversion isn't `cancelled`.
+async fn wait_while_paused(link: &Scope<App>, cancelled: &Cell<bool>) {
+    while !cancelled.get() && link.get_component().is_some_and(|app| app.paused) {
+        let _ = JsFuture::from(pause_poll()).await;
+    }
+}
+
+/// A promise resolving after [`PAUSE_POLL_MILLIS`].
+fn pause_poll() -> Promise {
+    Promise::new(&mut |resolve, _| {
+        let _ = gloo::utils::window()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, PAUSE_POLL_MILLIS);
+    })
+}
+
+/// Whether `target` is somewhere the user types text, where shortcuts must not fire.
+fn accepts_typing(target: &EventTarget) -> bool {
+    let Some(element) = target.dyn_ref::<HtmlElement>() else {
+        return false;
+    };
+    let typeable_input = element
+        .dyn_ref::<HtmlInputElement>()
+        .is_some_and(|input| !matches!(input.type_().as_str(), "checkbox" | "color" | "file"));
+    typeable_input
+        || element.is_content_editable()
+        || matches!(element.tag_name().as_str(), "TEXTAREA" | "SELECT")
+}
+
+/// Object URL of page 1, for files of which only the first page has been rendered.
+fn first_page_url(file: &mut RenderedImage, sharp: bool) -> Option<String> {
+    if !file.is_partial() {
+        return None;
+    }
+    file.preview_image(sharp)
+        .map(|(mime_type, bytes)| object_url(bytes, mime_type))
+}
+
+/// How many pixels wide previews are rendered on high-DPI screens, to cover the widest they
+/// are shown: the 20rem inline preview or the hover preview at 40% of the window. `None` on
+/// screens without extra pixels, where the downloadable image looks just as good.
+fn sharp_preview_width() -> Option<u32> {
+    let window = gloo::utils::window();
+    if window.device_pixel_ratio() <= 1.0 {
+        return None;
+    }
+    let window_width = window.inner_width().ok()?.as_f64()?;
+    Some(screen_pixels((window_width * 0.4).max(320.0)))
+}
+
+/// How many device pixels make up `css_pixels` on this screen.
+fn screen_pixels(css_pixels: f64) -> u32 {
+    (css_pixels * gloo::utils::window().device_pixel_ratio()).round() as u32
+}
+
+fn is_zip(file: &gloo::file::File) -> bool {
+    matches!(
+        file.raw_mime_type().as_str(),
+        "application/zip" | "application/x-zip-compressed"
+    ) || file.name().to_lowercase().ends_with(".zip")
+}
+
+/// Whether `file` may be a PDF, by its type or else its name.
+fn is_pdf(file: &gloo::file::File) -> bool {
+    may_be_pdf_type(&file.raw_mime_type()) || file.name().to_lowercase().ends_with(".pdf")
+}
+
+/// Whether a file of `mime_type` may be a PDF. Some file managers drop PDFs without a type, or
+/// as generic binary data, so those count until they fail to open as one.
+fn may_be_pdf_type(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "application/pdf" | "" | "application/octet-stream"
+    )
+}
+
+fn object_url(bytes: &[u8], mime_type: &str) -> String {
+    let blob = Blob::new_with_options(bytes, Some(mime_type));
+    Url::create_object_url_with_blob(&blob.into()).expect("failed creating object url")
+}
+
+fn main() {
+    console_error_panic_hook::set_once();
+
+    yew::Renderer::<App>::new().render();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> (Task, Rc<Cell<bool>>) {
+        let cancelled = Rc::new(Cell::new(false));
+        let task = Task {
+            _reader: None,
+            size: 1,
+            cancelled: cancelled.clone(),
+        };
+        (task, cancelled)
+    }
+
+    #[test]
+    fn a_file_dropped_again_cancels_the_earlier_one() {
+        let mut tasks = HashMap::new();
+        let (first, first_cancelled) = task();
+        let (second, second_cancelled) = task();
+        track(&mut tasks, "report".to_string(), first);
+        track(&mut tasks, "report".to_string(), second);
+        assert!(first_cancelled.get());
+        assert!(!second_cancelled.get());
+        assert_eq!(tasks.len(), 1);
+    }
+
+    #[test]
+    fn files_of_other_names_keep_going() {
+        let mut tasks = HashMap::new();
+        let (first, first_cancelled) = task();
+        let (second, _) = task();
+        track(&mut tasks, "report".to_string(), first);
+        track(&mut tasks, "report-2".to_string(), second);
+        assert!(!first_cancelled.get());
+        assert_eq!(tasks.len(), 2);
+    }
+
+    #[test]
+    fn duplicates_are_placed_by_the_policy() {
+        assert_eq!(placement(Duplicates::Add, true), Placement::Add);
+        assert_eq!(placement(Duplicates::Skip, true), Placement::Skip);
+        assert_eq!(placement(Duplicates::Replace, true), Placement::Replace);
+    }
+
+    #[test]
+    fn new_files_are_added_under_every_policy() {
+        for policy in Duplicates::ALL {
+            assert_eq!(placement(policy, false), Placement::Add);
+        }
+    }
+
+    #[test]
+    fn files_of_the_same_name_both_get_one() {
+        let mut stems: Vec<String> = Vec::new();
+        for _ in 0..3 {
+            let stem = first_free("scan", |candidate| {
+                stems.iter().any(|stem| stem == candidate)
+            });
+            stems.push(stem);
+        }
+        assert_eq!(stems, ["scan", "scan-2", "scan-3"]);
+    }
+
+    #[test]
+    fn names_in_use_are_skipped() {
+        let taken = ["scan", "scan-2", "scan-4"];
+        let stem = first_free("scan", |candidate| taken.contains(&candidate));
+        assert_eq!(stem, "scan-3");
+        assert_eq!(
+            first_free("report", |candidate| taken.contains(&candidate)),
+            "report"
+        );
+    }
+
+    #[test]
+    fn cleared_names_stay_taken_until_forgotten() {
+        // "scan" was cleared and can be restored, "scan-2" was dropped after the clear
+        let (cleared, files) = (["scan"], ["scan-2"]);
+        let taken = |candidate: &str| cleared.contains(&candidate) || files.contains(&candidate);
+        assert_eq!(first_free("scan", taken), "scan-3");
+    }
+
+    #[test]
+    fn files_without_a_type_may_be_pdfs() {
+        assert!(may_be_pdf_type("application/pdf"));
+        assert!(may_be_pdf_type(""));
+        assert!(may_be_pdf_type("application/octet-stream"));
+        assert!(!may_be_pdf_type("image/png"));
+        assert!(!may_be_pdf_type("text/plain"));
+    }
+}
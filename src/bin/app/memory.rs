@@ -0,0 +1,98 @@
+use humansize::format_size;
+use js_sys::Reflect;
+use pdf_to_whatever::Settings;
+use std::cell::Cell;
+
+/// Rough ratio of memory needed while converting to the size of the source PDF. Pages are
+/// decoded into full RGBA bitmaps, which are usually far larger than the compressed document.
+const MEMORY_PER_BYTE: u64 = 30;
+/// What a wasm32 module can address, used when the browser doesn't report its memory.
+const WASM_MEMORY: u64 = 4 * 1024 * 1024 * 1024;
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+/// Bytes of memory to allow for every rendered pixel: its RGBA bitmap while it is encoded, the
+/// encoded images kept afterwards and the copies made for previews and downloads.
+const MEMORY_PER_PIXEL: u64 = 16;
+/// How much the scale is lowered once an upload is over its pixel budget.
+const OVER_BUDGET_FACTOR: f32 = 0.5;
+
+/// Pixels rendered for the documents dropped together, lowering the scale of the pages left
+/// once they add up to more than the limit.
+pub struct PixelBudget {
+    /// `None` without a limit.
+    limit: Option<u64>,
+    spent: Cell<u64>,
+}
+
+impl PixelBudget {
+    pub fn new(settings: &Settings) -> Self {
+        let limit = settings.pixel_budget.then(|| {
+            settings.pixel_budget_megapixels.map_or_else(
+                || available_memory() / MEMORY_PER_PIXEL,
+                |megapixels| u64::from(megapixels) * 1_000_000,
+            )
+        });
+        Self {
+            limit,
+            spent: Cell::new(0),
+        }
+    }
+
+    /// Count `pixels` more as rendered. Returns the limit the first time it is passed.
+    pub fn spend(&self, pixels: u64) -> Option<u64> {
+        let limit = self.limit?;
+        let before = self.spent.get();
+        self.spent.set(before + pixels);
+        (before <= limit && before + pixels > limit).then_some(limit)
+    }
+
+    /// What to scale the pages rendered next by.
+    pub fn scale_factor(&self) -> f32 {
+        match self.limit {
+            Some(limit) if self.spent.get() > limit => OVER_BUDGET_FACTOR,
+            _ => 1.0,
+        }
+    }
+}
+
+pub use pdf_to_whatever::MAX_FILE_SIZE;
+
+/// The warning for turning away the file `name` of `size` bytes, for being larger than
+/// [`MAX_FILE_SIZE`].
+pub fn too_large(name: &str, size: u64) -> String {
+    format!(
+        "Skipped {name}: file too large ({}, limit {}).",
+        format_size(size, humansize::BINARY),
+        format_size(MAX_FILE_SIZE, humansize::BINARY)
+    )
+}
+
+/// A warning if converting documents totalling `total_size` bytes is likely to run out of
+/// memory, or `None` if it should be fine.
+pub fn warning(total_size: u64) -> Option<String> {
+    let needed = total_size.saturating_mul(MEMORY_PER_BYTE);
+    (needed > available_memory()).then(|| {
+        format!(
+            "Converting these files may need around {} of memory, more than is likely available. \
+             If the page crashes, drop fewer files at a time.",
+            format_size(needed, humansize::BINARY)
+        )
+    })
+}
+
+/// Memory the page can likely use. Don't count on more than half of the device's memory being
+/// spared for one tab.
+fn available_memory() -> u64 {
+    device_memory()
+        .map_or(WASM_MEMORY, |memory| memory / 2)
+        .min(WASM_MEMORY)
+}
+
+/// Memory of the device in bytes, from `navigator.deviceMemory`, which only Chromium-based
+/// browsers expose. It is rounded and capped at 8 GiB for privacy.
+fn device_memory() -> Option<u64> {
+    let navigator = Reflect::get(&gloo::utils::window(), &"navigator".into()).ok()?;
+    let gib = Reflect::get(&navigator, &"deviceMemory".into())
+        .ok()?
+        .as_f64()?;
+    Some((gib * GIB) as u64)
+}
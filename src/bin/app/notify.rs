@@ -0,0 +1,28 @@
+use web_sys::{Notification, NotificationOptions, NotificationPermission};
+
+/// Ask for permission to show notifications, unless it was already granted or denied. Returns
+/// whether they are blocked.
+pub fn request_permission() -> bool {
+    match Notification::permission() {
+        NotificationPermission::Default => {
+            // the answer only matters once there is something to notify about
+            let _ = Notification::request_permission();
+            false
+        }
+        NotificationPermission::Denied => true,
+        _ => false,
+    }
+}
+
+/// Show a notification, if they are allowed and the page isn't visible. Someone looking at the
+/// page already sees the results come in.
+pub fn notify(title: &str, body: &str) {
+    if Notification::permission() != NotificationPermission::Granted
+        || !gloo::utils::document().hidden()
+    {
+        return;
+    }
+    let options = NotificationOptions::new();
+    options.set_body(body);
+    let _ = Notification::new_with_options(title, &options);
+}
@@ -0,0 +1,147 @@
+use crate::{App, Msg};
+use pdf_to_whatever::Settings;
+use web_sys::{Event, HtmlInputElement, HtmlSelectElement};
+use yew::html::TargetCast;
+use yew::{Context, Html, html};
+
+/// Settings changed for a single document, applied on top of the global ones whenever it is
+/// converted. `None` keeps the global value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    pub scales: Option<String>,
+    pub pages: Option<String>,
+    /// As typed, anything but a positive number removes the target width.
+    pub target_width: Option<String>,
+    /// Degrees to turn every page clockwise by.
+    pub rotation: Option<u32>,
+}
+
+impl Overrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `settings` with every overridden field replaced.
+    pub fn apply(&self, settings: &Settings) -> Settings {
+        let mut settings = settings.clone();
+        if let Some(scales) = &self.scales {
+            settings.scales = scales.clone();
+        }
+        if let Some(pages) = &self.pages {
+            settings.pages = pages.clone();
+        }
+        if let Some(target_width) = &self.target_width {
+            settings.target_width = target_width.parse().ok().filter(|&width| width >= 1);
+        }
+        if let Some(rotation) = self.rotation {
+            settings.rotation = rotation;
+        }
+        settings
+    }
+}
+
+/// Inputs overriding the settings of the document `stem`, empty ones showing the global value.
+pub fn view_overrides(
+    ctx: &Context<App>,
+    stem: &str,
+    overrides: &Overrides,
+    settings: &Settings,
+) -> Html {
+    let reset_stem = stem.to_string();
+    let apply_stem = stem.to_string();
+    html! {
+        <details class="overrides">
+            <summary>
+                { if overrides.is_empty() { "Settings for this document" } else { "Settings for this document (changed)" } }
+            </summary>
+            { override_input(ctx, stem, overrides, "Scales", "text", &settings.scales, |o| &mut o.scales) }
+            { override_input(ctx, stem, overrides, "Pages", "text", &settings.pages, |o| &mut o.pages) }
+            { override_input(
+                ctx,
+                stem,
+                overrides,
+                "Width (px)",
+                "number",
+                &settings.target_width.map(|width| width.to_string()).unwrap_or_default(),
+                |o| &mut o.target_width,
+            ) }
+            { rotation_select(ctx, stem, overrides, settings.rotation) }
+            <button onclick={ctx.link().callback(move |_| Msg::Reconvert(apply_stem.clone()))}>
+                {"Convert again"}
+            </button>
+            <button
+                disabled={overrides.is_empty()}
+                onclick={ctx.link().callback(move |_| Msg::Override(reset_stem.clone(), Overrides::default()))}
+            >
+                {"Use global settings"}
+            </button>
+        </details>
+    }
+}
+
+/// Degrees every page can be turned by.
+const ROTATIONS: [u32; 4] = [0, 90, 180, 270];
+
+/// A select for the rotation, starting at the global `current` one.
+fn rotation_select(ctx: &Context<App>, stem: &str, overrides: &Overrides, current: u32) -> Html {
+    let selected = overrides.rotation.unwrap_or(current);
+    let onchange = {
+        let stem = stem.to_string();
+        let overrides = overrides.clone();
+        ctx.link().batch_callback(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let rotation = *ROTATIONS.get(usize::try_from(select.selected_index()).ok()?)?;
+            let mut overrides = overrides.clone();
+            // picking the global value goes back to it
+            overrides.rotation = (rotation != current).then_some(rotation);
+            Some(Msg::Override(stem.clone(), overrides))
+        })
+    };
+    html! {
+        <label>
+            {"Rotation"}
+            <select {onchange}>
+                { for ROTATIONS.iter().map(|&rotation| html! {
+                    <option selected={rotation == selected}>{format!("{rotation}°")}</option>
+                }) }
+            </select>
+        </label>
+    }
+}
+
+/// An input for one overridden field. While it is empty its placeholder shows the global
+/// `current` value.
+fn override_input(
+    ctx: &Context<App>,
+    stem: &str,
+    overrides: &Overrides,
+    label: &str,
+    kind: &'static str,
+    current: &str,
+    field: fn(&mut Overrides) -> &mut Option<String>,
+) -> Html {
+    let value = field(&mut overrides.clone()).clone();
+    let onchange = {
+        let stem = stem.to_string();
+        let overrides = overrides.clone();
+        ctx.link().callback(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut overrides = overrides.clone();
+            // emptying a field goes back to the global value
+            let value = input.value();
+            *field(&mut overrides) = (!value.trim().is_empty()).then_some(value);
+            Msg::Override(stem.clone(), overrides)
+        })
+    };
+    html! {
+        <label>
+            {label}
+            <input
+                type={kind}
+                placeholder={if current.is_empty() { "as in settings".to_string() } else { current.to_string() }}
+                value={value.unwrap_or_default()}
+                {onchange}
+            />
+        </label>
+    }
+}
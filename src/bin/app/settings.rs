@@ -0,0 +1,658 @@
+use crate::{App, Msg, download};
+use gloo::storage::{LocalStorage, Storage};
+use pdf_to_whatever::{
+    DEFAULT_TEMPLATE, Duplicates, Orientation, OutputFormat, PageOrder, PaperSize, PngCompression,
+    Settings, parse_hex_color, to_hex_color,
+};
+use std::collections::BTreeSet;
+use web_sys::{Event, HtmlInputElement, HtmlSelectElement};
+use yew::html::TargetCast;
+use yew::{Context, Html, html};
+
+pub fn view_settings(ctx: &Context<App>, settings: &Settings) -> Html {
+    html! {
+        <details id="settings">
+            <summary>{"Settings"}</summary>
+            <fieldset>
+                <legend>{"Formats"}</legend>
+                { for OutputFormat::ALL.into_iter().map(|format| format_checkbox(ctx, settings, format)) }
+                { checkbox(ctx, settings, "Embed DPI in images", settings.dpi_metadata, |s, v| {
+                    s.dpi_metadata = v
+                }) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Pages"}</legend>
+                { text(ctx, settings, "Pages", "Page numbers and ranges, like 1-20", "all", &settings.pages, |s, v| {
+                    s.pages = v
+                }) }
+                { text(ctx, settings, "Except", "Pages to leave out, like 5,9-11", "none", &settings.exclude_pages, |s, v| {
+                    s.exclude_pages = v
+                }) }
+                { optional_number(ctx, settings, "At most", "all pages", settings.max_pages, |s, v| s.max_pages = v) }
+                { text(ctx, settings, "Containing", "Only pages with this text, ignoring case", "any text", &settings.search, |s, v| {
+                    s.search = v
+                }) }
+                { select(
+                    ctx,
+                    settings,
+                    "Order",
+                    PageOrder::ALL.map(|order| (order, order.label())).to_vec(),
+                    settings.page_order,
+                    |s, v| s.page_order = v,
+                ) }
+                if settings.page_order == PageOrder::Custom {
+                    { text(ctx, settings, "Custom order", "Every page once, like 3,1-2,5-4", "all pages", &settings.custom_order, |s, v| {
+                        s.custom_order = v
+                    }) }
+                }
+                { checkbox(ctx, settings, "Skip blank pages", settings.skip_blank_pages, |s, v| {
+                    s.skip_blank_pages = v
+                }) }
+                if settings.skip_blank_pages {
+                    { checkbox(ctx, settings, "Renumber the remaining pages", settings.renumber_after_blanks, |s, v| {
+                        s.renumber_after_blanks = v
+                    }) }
+                }
+                { checkbox(ctx, settings, "Trim margins", settings.trim_margins, |s, v| s.trim_margins = v) }
+                if settings.trim_margins {
+                    { number(ctx, settings, "Padding (px)", settings.trim_padding as f64, 0.0, 1024.0, |s, v| {
+                        s.trim_padding = v.clamp(0.0, 1024.0) as u32
+                    }) }
+                }
+                { checkbox(ctx, settings, "Crop to text", settings.crop_to_text, |s, v| s.crop_to_text = v) }
+                { checkbox(ctx, settings, "Split into tiles", settings.tiles, |s, v| s.tiles = v) }
+                if settings.tiles {
+                    { number(ctx, settings, "Tile size", settings.tile_size as f64, 16.0, 16384.0, |s, v| {
+                        s.tile_size = v.max(16.0) as u32
+                    }) }
+                }
+                { text(ctx, settings, "Scales", "Also export at these sizes, like 1,2 for @1x and @2x", "1", &settings.scales, |s, v| {
+                    s.scales = v
+                }) }
+                { checkbox(ctx, settings, "Snap scale to whole numbers", settings.integer_scale, |s, v| {
+                    s.integer_scale = v
+                }) }
+                { checkbox(ctx, settings, "Supersample (slower)", settings.supersample, |s, v| s.supersample = v) }
+                if settings.supersample {
+                    { number(ctx, settings, "Supersample factor", settings.supersample_factor as f64, 2.0, 4.0, |s, v| {
+                        s.supersample_factor = v.clamp(2.0, 4.0) as u32
+                    }) }
+                }
+                { checkbox(ctx, settings, "Preview first page only", settings.first_page_only, |s, v| {
+                    s.first_page_only = v
+                }) }
+                { checkbox(ctx, settings, "Sharp previews on high-DPI screens", settings.sharp_previews, |s, v| {
+                    s.sharp_previews = v
+                }) }
+                { checkbox(ctx, settings, "Combine facing pages", settings.spreads, |s, v| s.spreads = v) }
+                { checkbox(ctx, settings, "Keep page 1 as a standalone cover", settings.spread_cover, |s, v| {
+                    s.spread_cover = v
+                }) }
+                { checkbox(ctx, settings, "Read right to left", settings.right_to_left, |s, v| {
+                    s.right_to_left = v
+                }) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Border"}</legend>
+                { checkbox(ctx, settings, "Draw border", settings.border, |s, v| s.border = v) }
+                { number(ctx, settings, "Thickness (px)", settings.border_thickness as f64, 1.0, 100.0, |s, v| {
+                    s.border_thickness = v as u32
+                }) }
+                { color(ctx, settings, "Color", settings.border_color, |s, v| s.border_color = v) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Size"}</legend>
+                { optional_number(ctx, settings, "Target width (px)", "auto", settings.target_width, |s, v| {
+                    s.target_width = v
+                }) }
+                { optional_number(ctx, settings, "Target height (px)", "auto", settings.target_height, |s, v| {
+                    s.target_height = v
+                }) }
+                { optional_number(ctx, settings, "Max width (px)", "none", settings.max_width, |s, v| {
+                    s.max_width = v
+                }) }
+                { optional_number(ctx, settings, "Max height (px)", "none", settings.max_height, |s, v| {
+                    s.max_height = v
+                }) }
+                { optional_number(ctx, settings, "Shrink pages longer than (pt)", "never", settings.large_page_threshold, |s, v| {
+                    s.large_page_threshold = v
+                }) }
+                if settings.crop.is_some() {
+                    <span>
+                        {"Cropped to the region picked with \"Crop\" "}
+                        <button onclick={ctx.link().callback(|_| Msg::CropTo(None))}>{"Don't crop"}</button>
+                    </span>
+                }
+                { select(
+                    ctx,
+                    settings,
+                    "Turn pages to",
+                    vec![
+                        (Orientation::MatchPage, "As they are"),
+                        (Orientation::Portrait, "Portrait"),
+                        (Orientation::Landscape, "Landscape"),
+                    ],
+                    settings.page_orientation,
+                    |s, v| s.page_orientation = v,
+                ) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Paper size"}</legend>
+                { select(
+                    ctx,
+                    settings,
+                    "Fit onto",
+                    std::iter::once((None, "Original size"))
+                        .chain(PaperSize::ALL.map(|paper| (Some(paper), paper.label())))
+                        .collect(),
+                    settings.paper_size,
+                    |s, v| s.paper_size = v,
+                ) }
+                { number(ctx, settings, "DPI", settings.paper_dpi as f64, 10.0, 1200.0, |s, v| {
+                    s.paper_dpi = v as f32
+                }) }
+                { select(
+                    ctx,
+                    settings,
+                    "Orientation",
+                    Orientation::ALL.map(|orientation| (orientation, orientation.label())).to_vec(),
+                    settings.paper_orientation,
+                    |s, v| s.paper_orientation = v,
+                ) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Advanced"}</legend>
+                { text(
+                    ctx,
+                    settings,
+                    "Pixels per point",
+                    "Render at exactly this many pixels per PDF point, ignoring scales and sizes",
+                    "as scaled",
+                    &settings.pixels_per_point.map(|ratio| ratio.to_string()).unwrap_or_default(),
+                    |s, v| {
+                        s.pixels_per_point =
+                            v.trim().parse().ok().filter(|ratio: &f32| ratio.is_finite() && *ratio > 0.0)
+                    },
+                ) }
+                { number(
+                    ctx,
+                    settings,
+                    "Background tolerance",
+                    settings.background_tolerance as f64,
+                    0.0,
+                    255.0,
+                    |s, v| s.background_tolerance = v.clamp(0.0, 255.0) as u8,
+                ) }
+                { checkbox(ctx, settings, "Convert in background workers", settings.background_workers, |s, v| {
+                    s.background_workers = v
+                }) }
+                if !settings.background_workers {
+                    { checkbox(ctx, settings, "Keep the page responsive while converting", settings.responsive_rendering, |s, v| {
+                        s.responsive_rendering = v
+                    }) }
+                }
+                { checkbox(ctx, settings, "Limit the pixels of an upload", settings.pixel_budget, |s, v| {
+                    s.pixel_budget = v
+                }) }
+                if settings.pixel_budget {
+                    { optional_number(ctx, settings, "Pixel limit (MP)", "from memory", settings.pixel_budget_megapixels, |s, v| {
+                        s.pixel_budget_megapixels = v
+                    }) }
+                }
+                { checkbox(ctx, settings, "Anti-moiré (softer)", settings.anti_moire, |s, v| s.anti_moire = v) }
+                if settings.anti_moire {
+                    { number(ctx, settings, "Anti-moiré radius (px)", settings.anti_moire_radius as f64, 0.1, 10.0, |s, v| {
+                        s.anti_moire_radius = v.clamp(0.1, 10.0) as f32
+                    }) }
+                }
+                { select(
+                    ctx,
+                    settings,
+                    "Files dropped again",
+                    Duplicates::ALL.map(|duplicates| (duplicates, duplicates.label())).to_vec(),
+                    settings.duplicates,
+                    |s, v| s.duplicates = v,
+                ) }
+                { checkbox(ctx, settings, "Notify when done", settings.notify_when_done, |s, v| {
+                    s.notify_when_done = v
+                }) }
+                { checkbox(ctx, settings, "Keep results while idle", settings.keep_results, |s, v| {
+                    s.keep_results = v
+                }) }
+                if !settings.keep_results {
+                    { number(ctx, settings, "Clear results after idle (min)", settings.idle_clear_minutes as f64, 1.0, 10080.0, |s, v| {
+                        s.idle_clear_minutes = v.max(1.0) as u32
+                    }) }
+                }
+                { substitute_font(ctx, settings) }
+                { select(
+                    ctx,
+                    settings,
+                    "PNG compression",
+                    PngCompression::ALL.map(|compression| (compression, compression.label())).to_vec(),
+                    settings.png_compression,
+                    |s, v| s.png_compression = v,
+                ) }
+                { number(
+                    ctx,
+                    settings,
+                    "Compression time limit (ms)",
+                    settings.compression_budget_ms as f64,
+                    0.0,
+                    600000.0,
+                    |s, v| s.compression_budget_ms = v.max(0.0) as u32,
+                ) }
+                { checkbox(ctx, settings, "Grayscale", settings.grayscale, |s, v| s.grayscale = v) }
+                { number(ctx, settings, "JPEG quality", settings.jpeg_quality as f64, 1.0, 100.0, |s, v| {
+                    s.jpeg_quality = v.clamp(1.0, 100.0) as u8
+                }) }
+                { number(
+                    ctx,
+                    settings,
+                    "JPEG target size (KB)",
+                    settings.jpeg_target_kb as f64,
+                    0.0,
+                    100000.0,
+                    |s, v| s.jpeg_target_kb = v.max(0.0) as u32,
+                ) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Advanced metadata"}</legend>
+                { checkbox(ctx, settings, "Embed XMP metadata", settings.embed_xmp, |s, v| s.embed_xmp = v) }
+                if settings.embed_xmp {
+                    { text(ctx, settings, "Title", "", "from document", &settings.xmp.title, |s, v| s.xmp.title = v) }
+                    { text(ctx, settings, "Creator", "", "from document", &settings.xmp.creator, |s, v| {
+                        s.xmp.creator = v
+                    }) }
+                    { text(ctx, settings, "Rights", "", "", &settings.xmp.rights, |s, v| s.xmp.rights = v) }
+                    { text(ctx, settings, "Keywords", "Comma-separated", "from document", &settings.xmp.keywords, |s, v| {
+                        s.xmp.keywords = v
+                    }) }
+                }
+                { checkbox(ctx, settings, "Embed the settings used", settings.embed_provenance, |s, v| {
+                    s.embed_provenance = v
+                }) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Archive"}</legend>
+                { text(
+                    ctx,
+                    settings,
+                    "Filenames",
+                    "{stem}, {page}, {created}, {modified} and {settingshash} are replaced",
+                    DEFAULT_TEMPLATE,
+                    &settings.filename_template,
+                    // an empty template falls back to the default rather than naming nothing
+                    |s, v| s.filename_template = if v.trim().is_empty() { DEFAULT_TEMPLATE.to_string() } else { v },
+                ) }
+                { checkbox(ctx, settings, "Name documents in dropped folders after their path", settings.folder_names, |s, v| {
+                    s.folder_names = v
+                }) }
+                { checkbox(ctx, settings, "Single archive with a folder per format", settings.single_archive, |s, v| {
+                    s.single_archive = v
+                }) }
+                { checkbox(ctx, settings, "Zip single pages too", settings.always_zip, |s, v| {
+                    s.always_zip = v
+                }) }
+                { checkbox(ctx, settings, "Include SHA-256 checksums", settings.checksums, |s, v| {
+                    s.checksums = v
+                }) }
+                { checkbox(ctx, settings, "Include a CSV manifest", settings.manifest, |s, v| s.manifest = v) }
+                { checkbox(ctx, settings, "Include a JSON manifest", settings.json_manifest, |s, v| {
+                    s.json_manifest = v
+                }) }
+                { checkbox(ctx, settings, "Include rendering diagnostics", settings.diagnostics, |s, v| {
+                    s.diagnostics = v
+                }) }
+                { checkbox(ctx, settings, "Include a list of links", settings.links, |s, v| s.links = v) }
+                { checkbox(ctx, settings, "Include text with positions", settings.text_positions, |s, v| {
+                    s.text_positions = v
+                }) }
+                { checkbox(ctx, settings, "Include embedded attachments", settings.attachments, |s, v| {
+                    s.attachments = v
+                }) }
+                { checkbox(ctx, settings, "Include the source PDF", settings.include_source, |s, v| {
+                    s.include_source = v
+                }) }
+                { checkbox(ctx, settings, "Sort files by name", settings.sorted_entries, |s, v| {
+                    s.sorted_entries = v
+                }) }
+                { for OutputFormat::ALL.into_iter().map(|format| format_set_checkbox(
+                    ctx,
+                    settings,
+                    &format!("Deflate {} files", format.label()),
+                    format,
+                    settings.deflated_formats.contains(&format),
+                    |s| &mut s.deflated_formats,
+                )) }
+                { optional_number(ctx, settings, "Split into parts of (MB)", "no limit", settings.split_size_mb, |s, v| {
+                    s.split_size_mb = v
+                }) }
+                { checkbox(ctx, settings, "Include index.html in combined archives", settings.index_page, |s, v| {
+                    s.index_page = v
+                }) }
+                { checkbox(ctx, settings, "Include a preview image in combined archives", settings.zip_preview, |s, v| {
+                    s.zip_preview = v
+                }) }
+                { checkbox(ctx, settings, "Include images in exported sessions", settings.session_outputs, |s, v| {
+                    s.session_outputs = v
+                }) }
+                { text(
+                    ctx,
+                    settings,
+                    "IIIF base URL",
+                    "Where IIIF exports will be served from, their manifests point at images under it",
+                    "http://localhost:8000",
+                    &settings.iiif_base_url,
+                    |s, v| s.iiif_base_url = v.trim().to_string(),
+                ) }
+                if download::folder_access_supported() {
+                    { checkbox(ctx, settings, "Save loose images into a folder", settings.save_to_folder, |s, v| {
+                        s.save_to_folder = v
+                    }) }
+                }
+                if download::save_file_supported() {
+                    { checkbox(ctx, settings, "Write a single document straight to disk", settings.stream_to_disk, |s, v| {
+                        s.stream_to_disk = v
+                    }) }
+                }
+            </fieldset>
+            <fieldset>
+                <legend>{"Web copies"}</legend>
+                { checkbox(ctx, settings, "Master + web: add a small JPEG of every page", settings.web_copies, |s, v| {
+                    s.web_copies = v
+                }) }
+                if settings.web_copies {
+                    { number(ctx, settings, "Longest side (px)", settings.web_max_size as f64, 16.0, 16384.0, |s, v| {
+                        s.web_max_size = v.max(16.0) as u32
+                    }) }
+                    { number(ctx, settings, "Quality", settings.web_quality as f64, 1.0, 100.0, |s, v| {
+                        s.web_quality = v.clamp(1.0, 100.0) as u8
+                    }) }
+                }
+            </fieldset>
+            <fieldset>
+                <legend>{"Sprite sheet"}</legend>
+                { number(ctx, settings, "Max sheet size (px)", settings.sprite_max_size as f64, 64.0, 16384.0, |s, v| {
+                    s.sprite_max_size = v.max(64.0) as u32
+                }) }
+                { number(ctx, settings, "Padding (px)", settings.sprite_padding as f64, 0.0, 64.0, |s, v| {
+                    s.sprite_padding = v.max(0.0) as u32
+                }) }
+            </fieldset>
+            <fieldset>
+                <legend>{"Animated PNG"}</legend>
+                { number(ctx, settings, "Page delay (ms)", settings.apng_delay_millis as f64, 10.0, 65535.0, |s, v| {
+                    s.apng_delay_millis = v.clamp(10.0, 65535.0) as u32
+                }) }
+            </fieldset>
+        </details>
+    }
+}
+
+/// `localStorage` key holding the enabled formats as comma-separated extensions, saved by
+/// visits before every setting was.
+const FORMATS_KEY: &str = "formats";
+/// `localStorage` key holding every setting as JSON.
+const SETTINGS_KEY: &str = "settings";
+
+/// Picking the font fonts that documents don't embed are drawn with, or going back to hayro's
+/// choice of standard font once one is picked.
+fn substitute_font(ctx: &Context<App>, settings: &Settings) -> Html {
+    match &settings.substitute_font {
+        Some(font) => {
+            let mut without = settings.clone();
+            without.substitute_font = None;
+            html! {
+                <span>
+                    {format!("Missing fonts are drawn with {} ", font.name)}
+                    <button onclick={ctx.link().callback(move |_| Msg::Settings(Box::new(without.clone())))}>
+                        {"Don't substitute"}
+                    </button>
+                </span>
+            }
+        }
+        None => html! {
+            <label title="Draw fonts that documents don't embed with this TrueType or OpenType font">
+                {"Substitute font "}
+                <input
+                    type="file"
+                    accept=".ttf,.otf,font/ttf,font/otf"
+                    onchange={ctx.link().callback(|e: Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        Msg::PickFont(input.files().expect("must be some files"))
+                    })}
+                />
+            </label>
+        },
+    }
+}
+
+/// The settings of a previous visit, the defaults if none were saved or they don't parse.
+/// Only the formats are restored from visits that saved nothing else.
+pub fn load() -> Settings {
+    if let Ok(settings) = LocalStorage::get(SETTINGS_KEY) {
+        return settings;
+    }
+    let mut settings = Settings::default();
+    if let Some(formats) = load_formats() {
+        settings.formats = formats;
+    }
+    settings
+}
+
+/// Keep `settings` for the next visit. The substitute font is left out like in sessions.
+pub fn save(settings: &Settings) {
+    let _ = LocalStorage::set(SETTINGS_KEY, settings);
+}
+
+/// The formats enabled in a previous visit, if any were saved.
+fn load_formats() -> Option<BTreeSet<OutputFormat>> {
+    let saved = LocalStorage::raw().get_item(FORMATS_KEY).ok()??;
+    Some(
+        OutputFormat::ALL
+            .into_iter()
+            .filter(|format| saved.split(',').any(|ext| ext == format.extension()))
+            .collect(),
+    )
+}
+
+fn format_checkbox(ctx: &Context<App>, settings: &Settings, format: OutputFormat) -> Html {
+    let checked = settings.formats.contains(&format);
+    format_set_checkbox(ctx, settings, format.label(), format, checked, |s| {
+        &mut s.formats
+    })
+}
+
+/// A checkbox adding `format` to or removing it from the set of formats `set` picks out, which
+/// holds it if `checked`.
+fn format_set_checkbox(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    format: OutputFormat,
+    checked: bool,
+    set: fn(&mut Settings) -> &mut BTreeSet<OutputFormat>,
+) -> Html {
+    let onchange = {
+        let settings = settings.clone();
+        ctx.link().callback(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut settings = settings.clone();
+            if input.checked() {
+                set(&mut settings).insert(format);
+            } else {
+                set(&mut settings).remove(&format);
+            }
+            Msg::Settings(Box::new(settings))
+        })
+    };
+    html! {
+        <label>
+            <input type="checkbox" {checked} {onchange} />
+            {label}
+        </label>
+    }
+}
+
+/// Build a callback that applies `set` to a copy of the current settings.
+fn on_input<T: 'static>(
+    ctx: &Context<App>,
+    settings: &Settings,
+    read: impl Fn(&HtmlInputElement) -> Option<T> + 'static,
+    set: fn(&mut Settings, T),
+) -> yew::Callback<Event> {
+    let settings = settings.clone();
+    ctx.link().batch_callback(move |e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        let value = read(&input)?;
+        let mut settings = settings.clone();
+        set(&mut settings, value);
+        Some(Msg::Settings(Box::new(settings)))
+    })
+}
+
+pub fn checkbox(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    checked: bool,
+    set: fn(&mut Settings, bool),
+) -> Html {
+    html! {
+        <label>
+            <input
+                type="checkbox"
+                {checked}
+                onchange={on_input(ctx, settings, |input| Some(input.checked()), set)}
+            />
+            {label}
+        </label>
+    }
+}
+
+pub fn number(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    value: f64,
+    min: f64,
+    max: f64,
+    set: fn(&mut Settings, f64),
+) -> Html {
+    html! {
+        <label>
+            {label}
+            <input
+                type="number"
+                min={min.to_string()}
+                max={max.to_string()}
+                step="any"
+                value={value.to_string()}
+                onchange={on_input(ctx, settings, move |input| {
+                    let value = input.value_as_number();
+                    (!value.is_nan()).then_some(value)
+                }, set)}
+            />
+        </label>
+    }
+}
+
+/// A number input for a pixel size or count that can be left empty, showing `placeholder` then.
+pub fn optional_number(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    placeholder: &str,
+    value: Option<u32>,
+    set: fn(&mut Settings, Option<u32>),
+) -> Html {
+    html! {
+        <label>
+            {label}
+            <input
+                type="number"
+                min="1"
+                max="16384"
+                placeholder={placeholder.to_string()}
+                value={value.map(|value| value.to_string()).unwrap_or_default()}
+                onchange={on_input(ctx, settings, |input| {
+                    let value = input.value_as_number();
+                    Some((value >= 1.0).then_some(value as u32))
+                }, set)}
+            />
+        </label>
+    }
+}
+
+pub fn text(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    title: &str,
+    placeholder: &str,
+    value: &str,
+    set: fn(&mut Settings, String),
+) -> Html {
+    html! {
+        <label title={title.to_string()}>
+            {label}
+            <input
+                type="text"
+                placeholder={placeholder.to_string()}
+                value={value.to_string()}
+                onchange={on_input(ctx, settings, |input| Some(input.value()), set)}
+            />
+        </label>
+    }
+}
+
+pub fn color(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    value: [u8; 3],
+    set: fn(&mut Settings, [u8; 3]),
+) -> Html {
+    html! {
+        <label>
+            {label}
+            <input
+                type="color"
+                value={to_hex_color(value)}
+                onchange={on_input(ctx, settings, |input| parse_hex_color(&input.value()), set)}
+            />
+        </label>
+    }
+}
+
+pub fn select<T: Copy + PartialEq + 'static>(
+    ctx: &Context<App>,
+    settings: &Settings,
+    label: &str,
+    options: Vec<(T, &'static str)>,
+    current: T,
+    set: fn(&mut Settings, T),
+) -> Html {
+    let values: Vec<T> = options.iter().map(|(value, _)| *value).collect();
+    let onchange = {
+        let settings = settings.clone();
+        ctx.link().batch_callback(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let value = *values.get(usize::try_from(select.selected_index()).ok()?)?;
+            let mut settings = settings.clone();
+            set(&mut settings, value);
+            Some(Msg::Settings(Box::new(settings)))
+        })
+    };
+    html! {
+        <label>
+            {label}
+            <select {onchange}>
+                { for options.iter().map(|(value, name)| html! {
+                    <option selected={*value == current}>{*name}</option>
+                }) }
+            </select>
+        </label>
+    }
+}
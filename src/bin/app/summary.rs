@@ -0,0 +1,41 @@
+use crate::{FailedFile, RenderedImage};
+use pdf_to_whatever::OutputFormat;
+
+/// A CSV with a row for every converted and every failed file: its size, pages, the size of
+/// its images in every format, how long it took and why it failed. Sizes are in bytes, and
+/// formats that weren't rendered are left empty.
+pub fn summary_csv(files: &[RenderedImage], failed: &[FailedFile]) -> String {
+    let mut csv = String::from("file,source_bytes,pages,rendered_pages");
+    for format in OutputFormat::ALL {
+        csv += &format!(",{}_bytes", format.extension());
+    }
+    csv += ",render_secs,error\n";
+    for file in files {
+        let sizes = file.format_sizes();
+        csv += &format!(
+            "{},{},{},{}",
+            quoted(&file.stem),
+            file.data.len(),
+            file.page_count,
+            file.rendered_pages()
+        );
+        for format in OutputFormat::ALL {
+            csv += ",";
+            if let Some((_, size)) = sizes.iter().find(|(rendered, _)| *rendered == format) {
+                csv += &size.to_string();
+            }
+        }
+        csv += &format!(",{:.2},\n", file.render_secs);
+    }
+    for file in failed {
+        csv += &format!("{},{},,", quoted(&file.stem), file.data.len());
+        csv += &",".repeat(OutputFormat::ALL.len());
+        csv += &format!(",,{}\n", quoted(&file.error.to_string()));
+    }
+    csv
+}
+
+/// `text` quoted as CSV requires, since names and errors can contain anything.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
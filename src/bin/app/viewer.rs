@@ -0,0 +1,95 @@
+use crate::{App, Msg, object_url, screen_pixels};
+use pdf_to_whatever::{ConversionError, OutputFormat};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use web_sys::Url;
+use yew::{Context, Html, html};
+
+/// Pages kept rendered, so paging back and forth doesn't render them again.
+const CACHED_PAGES: usize = 5;
+/// Widest the viewer is shown, in CSS pixels.
+const MAX_WIDTH: f64 = 800.0;
+
+/// A document shown one page at a time, each page rendered when it is first viewed.
+pub struct Viewer {
+    pub stem: String,
+    data: Arc<Vec<u8>>,
+    page_count: usize,
+    number: usize,
+    /// `(page number, object URL)` of recently viewed pages, the least recent first.
+    cache: VecDeque<(usize, String)>,
+}
+
+impl Viewer {
+    pub fn new(stem: String, data: Arc<Vec<u8>>, page_count: usize) -> Self {
+        Self {
+            stem,
+            data,
+            page_count,
+            number: 0,
+            cache: VecDeque::new(),
+        }
+    }
+
+    /// Show page `number`, rendering it unless it was viewed recently.
+    pub fn show(&mut self, number: usize) -> Result<(), ConversionError> {
+        if let Some(index) = self.cache.iter().position(|(cached, _)| *cached == number) {
+            let page = self.cache.remove(index).expect("index was just found");
+            self.cache.push_back(page);
+        } else {
+            let window_width = gloo::utils::window()
+                .inner_width()
+                .ok()
+                .and_then(|width| width.as_f64())
+                .unwrap_or(MAX_WIDTH);
+            let width = screen_pixels(window_width.min(MAX_WIDTH));
+            let bytes = pdf_to_whatever::render_preview(self.data.clone(), number, width)?;
+            if self.cache.len() == CACHED_PAGES
+                && let Some((_, url)) = self.cache.pop_front()
+            {
+                let _ = Url::revoke_object_url(&url);
+            }
+            let url = object_url(&bytes, OutputFormat::Png.mime_type());
+            self.cache.push_back((number, url));
+        }
+        self.number = number;
+        Ok(())
+    }
+
+    pub fn view(&self, ctx: &Context<App>) -> Html {
+        let Some((_, url)) = self.cache.iter().find(|(cached, _)| *cached == self.number) else {
+            return html! {};
+        };
+        let (previous, next) = (self.number - 1, self.number + 1);
+        html! {
+            <section id="viewer" aria-label={format!("Viewer for {}", self.stem)}>
+                <div class="controls">
+                    <span>{&self.stem}</span>
+                    <button
+                        disabled={previous == 0}
+                        onclick={ctx.link().callback(move |_| Msg::ViewPage(previous))}
+                    >
+                        {"Previous"}
+                    </button>
+                    <span aria-live="polite">{format!("Page {} of {}", self.number, self.page_count)}</span>
+                    <button
+                        disabled={next > self.page_count}
+                        onclick={ctx.link().callback(move |_| Msg::ViewPage(next))}
+                    >
+                        {"Next"}
+                    </button>
+                    <button onclick={ctx.link().callback(|_| Msg::CloseViewer)}>{"Close"}</button>
+                </div>
+                <img src={url.clone()} alt={format!("{}, page {}", self.stem, self.number)} />
+            </section>
+        }
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        for (_, url) in &self.cache {
+            let _ = Url::revoke_object_url(url);
+        }
+    }
+}
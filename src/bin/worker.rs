@@ -0,0 +1,8 @@
+use gloo::worker::Registrable;
+use pdf_to_whatever::ConvertWorker;
+
+fn main() {
+    console_error_panic_hook::set_once();
+
+    ConvertWorker::registrar().register();
+}
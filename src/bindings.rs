@@ -0,0 +1,65 @@
+//! Entry points for using the library from JavaScript outside of the app, e.g. in Node or Deno.
+
+use crate::{Settings, convert_with_progress, zip_formats};
+use js_sys::{Array, Function, JSON, Object, Reflect, Uint8Array};
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+/// Convert every PDF in `files`, an array of `Uint8Array`s, with `settings` given as an object
+/// with the fields of [`Settings`], missing ones taking their defaults.
+///
+/// Resolves to an array with one entry per file, in order: `{ zip: Uint8Array }` with the
+/// page images of every enabled format, or `{ error: string }`. An `onProgress` function, if
+/// given, is called after every page with the pages rendered so far, the pages to render and
+/// the index of the file.
+#[wasm_bindgen(js_name = convertBatch)]
+pub fn convert_batch(
+    files: Array,
+    settings: JsValue,
+    on_progress: Option<Function>,
+) -> js_sys::Promise {
+    future_to_promise(async move {
+        let settings: Settings = if settings.is_undefined() || settings.is_null() {
+            Settings::default()
+        } else {
+            let json = String::from(JSON::stringify(&settings)?);
+            serde_json::from_str(&json).map_err(|err| JsError::new(&err.to_string()))?
+        };
+        let results = Array::new();
+        for (index, file) in files.iter().enumerate() {
+            let stem = format!("document-{}", index + 1);
+            let data = Uint8Array::new(&file).to_vec();
+            let progress = |done: usize, total: usize| {
+                if let Some(on_progress) = &on_progress {
+                    // the conversion carries on whatever the callback does
+                    let _ = on_progress.call3(
+                        &JsValue::NULL,
+                        &done.into(),
+                        &total.into(),
+                        &index.into(),
+                    );
+                }
+            };
+            let data = Arc::new(data);
+            let zip = convert_with_progress(data.clone(), &stem, &settings, progress).and_then(
+                |converted| {
+                    zip_formats(
+                        &stem,
+                        &converted.pages,
+                        &converted.attachments,
+                        &data,
+                        &settings,
+                    )
+                },
+            );
+            let result = Object::new();
+            match zip {
+                Ok(zip) => Reflect::set(&result, &"zip".into(), &Uint8Array::from(zip.as_slice()))?,
+                Err(err) => Reflect::set(&result, &"error".into(), &err.to_string().into())?,
+            };
+            results.push(&result);
+        }
+        Ok(results.into())
+    })
+}
@@ -0,0 +1,1674 @@
+use crate::inspect::{
+    Attachment, DocumentDates, DocumentKind, Layer, Link, SearchResult, TextRun, attachments,
+    check_complete, document_dates, document_kind, document_metadata, has_form_values, layers,
+    links, search, text_crop, text_runs, unusual_rotation, uses_cmyk, uses_unsupported_blending,
+};
+use crate::postprocess::{
+    crop, draw_border, grayscale, is_blank, letterbox, side_by_side, soften, trim,
+};
+use crate::provenance::{self, PNG_PROVENANCE_KEYWORD, Provenance};
+use crate::range::{last_named, page_order, select_pages};
+use crate::tile::{self, Tile};
+use crate::xmp::{PNG_XMP_KEYWORD, insert_into_jpeg};
+use crate::{ConversionError, Orientation, OutputFormat, PageOrder, PngCompression, Settings};
+use crate::{filename, font};
+use hayro::{Pdf, RenderSettings, render};
+use hayro_interpret::{InterpreterSettings, InterpreterWarning};
+use image::codecs::jpeg::{JpegEncoder, PixelDensity};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ExtendedColorType, GenericImageView, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use web_time::Instant;
+
+/// Log to the browser console like [`gloo::console::log!`]. Elsewhere there is no console to
+/// call into, so the arguments are only evaluated, which keeps conversions working natively.
+macro_rules! log {
+    ($($arg:expr),+ $(,)?) => {
+        #[cfg(target_arch = "wasm32")]
+        gloo::console::log!($($arg),+);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            $(let _ = &$arg;)+
+        }
+    };
+}
+
+/// Page numbers in filenames are padded to at least this many digits.
+const MIN_NUMBER_WIDTH: usize = 3;
+/// Resolution of a page rendered at scale 1, PDF units are points.
+const POINTS_PER_INCH: f32 = 72.0;
+/// Quality `image` encodes JPEGs with by default, used for everything but the page images.
+const JPEG_QUALITY: u8 = 75;
+/// Most JPEGs encoded per image while searching for the quality that fits
+/// `settings.jpeg_target_kb`, enough to narrow 1 to 100 down to a single quality.
+const JPEG_TARGET_STEPS: u32 = 7;
+
+/// Longest side, in pixels, a page is rendered at. Browsers refuse to decode or draw larger images.
+const MAX_DIMENSION: f32 = 16384.0;
+/// Most pixels a single page is rendered with, to bound memory use.
+pub(crate) const MAX_PIXELS: f32 = 64.0 * 1024.0 * 1024.0;
+
+/// A single rendered page, encoded into every enabled output format.
+#[derive(Serialize, Deserialize)]
+pub struct RenderedPage {
+    /// 1-indexed page number in the source document.
+    pub number: usize,
+    /// Number of the right-hand page, when this is a spread of two facing pages.
+    pub spread_with: Option<usize>,
+    /// Width and height of the rendered image in pixels.
+    pub dimensions: (u32, u32),
+    /// Dimensions the page would have had, when it was too large and was rendered smaller.
+    pub downscaled_from: Option<(u32, u32)>,
+    /// The whole render scale the page was snapped to, with `settings.integer_scale`.
+    pub snapped_scale: Option<f32>,
+    /// Some pixels aren't fully opaque. As hayro renders onto white this is never the case yet,
+    /// but tooling reading the manifest shouldn't have to know that.
+    pub has_alpha: bool,
+    /// Problems hayro ran into while rendering, which may make the page look off.
+    pub warnings: Vec<Cow<'static, str>>,
+    /// Links on the page, or on both pages of a spread, with `settings.links`.
+    pub links: Vec<Link>,
+    /// Text on the page, or on both pages of a spread, with `settings.text_positions`.
+    pub text: Vec<TextRun>,
+    /// Pixels per inch of the main image, for placing it at its physical size.
+    pub(crate) dpi: f32,
+    /// Quality the main JPEG was encoded at when it was searched for to fit
+    /// `settings.jpeg_target_kb`.
+    pub jpeg_quality: Option<u8>,
+    /// Filename of the image without extension, from the filename template.
+    pub(crate) name: String,
+    encoded: BTreeMap<OutputFormat, Vec<u8>>,
+    /// Hex SHA-256 of each encoded image, when checksums were requested.
+    checksums: BTreeMap<OutputFormat, String>,
+    /// Suffix like `@1x` of the main image, when the page was exported at several scales.
+    scale_suffix: Option<String>,
+    /// The page at every other export scale.
+    variants: Vec<Variant>,
+    /// The page split into a grid, in tile mode. Archives hold these instead of the whole page.
+    tiles: Vec<Tile>,
+    /// A small JPEG of the page for the web, with `settings.web_copies`.
+    web: Option<Vec<u8>>,
+}
+
+impl RenderedPage {
+    /// The page encoded as `format`, unless that format was disabled when it was rendered.
+    pub fn bytes(&self, format: OutputFormat) -> Option<&[u8]> {
+        self.encoded.get(&format).map(Vec::as_slice)
+    }
+
+    /// Any one encoding of the page, for previews that don't care about the format.
+    pub fn first_encoded(&self) -> Option<(OutputFormat, &[u8])> {
+        self.encoded
+            .iter()
+            .next()
+            .map(|(format, bytes)| (*format, bytes.as_slice()))
+    }
+
+    /// Number of the last source page in this image, the right-hand one of a spread.
+    pub fn last_number(&self) -> usize {
+        self.spread_with.unwrap_or(self.number)
+    }
+
+    /// "Page 4" or, for spreads, "Pages 4–5".
+    pub fn label(&self) -> String {
+        match self.spread_with {
+            Some(right) => format!("Pages {}–{right}", self.number),
+            None => format!("Page {}", self.number),
+        }
+    }
+
+    pub fn checksum(&self, format: OutputFormat) -> Option<&str> {
+        self.checksums.get(&format).map(String::as_str)
+    }
+
+    pub fn filename(&self, format: OutputFormat) -> String {
+        format!("{}.{}", self.name, format.extension())
+    }
+
+    /// Every size of the page encoded as `format`, with its filename and dimensions: only the
+    /// main image, or one image per scale when exporting at several.
+    pub(crate) fn scaled_images(&self, format: OutputFormat) -> Vec<(String, (u32, u32), &[u8])> {
+        let Some(bytes) = self.bytes(format) else {
+            return Vec::new();
+        };
+        let Some(suffix) = &self.scale_suffix else {
+            return vec![(self.filename(format), self.dimensions, bytes)];
+        };
+        let scaled_name = |suffix: &str| format!("{}{suffix}.{}", self.name, format.extension());
+        let mut images = vec![(scaled_name(suffix), self.dimensions, bytes)];
+        for variant in &self.variants {
+            if let Some(bytes) = variant.encoded.get(&format) {
+                images.push((scaled_name(&variant.suffix), variant.dimensions, bytes));
+            }
+        }
+        images
+    }
+
+    /// The web copy of the page with its filename, if one was made.
+    pub(crate) fn web_copy(&self) -> Option<(String, &[u8])> {
+        let bytes = self.web.as_deref()?;
+        Some((self.filename(OutputFormat::Jpeg), bytes))
+    }
+
+    /// Whether the page was split into tiles.
+    pub fn is_tiled(&self) -> bool {
+        !self.tiles.is_empty()
+    }
+
+    pub(crate) fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    pub(crate) fn tile_filename(&self, tile: &Tile, format: OutputFormat) -> String {
+        format!(
+            "{}-tile-{}-{}.{}",
+            self.name,
+            tile.row,
+            tile.column,
+            format.extension()
+        )
+    }
+}
+
+/// The result of converting a document.
+#[derive(Serialize, Deserialize)]
+pub struct Converted {
+    pub pages: Vec<RenderedPage>,
+    /// Number of pages in the document, which can be more than were rendered.
+    pub page_count: usize,
+    /// The document has CMYK content, which was converted to RGB.
+    pub uses_cmyk: bool,
+    pub kind: DocumentKind,
+    /// The document has filled-in form fields, whose values can't be rendered.
+    pub has_form_values: bool,
+    /// Numbers of the pages left out for being blank.
+    pub blank_pages: Vec<usize>,
+    /// Layers of the document, rendered as they are shown by default.
+    pub layers: Vec<Layer>,
+    /// Files embedded in the document.
+    pub attachments: Vec<Attachment>,
+    /// What searching for `settings.search` found, if it was set.
+    pub search: Option<SearchResult>,
+    /// How many pages were selected, when `settings.max_pages` left some of them out.
+    pub truncated_from: Option<usize>,
+    /// The PNG compression the last pages were encoded with, when encoding took longer than
+    /// `settings.compression_budget_ms` and fell back to a faster one.
+    pub compression_fallback: Option<PngCompression>,
+    /// Names of the fonts `settings.substitute_font` was drawn in place of, see
+    /// [`SubstituteFont`](crate::SubstituteFont).
+    pub substituted_fonts: Vec<String>,
+}
+
+/// Render the pages of the PDF in `data`, encoding each into every format in `settings.formats`.
+pub fn convert(
+    data: Arc<Vec<u8>>,
+    stem: &str,
+    settings: &Settings,
+) -> Result<Converted, ConversionError> {
+    Conversion::new(data, stem, settings)?.finish()
+}
+
+/// Every page of the PDF in `data` encoded as `format` with the default settings, in order, for
+/// callers that only want the images.
+pub fn render_pdf_to_images(
+    data: Vec<u8>,
+    format: OutputFormat,
+) -> Result<Vec<Vec<u8>>, ConversionError> {
+    let settings = Settings {
+        formats: BTreeSet::from([format]),
+        ..Settings::default()
+    };
+    let converted = convert(Arc::new(data), "document", &settings)?;
+    Ok(converted
+        .pages
+        .into_iter()
+        .filter_map(|mut page| page.encoded.remove(&format))
+        .collect())
+}
+
+/// [`convert`], calling `progress` with the number of pages rendered so far and the number of
+/// pages to render after every page.
+pub fn convert_with_progress(
+    data: Arc<Vec<u8>>,
+    stem: &str,
+    settings: &Settings,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Converted, ConversionError> {
+    let mut conversion = Conversion::new(data, stem, settings)?;
+    while !conversion.is_done() {
+        conversion.render_next()?;
+        let (done, total) = conversion.progress();
+        progress(done, total);
+    }
+    conversion.finish()
+}
+
+/// A conversion in progress, which renders one page at a time on request so callers can do
+/// other work in between, like keeping a page responsive. [`convert`] runs one to the end.
+pub struct Conversion {
+    pdf: Pdf,
+    stem: String,
+    settings: Settings,
+    /// `(position in the order, page number)` of the pages to render, filenames are numbered by
+    /// the former.
+    sequence: Vec<(usize, usize)>,
+    /// Index into `sequence` of the next page to render.
+    next: usize,
+    warnings: Arc<Mutex<Vec<&'static str>>>,
+    /// Fonts drawn with `settings.substitute_font`, added to while rendering.
+    substituted_fonts: Arc<Mutex<BTreeSet<String>>>,
+    interpreter_settings: InterpreterSettings,
+    /// Digits page numbers in filenames are padded to.
+    number_width: usize,
+    dates: DocumentDates,
+    settings_hash: String,
+    xmp: Option<String>,
+    scales: Vec<f32>,
+    /// The largest of `scales`, which pages are rendered at.
+    largest_scale: f32,
+    compression: Cell<PngCompression>,
+    pages: Vec<RenderedPage>,
+    /// Left-hand page waiting for its facing page, in spread mode.
+    left: Option<PageImage>,
+    blank_pages: Vec<usize>,
+    search: Option<SearchResult>,
+    truncated_from: Option<usize>,
+    /// Links of the pages yet to render, by page number.
+    links: HashMap<usize, Vec<Link>>,
+    started: Instant,
+    /// Applied on top of the scale the settings ask for, see [`Self::set_scale_factor`].
+    scale_factor: f32,
+    /// Pixels of every page rendered so far.
+    rendered_pixels: u64,
+}
+
+impl Conversion {
+    pub fn new(
+        data: Arc<Vec<u8>>,
+        stem: &str,
+        settings: &Settings,
+    ) -> Result<Self, ConversionError> {
+        check_complete(&data)?;
+        let pdf = Pdf::new(data)?;
+        let page_count = pdf.pages().len();
+        let order = page_order(settings.page_order, &settings.custom_order, page_count)?;
+        let selected = select_pages(&settings.pages, &settings.exclude_pages, page_count)?;
+        if let Some(last) = last_named(&settings.pages).filter(|&last| last > page_count) {
+            log!(
+                "pages up to",
+                last,
+                "were asked for but",
+                stem,
+                "has",
+                page_count,
+                ", skipping those past the end"
+            );
+        }
+        let mut sequence: Vec<_> = (1..)
+            .zip(order)
+            .filter(|(_, number)| selected.contains(number))
+            .collect();
+        let query = settings.search.trim();
+        let search = (!query.is_empty()).then(|| match search(&pdf, query) {
+            Some(matches) => {
+                sequence.retain(|(_, number)| matches.contains(number));
+                SearchResult::Matched(sequence.len())
+            }
+            None => {
+                sequence.clear();
+                SearchResult::NoText
+            }
+        });
+        let selected_count = sequence.len();
+        if let Some(max_pages) = settings.max_pages {
+            sequence.truncate(max_pages as usize);
+        }
+        let truncated_from = (sequence.len() < selected_count).then_some(selected_count);
+        if settings.first_page_only {
+            sequence.truncate(1);
+        }
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let substituted_fonts = Arc::new(Mutex::new(BTreeSet::new()));
+        let mut interpreter_settings = InterpreterSettings {
+            warning_sink: {
+                let warnings = warnings.clone();
+                Arc::new(move |warning| warnings.lock().unwrap().push(describe(warning)))
+            },
+            ..Default::default()
+        };
+        if let Some(font) = &settings.substitute_font {
+            interpreter_settings.font_resolver = font::resolver(
+                font.clone(),
+                interpreter_settings.font_resolver,
+                substituted_fonts.clone(),
+            );
+        }
+        let xmp = settings
+            .embed_xmp
+            .then(|| settings.xmp.or(&document_metadata(&pdf)).packet());
+        let (scales, largest_scale) = match settings.pixels_per_point {
+            Some(pixels_per_point) => (Vec::new(), pixels_per_point),
+            None => {
+                let scales = settings.scale_list()?;
+                // with several scales, pages are rendered once at the largest and scaled down to
+                // the others
+                let largest_scale = scales.iter().copied().fold(1.0, f32::max);
+                (scales, largest_scale)
+            }
+        };
+        Ok(Self {
+            stem: stem.to_string(),
+            settings: settings.clone(),
+            sequence,
+            next: 0,
+            warnings,
+            substituted_fonts,
+            interpreter_settings,
+            number_width: number_width(page_count),
+            dates: document_dates(&pdf),
+            settings_hash: settings.hash(),
+            xmp,
+            scales,
+            largest_scale,
+            compression: Cell::new(settings.png_compression),
+            pages: Vec::new(),
+            left: None,
+            blank_pages: Vec::new(),
+            search,
+            truncated_from,
+            links: if settings.links {
+                links(&pdf)
+            } else {
+                HashMap::new()
+            },
+            started: Instant::now(),
+            scale_factor: 1.0,
+            rendered_pixels: 0,
+            pdf,
+        })
+    }
+
+    /// How many of the pages to render have been rendered, and how many there are.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.sequence.len())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next == self.sequence.len()
+    }
+
+    /// Render the pages that are left at `factor` times the scale the settings ask for, like to
+    /// save memory when many documents are converted at once.
+    pub fn set_scale_factor(&mut self, factor: f32) {
+        self.scale_factor = factor;
+    }
+
+    /// How many pixels the pages rendered so far have in total.
+    pub fn rendered_pixels(&self) -> u64 {
+        self.rendered_pixels
+    }
+
+    /// Render and encode the next page, if there is one left.
+    pub fn render_next(&mut self) -> Result<(), ConversionError> {
+        let Some(&(position, page_num)) = self.sequence.get(self.next) else {
+            return Ok(());
+        };
+        self.next += 1;
+        let settings = &self.settings;
+        let largest_scale = self.largest_scale * self.scale_factor;
+        let page = &self.pdf.pages()[page_num - 1]; // 1-indexed!
+        let page_size = page.render_dimensions();
+        let rotation = unusual_rotation(page);
+        let turn = rotation.map_or(0, |(_, turn)| turn);
+        if let Some((degrees, _)) = rotation {
+            log!(
+                "page",
+                page_num,
+                &self.stem,
+                "has a rotation of",
+                degrees,
+                "degrees, turning it by",
+                turn
+            );
+        }
+        let turned = if turn % 180 == 90 {
+            (page_size.1, page_size.0)
+        } else {
+            page_size
+        };
+        let wrong_way = match settings.page_orientation {
+            Orientation::MatchPage => false,
+            Orientation::Portrait => turned.0 > turned.1,
+            Orientation::Landscape => turned.1 > turned.0,
+        };
+        let turn = if wrong_way {
+            log!(
+                "turning page",
+                page_num,
+                &self.stem,
+                "to",
+                settings.page_orientation.label()
+            );
+            (turn + 90) % 360
+        } else {
+            turn
+        };
+        let turn = (turn + settings.rotation / 90 % 4 * 90) % 360;
+        // everything is sized for the page as it ends up, hayro renders it before the turn
+        let quarter_turn = turn % 180 == 90;
+        let page_size = if quarter_turn {
+            (page_size.1, page_size.0)
+        } else {
+            page_size
+        };
+        let region = if settings.crop_to_text {
+            text_crop(page).map(|crop| crop.turned(turn))
+        } else {
+            settings.crop
+        };
+        // what is left of the page once cropped is what gets fitted
+        let visible = region.map_or(page_size, |crop| crop.of(page_size));
+        let exact = settings.pixels_per_point.is_some();
+        let mut canvas = settings.paper_size.filter(|_| !exact).map(|paper| {
+            let (width, height) =
+                paper.canvas(settings.paper_dpi, settings.paper_orientation, visible);
+            (
+                (width as f32 * largest_scale).round() as u32,
+                (height as f32 * largest_scale).round() as u32,
+            )
+        });
+        let mut scale = match canvas {
+            Some((width, height)) => (width as f32 / visible.0).min(height as f32 / visible.1),
+            None if exact => largest_scale,
+            None => {
+                target_scale(visible, settings.target_width, settings.target_height)
+                    * largest_scale
+                    * self.large_page_shrink(page_num, visible)
+            }
+        };
+        let requested = canvas.unwrap_or((
+            (visible.0 * scale).round() as u32,
+            (visible.1 * scale).round() as u32,
+        ));
+        // the whole page is rendered before it is cropped, which has to fit as well
+        let factor = downscale_factor(requested).min(downscale_factor((
+            (page_size.0 * scale).round() as u32,
+            (page_size.1 * scale).round() as u32,
+        )));
+        let limited = limit_factor(requested, settings.max_width, settings.max_height);
+        if limited < factor {
+            log!(
+                "page",
+                page_num,
+                &self.stem,
+                "would be",
+                format!("{}×{}", requested.0, requested.1),
+                "px, scaling it down to",
+                format!(
+                    "{}×{}",
+                    (requested.0 as f32 * limited) as u32,
+                    (requested.1 as f32 * limited) as u32
+                ),
+                "px to stay within the maximum size"
+            );
+        }
+        let factor = factor.min(limited);
+        let mut downscaled_from = None;
+        if factor < 1.0 {
+            scale *= factor;
+            canvas = canvas.map(|(width, height)| {
+                (
+                    (width as f32 * factor) as u32,
+                    (height as f32 * factor) as u32,
+                )
+            });
+            downscaled_from = Some(requested);
+        }
+        let size = |scale: f32| {
+            (
+                (page_size.0 * scale).round() as u32,
+                (page_size.1 * scale).round() as u32,
+            )
+        };
+        let mut snapped_scale = None;
+        if settings.integer_scale {
+            // rounding up could overflow the paper or the size limits, the scale below is safe
+            // unless the page is too large even at 1x
+            let rounded = scale.round().max(1.0);
+            let limited = settings.max_width.is_some() || settings.max_height.is_some();
+            let snapped = if canvas.is_some() || limited || downscale_factor(size(rounded)) < 1.0 {
+                scale.floor().max(1.0)
+            } else {
+                rounded
+            };
+            if downscale_factor(size(snapped)) >= 1.0 {
+                log!(
+                    "snapped page",
+                    page_num,
+                    &self.stem,
+                    "from scale",
+                    scale,
+                    "to",
+                    snapped
+                );
+                scale = snapped;
+                snapped_scale = Some(snapped);
+            }
+        }
+        // render larger and scale down for smoother edges, as far as the size limits allow
+        let supersample = if settings.supersample {
+            (1..=settings.supersample_factor.clamp(2, 4))
+                .rev()
+                .find(|&factor| downscale_factor(size(scale * factor as f32)) >= 1.0)
+                .unwrap_or(1)
+        } else {
+            1
+        };
+        let render_scale = scale * supersample as f32;
+        // hayro floors the scaled size, which can come out a pixel short of a target size
+        let (render_width, render_height) = size(render_scale);
+        let (render_width, render_height) = if quarter_turn {
+            (render_height, render_width)
+        } else {
+            (render_width, render_height)
+        };
+        let render_settings = RenderSettings {
+            x_scale: render_scale,
+            y_scale: render_scale,
+            width: Some(render_width as u16),
+            height: Some(render_height as u16),
+        };
+        let page_pixmap = render(page, &self.interpreter_settings, &render_settings);
+        let mut page_warnings = std::mem::take(&mut *self.warnings.lock().unwrap());
+        // the same problem tends to be reported for every glyph or tile it affects
+        if rotation.is_some_and(|(degrees, _)| degrees % 90.0 != 0.0) {
+            page_warnings.push("a rotation that isn't a multiple of 90 degrees was ignored");
+        }
+        if uses_unsupported_blending(page) {
+            page_warnings.push(
+                "blend modes or knockout groups were drawn as normal transparency, so it may look different in other viewers",
+            );
+        }
+        page_warnings.sort_unstable();
+        page_warnings.dedup();
+
+        let (width, height) = (page_pixmap.width() as u32, page_pixmap.height() as u32);
+        // hayro paints onto opaque white, so premultiplied and straight alpha are the same here
+        let mut image = RgbaImage::from_raw(width, height, page_pixmap.take_u8())
+            .expect("pixmap buffer matches its dimensions");
+        image = turn_clockwise(image, turn);
+        if supersample > 1 {
+            let (width, height) = size(scale);
+            if settings.anti_moire {
+                image = soften(&image, settings.anti_moire_radius);
+            }
+            image = imageops::resize(&image, width.max(1), height.max(1), FilterType::Lanczos3);
+        }
+        if let Some(region) = region {
+            image = crop(&image, region);
+        }
+
+        if settings.skip_blank_pages && is_blank(&image, settings.background_tolerance) {
+            log!("skipped blank page", page_num, &self.stem);
+            self.blank_pages.push(page_num);
+            return Ok(());
+        }
+        if settings.trim_margins {
+            image = trim(&image, settings.background_tolerance, settings.trim_padding);
+        }
+        if let Some((width, height)) = canvas {
+            image = letterbox(&image, width, height);
+        }
+        if settings.border {
+            let thickness = (settings.border_thickness as f32 * largest_scale).round() as u32;
+            draw_border(&mut image, thickness, settings.border_color);
+        }
+
+        let text = if settings.text_positions {
+            text_runs(page, page_num)
+        } else {
+            Vec::new()
+        };
+        self.rendered_pixels += u64::from(image.width()) * u64::from(image.height());
+        let page_image = PageImage {
+            number: page_num,
+            // close the gaps left by blank pages, unless the original numbers are wanted
+            position: if settings.renumber_after_blanks {
+                position - self.blank_pages.len()
+            } else {
+                position
+            },
+            image,
+            scale,
+            downscaled_from,
+            snapped_scale,
+            warnings: page_warnings,
+            links: self.links.remove(&page_num).unwrap_or_default(),
+            text,
+        };
+        // progress is reported through `convert_with_progress`, this is for debugging only
+        if cfg!(debug_assertions) {
+            log!("processed page", page_num, &self.stem, "at scale", scale);
+        }
+
+        let standalone_cover =
+            settings.spread_cover && self.pages.is_empty() && self.left.is_none();
+        let finished = if !settings.spreads || standalone_cover {
+            self.finish_page(page_image, None)?
+        } else if let Some(left) = self.left.take() {
+            self.finish_page(left, Some(page_image))?
+        } else {
+            self.left = Some(page_image);
+            return Ok(());
+        };
+        self.pages.push(finished);
+        Ok(())
+    }
+
+    /// Hand out the pages finished so far, so callers writing them out as they go don't have
+    /// to hold on to every page until the end. They are left out of what [`Self::finish`]
+    /// returns.
+    pub fn take_pages(&mut self) -> Vec<RenderedPage> {
+        std::mem::take(&mut self.pages)
+    }
+
+    /// Render the pages that are left and gather the results.
+    pub fn finish(mut self) -> Result<Converted, ConversionError> {
+        while !self.is_done() {
+            self.render_next()?;
+        }
+        // an odd page out at the end stays on its own
+        if let Some(left) = self.left.take() {
+            let finished = self.finish_page(left, None)?;
+            self.pages.push(finished);
+        }
+        log!(
+            "processed all pages for",
+            &self.stem,
+            self.started.elapsed().as_secs_f32(),
+            "s"
+        );
+
+        let compression = self.compression.get();
+        Ok(Converted {
+            page_count: self.pdf.pages().len(),
+            uses_cmyk: uses_cmyk(&self.pdf),
+            kind: document_kind(&self.pdf),
+            has_form_values: has_form_values(&self.pdf),
+            layers: layers(&self.pdf),
+            attachments: attachments(&self.pdf),
+            search: self.search,
+            truncated_from: self.truncated_from,
+            compression_fallback: (compression != self.settings.png_compression)
+                .then_some(compression),
+            substituted_fonts: std::mem::take(&mut *self.substituted_fonts.lock().unwrap())
+                .into_iter()
+                .collect(),
+            pages: self.pages,
+            blank_pages: self.blank_pages,
+        })
+    }
+
+    /// How much to scale down a page of `page_size` points that is larger than
+    /// [`Settings::large_page_threshold`], or 1. Pages fitted to a target size are left alone.
+    fn large_page_shrink(&self, page_num: usize, page_size: (f32, f32)) -> f32 {
+        let settings = &self.settings;
+        let Some(threshold) = settings.large_page_threshold else {
+            return 1.0;
+        };
+        let longest = page_size.0.max(page_size.1);
+        if settings.target_width.is_some()
+            || settings.target_height.is_some()
+            || longest <= threshold as f32
+        {
+            return 1.0;
+        }
+        let shrink = threshold as f32 / longest;
+        log!(
+            "page",
+            page_num,
+            &self.stem,
+            "is",
+            longest,
+            "points long, scaling it down by",
+            shrink
+        );
+        shrink
+    }
+
+    /// Filename of a page or spread from its position or positions in the order.
+    fn name(&self, position: usize, spread_with: Option<usize>) -> String {
+        let width = self.number_width;
+        let page = match spread_with {
+            Some(right) => format!("{position:0>width$}-{right:0>width$}"),
+            None => format!("{position:0>width$}"),
+        };
+        filename::expand(
+            &self.settings.filename_template,
+            &self.stem,
+            &page,
+            &self.dates,
+            &self.settings_hash,
+        )
+    }
+
+    fn finish_page(
+        &self,
+        left: PageImage,
+        right: Option<PageImage>,
+    ) -> Result<RenderedPage, ConversionError> {
+        let name = |position, spread_with| self.name(position, spread_with);
+        let output = Output {
+            settings: &self.settings,
+            scales: &self.scales,
+            largest_scale: self.largest_scale,
+            xmp: self.xmp.as_deref(),
+            settings_hash: &self.settings_hash,
+            name: &name,
+            compression: &self.compression,
+        };
+        finish(left, right, &output)
+    }
+}
+
+/// How many digits page numbers are zero-padded to in a document of `page_count` pages: those of
+/// the last page, so filenames sort in page order, and at least [`MIN_NUMBER_WIDTH`].
+fn number_width(page_count: usize) -> usize {
+    page_count.to_string().len().max(MIN_NUMBER_WIDTH)
+}
+
+/// Render the page at 0-indexed `page_index` of the PDF in `data` as [`convert`] would, encoded
+/// as the first of `settings.formats`. Settings picking, ordering or combining pages are ignored,
+/// as are every scale but the first.
+pub fn render_page(
+    data: &[u8],
+    page_index: usize,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let number = page_index.saturating_add(1);
+    let format = settings
+        .formats
+        .first()
+        .copied()
+        .unwrap_or(OutputFormat::Png);
+    let scale = settings.scale_list()?.first().map(f32::to_string);
+    let settings = Settings {
+        formats: [format].into(),
+        pages: number.to_string(),
+        exclude_pages: String::new(),
+        max_pages: None,
+        search: String::new(),
+        page_order: PageOrder::Document,
+        skip_blank_pages: false,
+        tiles: false,
+        scales: scale.unwrap_or_default(),
+        first_page_only: false,
+        spreads: false,
+        checksums: false,
+        web_copies: false,
+        ..settings.clone()
+    };
+    // pages past the end are left out of the selection rather than rejected
+    let converted = convert(Arc::new(data.to_vec()), "", &settings)?;
+    converted
+        .pages
+        .into_iter()
+        .next()
+        .and_then(|page| page.encoded.into_values().next())
+        .ok_or(ConversionError::NoSuchPage(number))
+}
+
+/// Render page `number` of the PDF in `data` as a PNG exactly `width` pixels wide, for showing
+/// on screen. Unlike [`render_page`] it ignores every setting.
+pub fn render_preview(
+    data: Arc<Vec<u8>>,
+    number: usize,
+    width: u32,
+) -> Result<Vec<u8>, ConversionError> {
+    let pdf = Pdf::new(data)?;
+    let pages = pdf.pages();
+    let page = number
+        .checked_sub(1)
+        .and_then(|index| pages.get(index))
+        .ok_or(ConversionError::NoSuchPage(number))?;
+    let page_size = page.render_dimensions();
+    let turn = unusual_rotation(page).map_or(0, |(_, turn)| turn);
+    // scaled to the width of the page as it ends up after the turn
+    let scale_by = if turn % 180 == 90 {
+        page_size.1
+    } else {
+        page_size.0
+    };
+    let mut scale = width as f32 / scale_by;
+    let size = (
+        (page_size.0 * scale).round() as u32,
+        (page_size.1 * scale).round() as u32,
+    );
+    scale *= downscale_factor(size);
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        width: Some((page_size.0 * scale).round() as u16),
+        height: Some((page_size.1 * scale).round() as u16),
+    };
+    let pixmap = render(page, &InterpreterSettings::default(), &render_settings);
+    let (width, height) = (pixmap.width() as u32, pixmap.height() as u32);
+    let image = RgbaImage::from_raw(width, height, pixmap.take_u8())
+        .expect("pixmap buffer matches its dimensions");
+    let image = turn_clockwise(image, turn);
+    encode(
+        &DynamicImage::ImageRgba8(image),
+        OutputFormat::Png,
+        None,
+        None,
+        PngCompression::default(),
+        None,
+    )
+}
+
+/// A rendered page at one of several export scales.
+#[derive(Serialize, Deserialize)]
+struct Variant {
+    /// `@2x` and the like.
+    suffix: String,
+    dimensions: (u32, u32),
+    encoded: BTreeMap<OutputFormat, Vec<u8>>,
+}
+
+/// What [`finish`] needs to know about the whole document.
+struct Output<'a> {
+    settings: &'a Settings,
+    /// Scales to export at besides the main image, see [`Settings::scale_list`].
+    scales: &'a [f32],
+    /// The largest of `scales`, which pages were rendered at.
+    largest_scale: f32,
+    xmp: Option<&'a str>,
+    settings_hash: &'a str,
+    /// Filename of a page or spread from its position or positions in the order.
+    name: &'a dyn Fn(usize, Option<usize>) -> String,
+    /// PNG compression for the next image, lowered when one takes too long.
+    compression: &'a Cell<PngCompression>,
+}
+
+/// A rendered page before it is encoded.
+struct PageImage {
+    number: usize,
+    /// Where the page is in the chosen order, which its filename is numbered by.
+    position: usize,
+    image: RgbaImage,
+    scale: f32,
+    downscaled_from: Option<(u32, u32)>,
+    snapped_scale: Option<f32>,
+    warnings: Vec<&'static str>,
+    links: Vec<Link>,
+    text: Vec<TextRun>,
+}
+
+/// Encode a page, or a spread of `left` and `right` side by side, into every enabled format.
+fn finish(
+    left: PageImage,
+    right: Option<PageImage>,
+    output: &Output,
+) -> Result<RenderedPage, ConversionError> {
+    let Output {
+        settings,
+        scales,
+        largest_scale,
+        xmp,
+        settings_hash,
+        name,
+        compression,
+    } = *output;
+    let budget = (settings.compression_budget_ms > 0)
+        .then(|| Duration::from_millis(settings.compression_budget_ms.into()));
+    let right_number = right.as_ref().map(|right| right.number);
+    let fixed_quality = settings.jpeg_quality.clamp(1, 100);
+    // encode as `format`, falling back to faster PNG compression for what follows if too slow
+    // returns the searched quality too, for JPEGs with a target size
+    let encode_within_budget = |image: &DynamicImage, format: OutputFormat, dpi, scale| {
+        let current = compression.get();
+        let provenance = |quality| {
+            settings.embed_provenance.then(|| {
+                Provenance {
+                    tool: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    page: left.number,
+                    spread_with: right_number,
+                    format,
+                    scale,
+                    jpeg_quality: (format == OutputFormat::Jpeg).then_some(quality),
+                    png_compression: (format == OutputFormat::Png).then_some(current),
+                    settings_hash,
+                    settings,
+                }
+                .json()
+            })
+        };
+        if format == OutputFormat::Jpeg && settings.jpeg_target_kb > 0 {
+            let target = settings.jpeg_target_kb as usize * 1000;
+            let (bytes, quality) = encode_jpeg_under(image, target, dpi, xmp, provenance)?;
+            return Ok((bytes, Some(quality)));
+        }
+        let started = Instant::now();
+        let bytes = encode_with_quality(
+            image,
+            format,
+            dpi,
+            xmp,
+            current,
+            provenance(fixed_quality).as_deref(),
+            fixed_quality,
+        )?;
+        let elapsed = started.elapsed();
+        if format == OutputFormat::Png
+            && budget.is_some_and(|budget| elapsed > budget)
+            && let Some(faster) = current.faster()
+        {
+            log!(
+                "compressing page",
+                left.number,
+                "took",
+                elapsed.as_secs_f32(),
+                "s, falling back to",
+                faster.label(),
+                "compression"
+            );
+            compression.set(faster);
+        }
+        Ok::<_, ConversionError>((bytes, None))
+    };
+    let mut warnings = left.warnings;
+    let mut links = left.links;
+    let mut text = left.text;
+    let right_position = right.as_ref().map(|right| right.position);
+    let (image, spread_with) = match right {
+        Some(right) => {
+            warnings.extend(right.warnings);
+            links.extend(right.links);
+            text.extend(right.text);
+            warnings.sort_unstable();
+            warnings.dedup();
+            // right-to-left documents start a spread on the right-hand side
+            let image = if settings.right_to_left {
+                side_by_side(&right.image, &left.image)
+            } else {
+                side_by_side(&left.image, &right.image)
+            };
+            (image, Some(right.number))
+        }
+        None => (left.image, None),
+    };
+
+    let has_alpha = image.pixels().any(|pixel| pixel[3] < u8::MAX);
+    // the smaller export scales are scaled down from a blurred copy, the largest stays sharp
+    let colors = |image| {
+        if settings.grayscale {
+            grayscale(image, has_alpha)
+        } else {
+            DynamicImage::ImageRgba8(image)
+        }
+    };
+    let softened = (settings.anti_moire && !settings.integer_scale && scales.len() > 1)
+        .then(|| colors(soften(&image, settings.anti_moire_radius)));
+    let rendered = colors(image);
+    // the page at `scale` of the export scales, from the rendering at the largest of them
+    let at_scale = |scale: f32| {
+        let image = if scale == largest_scale {
+            rendered.clone()
+        } else {
+            let ratio = scale / largest_scale;
+            // snapped scales are for pixel-exact pages, which smoothing would blur
+            let filter = if settings.integer_scale {
+                FilterType::Nearest
+            } else {
+                FilterType::Lanczos3
+            };
+            softened.as_ref().unwrap_or(&rendered).resize_exact(
+                ((rendered.width() as f32 * ratio).round() as u32).max(1),
+                ((rendered.height() as f32 * ratio).round() as u32).max(1),
+                filter,
+            )
+        };
+        let dpi = settings
+            .dpi_metadata
+            .then_some(POINTS_PER_INCH * left.scale * scale / largest_scale);
+        let mut jpeg_quality = None;
+        let encoded = settings
+            .formats
+            .iter()
+            .map(|format| {
+                let (bytes, quality) =
+                    encode_within_budget(&image, *format, dpi, left.scale * scale / largest_scale)?;
+                jpeg_quality = jpeg_quality.or(quality);
+                Ok((*format, bytes))
+            })
+            .collect::<Result<BTreeMap<_, _>, ConversionError>>()?;
+        Ok::<_, ConversionError>((image, dpi, encoded, jpeg_quality))
+    };
+    let suffix = |scale: f32| format!("@{scale}x");
+    // the first scale listed is the main image, the one previews and loose downloads use
+    let main_scale = scales.first().copied().unwrap_or(largest_scale);
+    let (image, dpi, encoded, jpeg_quality) = at_scale(main_scale)?;
+    let resolution = POINTS_PER_INCH * left.scale * main_scale / largest_scale;
+    let variants = scales
+        .iter()
+        .skip(1)
+        .map(|&scale| {
+            let (image, _, encoded, _) = at_scale(scale)?;
+            Ok(Variant {
+                suffix: suffix(scale),
+                dimensions: image.dimensions(),
+                encoded,
+            })
+        })
+        .collect::<Result<_, ConversionError>>()?;
+    let tiles = if settings.tiles {
+        tile::split(
+            &image,
+            settings.tile_size,
+            settings.formats.iter().copied(),
+            dpi,
+            xmp,
+            compression.get(),
+            // tiles of a page searched for a target size share its quality
+            jpeg_quality.unwrap_or(fixed_quality),
+        )?
+    } else {
+        Vec::new()
+    };
+    let web = settings
+        .web_copies
+        .then(|| web_copy(&rendered, settings, xmp))
+        .transpose()?;
+    let checksums = if settings.checksums {
+        encoded
+            .iter()
+            .map(|(format, bytes)| (*format, format!("{:x}", Sha256::digest(bytes))))
+            .collect()
+    } else {
+        BTreeMap::new()
+    };
+    Ok(RenderedPage {
+        number: left.number,
+        spread_with,
+        dimensions: image.dimensions(),
+        downscaled_from: left.downscaled_from,
+        snapped_scale: left.snapped_scale,
+        has_alpha,
+        warnings: warnings.into_iter().map(Cow::Borrowed).collect(),
+        links,
+        text,
+        dpi: resolution,
+        jpeg_quality,
+        name: name(left.position, right_position),
+        encoded,
+        checksums,
+        scale_suffix: (!scales.is_empty()).then(|| suffix(main_scale)),
+        variants,
+        tiles,
+        web,
+    })
+}
+
+/// `image` as a JPEG of `settings.web_quality`, scaled down to fit `settings.web_max_size`.
+fn web_copy(
+    image: &DynamicImage,
+    settings: &Settings,
+    xmp: Option<&str>,
+) -> Result<Vec<u8>, ConversionError> {
+    let size = settings.web_max_size.max(1);
+    let image = if image.width() > size || image.height() > size {
+        image.resize(size, size, FilterType::Lanczos3)
+    } else {
+        image.clone()
+    };
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, settings.web_quality.clamp(1, 100))
+        .encode_image(&image.to_rgb8())?;
+    if let Some(xmp) = xmp {
+        insert_into_jpeg(&mut bytes, xmp)?;
+    }
+    Ok(bytes)
+}
+
+fn describe(warning: InterpreterWarning) -> &'static str {
+    match warning {
+        InterpreterWarning::JpxImage => "JPEG 2000 images are not supported",
+        InterpreterWarning::UnsupportedFont => "a font is not supported",
+        InterpreterWarning::ImageDecodeFailure => "an image failed to decode",
+    }
+}
+
+/// `image` turned clockwise by `degrees`, a multiple of 90.
+fn turn_clockwise(image: RgbaImage, degrees: u32) -> RgbaImage {
+    match degrees {
+        90 => imageops::rotate90(&image),
+        180 => imageops::rotate180(&image),
+        270 => imageops::rotate270(&image),
+        _ => image,
+    }
+}
+
+/// The scale fitting a page of `page_size` to the target width and height, where set.
+fn target_scale(page_size: (f32, f32), width: Option<u32>, height: Option<u32>) -> f32 {
+    let by_width = width.map(|width| width as f32 / page_size.0);
+    let by_height = height.map(|height| height as f32 / page_size.1);
+    match (by_width, by_height) {
+        (Some(by_width), Some(by_height)) => by_width.min(by_height),
+        (by_width, by_height) => by_width.or(by_height).unwrap_or(1.0),
+    }
+}
+
+/// How much an image of `(width, height)` has to shrink to stay within `max_width` and
+/// `max_height` pixels, or `1.0` if it already fits or there are no limits.
+fn limit_factor(
+    (width, height): (u32, u32),
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+) -> f32 {
+    let by_width = max_width.map_or(1.0, |max| max as f32 / width as f32);
+    let by_height = max_height.map_or(1.0, |max| max as f32 / height as f32);
+    by_width.min(by_height).min(1.0)
+}
+
+/// How much an image of `(width, height)` has to shrink to stay within [`MAX_DIMENSION`] and
+/// [`MAX_PIXELS`], or `1.0` if it already fits.
+fn downscale_factor((width, height): (u32, u32)) -> f32 {
+    let (width, height) = (width as f32, height as f32);
+    let by_side = MAX_DIMENSION / width.max(height);
+    let by_area = (MAX_PIXELS / (width * height)).sqrt();
+    by_side.min(by_area).min(1.0)
+}
+
+/// Encode `image` as `format`, recording `dpi` and an XMP packet in its header if given. PNGs
+/// are compressed with `compression`, JPEGs get a quality of 75.
+pub(crate) fn encode(
+    image: &DynamicImage,
+    format: OutputFormat,
+    dpi: Option<f32>,
+    xmp: Option<&str>,
+    compression: PngCompression,
+    provenance: Option<&str>,
+) -> Result<Vec<u8>, ConversionError> {
+    encode_with_quality(
+        image,
+        format,
+        dpi,
+        xmp,
+        compression,
+        provenance,
+        JPEG_QUALITY,
+    )
+}
+
+/// `image` as the best quality JPEG of at most `target` bytes, and that quality, found by a
+/// binary search of at most [`JPEG_TARGET_STEPS`] encodings. When even the lowest quality is
+/// too large, that one is returned anyway.
+fn encode_jpeg_under(
+    image: &DynamicImage,
+    target: usize,
+    dpi: Option<f32>,
+    xmp: Option<&str>,
+    provenance: impl Fn(u8) -> Option<String>,
+) -> Result<(Vec<u8>, u8), ConversionError> {
+    let encode_at = |quality| {
+        encode_with_quality(
+            image,
+            OutputFormat::Jpeg,
+            dpi,
+            xmp,
+            PngCompression::default(),
+            provenance(quality).as_deref(),
+            quality,
+        )
+    };
+    let (mut low, mut high) = (1, 100);
+    let mut best = None;
+    for _ in 0..JPEG_TARGET_STEPS {
+        if low > high {
+            break;
+        }
+        let quality = low + (high - low) / 2;
+        let bytes = encode_at(quality)?;
+        if bytes.len() <= target {
+            best = Some((bytes, quality));
+            low = quality + 1;
+        } else {
+            high = quality - 1;
+        }
+    }
+    match best {
+        Some(best) => Ok(best),
+        None => Ok((encode_at(1)?, 1)),
+    }
+}
+
+/// [`encode`] with JPEGs at `jpeg_quality` from 1 to 100.
+pub(crate) fn encode_with_quality(
+    image: &DynamicImage,
+    format: OutputFormat,
+    dpi: Option<f32>,
+    xmp: Option<&str>,
+    compression: PngCompression,
+    provenance: Option<&str>,
+    jpeg_quality: u8,
+) -> Result<Vec<u8>, ConversionError> {
+    let mut bytes = Vec::new();
+    match format {
+        OutputFormat::Png
+            if dpi.is_none()
+                && xmp.is_none()
+                && provenance.is_none()
+                && compression == PngCompression::default() =>
+        {
+            image.write_to(&mut Cursor::new(&mut bytes), format.image_format())?
+        }
+        // `image`'s PNG encoder can't write `pHYs` or `iTXt` chunks or be told how hard to
+        // compress, so go through `png` directly
+        OutputFormat::Png => {
+            // grayscale pages keep their single channel
+            let (color, pixels) = match image {
+                DynamicImage::ImageLuma8(luma) => {
+                    (png::ColorType::Grayscale, luma.as_raw().clone())
+                }
+                DynamicImage::ImageLumaA8(luma) => {
+                    (png::ColorType::GrayscaleAlpha, luma.as_raw().clone())
+                }
+                _ => (png::ColorType::Rgba, image.to_rgba8().into_raw()),
+            };
+            let mut encoder = png::Encoder::new(&mut bytes, image.width(), image.height());
+            encoder.set_color(color);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_compression(compression.png());
+            if let Some(dpi) = dpi {
+                let pixels_per_meter = (dpi / 0.0254).round() as u32;
+                encoder.set_pixel_dims(Some(png::PixelDimensions {
+                    xppu: pixels_per_meter,
+                    yppu: pixels_per_meter,
+                    unit: png::Unit::Meter,
+                }));
+            }
+            if let Some(xmp) = xmp {
+                encoder
+                    .add_itxt_chunk(PNG_XMP_KEYWORD.to_string(), xmp.to_string())
+                    .map_err(|err| ConversionError::EncodeFailed(err.to_string()))?;
+            }
+            if let Some(provenance) = provenance {
+                encoder
+                    .add_itxt_chunk(PNG_PROVENANCE_KEYWORD.to_string(), provenance.to_string())
+                    .map_err(|err| ConversionError::EncodeFailed(err.to_string()))?;
+            }
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&pixels))
+                .map_err(|err| ConversionError::EncodeFailed(err.to_string()))?;
+        }
+        OutputFormat::Jpeg => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut bytes, jpeg_quality);
+            if let Some(dpi) = dpi {
+                encoder.set_pixel_density(PixelDensity::dpi(dpi.round() as u16));
+            }
+            if image.color().has_color() {
+                encoder.encode_image(&image.to_rgb8())?;
+            } else {
+                encoder.encode_image(&image.to_luma8())?;
+            }
+            if let Some(xmp) = xmp {
+                insert_into_jpeg(&mut bytes, xmp)?;
+            }
+            if let Some(provenance) = provenance {
+                provenance::insert_into_jpeg(&mut bytes, provenance)?;
+            }
+        }
+        // `image` writes bare WebP images without the chunks DPI or metadata would go into
+        OutputFormat::Webp => {
+            let (pixels, color) = match image {
+                DynamicImage::ImageLuma8(luma) => (luma.as_raw().clone(), ExtendedColorType::L8),
+                DynamicImage::ImageLumaA8(luma) => (luma.as_raw().clone(), ExtendedColorType::La8),
+                _ => (image.to_rgba8().into_raw(), ExtendedColorType::Rgba8),
+            };
+            WebPEncoder::new_lossless(&mut bytes).encode(
+                &pixels,
+                image.width(),
+                image.height(),
+                color,
+            )?;
+        }
+    }
+
+    // the bytes end up in files named after `format.extension()`, make sure they agree
+    let detected = image::guess_format(&bytes).ok();
+    let expected = ImageFormat::from_extension(format.extension());
+    debug_assert_eq!(
+        detected, expected,
+        "{format:?} encoded as {detected:?}, not matching its extension"
+    );
+    if detected.is_none() || detected != expected {
+        return Err(ConversionError::EncodeFailed(format!(
+            "encoded {} data as {detected:?}",
+            format.label()
+        )));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn number_width_fits_the_last_page() {
+        assert_eq!(number_width(1), MIN_NUMBER_WIDTH);
+        assert_eq!(number_width(999), 3);
+        assert_eq!(number_width(1000), 4);
+        assert_eq!(number_width(1200), 4);
+        assert_eq!(number_width(10_000), 5);
+    }
+
+    #[test]
+    fn encoded_images_match_their_extension() {
+        let image = DynamicImage::new_rgba8(8, 8);
+        for format in OutputFormat::ALL {
+            for (dpi, xmp) in [(None, None), (Some(300.0), Some("<x:xmpmeta/>"))] {
+                let bytes =
+                    encode(&image, format, dpi, xmp, PngCompression::default(), None).unwrap();
+                assert_eq!(
+                    image::guess_format(&bytes).ok(),
+                    ImageFormat::from_extension(format.extension()),
+                    "{format:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn png_density_is_read_back() {
+        let image = DynamicImage::new_rgba8(8, 8);
+        let bytes = encode(
+            &image,
+            OutputFormat::Png,
+            Some(300.0),
+            None,
+            PngCompression::default(),
+            None,
+        )
+        .unwrap();
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+        let dims = reader.info().pixel_dims.unwrap();
+        assert_eq!(dims.unit, png::Unit::Meter);
+        assert_eq!((dims.xppu, dims.yppu), (11811, 11811));
+    }
+
+    #[test]
+    fn jpeg_density_is_read_back() {
+        let image = DynamicImage::new_rgb8(8, 8);
+        let bytes = encode(
+            &image,
+            OutputFormat::Jpeg,
+            Some(300.0),
+            None,
+            PngCompression::default(),
+            None,
+        )
+        .unwrap();
+        let jfif = bytes
+            .windows(5)
+            .position(|window| window == b"JFIF\0")
+            .unwrap();
+        // version, then units (1 for dots per inch) and the horizontal and vertical densities
+        let density = &bytes[jfif + 7..jfif + 12];
+        assert_eq!(density, [1, 0x01, 0x2c, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn filled_forms_are_flagged() {
+        let page = [testing::TestPage::new(100, 100, [255, 255, 255])];
+        let filled = testing::pdf(&page, &["<< /FT /Tx /T (name) /V (Ada) >>"]);
+        let converted = convert(Arc::new(filled), "filled", &Settings::default()).unwrap();
+        assert!(converted.has_form_values);
+        let converted = convert(
+            Arc::new(testing::blank_pdf(1)),
+            "blank",
+            &Settings::default(),
+        );
+        assert!(!converted.unwrap().has_form_values);
+    }
+
+    #[test]
+    fn date_tokens_come_from_the_information_dictionary() {
+        let settings = Settings {
+            filename_template: "{stem}-{created}-{modified}-{page}".to_string(),
+            ..Settings::default()
+        };
+        let page = [testing::TestPage::new(100, 100, [255, 255, 255])];
+        let info = "<< /CreationDate (D:20210304120000Z) /ModDate (D:20220506) >>";
+        let dated = Arc::new(testing::pdf(&page, &[info]));
+        let conversion = Conversion::new(dated, "dated", &settings).unwrap();
+        assert_eq!(conversion.name(1, None), "dated-2021-03-04-2022-05-06-001");
+        let undated = Arc::new(testing::pdf(&page, &[]));
+        let conversion = Conversion::new(undated, "undated", &settings).unwrap();
+        assert_eq!(conversion.name(1, None), "undated---001");
+    }
+
+    #[test]
+    fn render_page_renders_the_page_asked_for() {
+        let pages = [[255, 0, 0], [0, 255, 0], [0, 0, 255]]
+            .map(|color| testing::TestPage::new(100, 100, color));
+        let data = testing::pdf(&pages, &[]);
+        let bytes = render_page(&data, 1, &Settings::default()).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!(image.get_pixel(50, 50).0, [0, 255, 0]);
+    }
+
+    #[test]
+    fn render_page_rejects_pages_past_the_end() {
+        let data = testing::blank_pdf(3);
+        let result = render_page(&data, 3, &Settings::default());
+        assert!(matches!(result, Err(ConversionError::NoSuchPage(4))));
+    }
+
+    /// The left and right halves' colors of the first image of a red and blue two-page spread.
+    fn spread_colors(right_to_left: bool) -> ([u8; 3], [u8; 3]) {
+        let pages = [[255, 0, 0], [0, 0, 255]].map(|color| testing::TestPage::new(100, 100, color));
+        let settings = Settings {
+            spreads: true,
+            spread_cover: false,
+            right_to_left,
+            ..Settings::default()
+        };
+        let converted = convert(Arc::new(testing::pdf(&pages, &[])), "spread", &settings).unwrap();
+        let bytes = converted.pages[0].bytes(OutputFormat::Png).unwrap();
+        let image = image::load_from_memory(bytes).unwrap().to_rgb8();
+        let (width, height) = image.dimensions();
+        (
+            image.get_pixel(width / 4, height / 2).0,
+            image.get_pixel(width * 3 / 4, height / 2).0,
+        )
+    }
+
+    #[test]
+    fn spreads_put_the_earlier_page_on_the_left() {
+        assert_eq!(spread_colors(false), ([255, 0, 0], [0, 0, 255]));
+    }
+
+    #[test]
+    fn right_to_left_spreads_put_the_earlier_page_on_the_right() {
+        assert_eq!(spread_colors(true), ([0, 0, 255], [255, 0, 0]));
+    }
+
+    #[test]
+    fn progress_is_reported_after_every_page() {
+        let mut reports = Vec::new();
+        let data = Arc::new(testing::blank_pdf(4));
+        convert_with_progress(data, "blank", &Settings::default(), |done, total| {
+            reports.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(reports, [(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn progress_counts_only_the_selected_pages() {
+        let settings = Settings {
+            pages: "2-3".to_string(),
+            ..Settings::default()
+        };
+        let mut reports = 0;
+        let data = Arc::new(testing::blank_pdf(4));
+        convert_with_progress(data, "blank", &settings, |_, total| {
+            assert_eq!(total, 2);
+            reports += 1;
+        })
+        .unwrap();
+        assert_eq!(reports, 2);
+    }
+
+    /// The first page of a 200 × 100 point document with `/Rotate rotation`, converted.
+    fn rotated(rotation: &'static str) -> RenderedPage {
+        let page = testing::TestPage {
+            extra: rotation,
+            ..testing::TestPage::new(200, 100, [255, 255, 255])
+        };
+        let data = Arc::new(testing::pdf(&[page], &[]));
+        let mut converted = convert(data, "rotated", &Settings::default()).unwrap();
+        converted.pages.remove(0)
+    }
+
+    fn dimensions(page: &RenderedPage) -> (u32, u32) {
+        let bytes = page.bytes(OutputFormat::Png).unwrap();
+        image::load_from_memory(bytes).unwrap().dimensions()
+    }
+
+    #[test]
+    fn negative_rotations_are_applied() {
+        let (width, height) = dimensions(&rotated("/Rotate -90"));
+        assert!(width < height);
+    }
+
+    #[test]
+    fn rotations_between_quarter_turns_are_ignored_with_a_warning() {
+        let page = rotated("/Rotate 45");
+        let (width, height) = dimensions(&page);
+        assert!(width > height);
+        assert!(
+            page.warnings
+                .iter()
+                .any(|warning| warning
+                    == "a rotation that isn't a multiple of 90 degrees was ignored")
+        );
+    }
+
+    #[test]
+    fn quarter_turns_have_no_warning() {
+        let page = rotated("/Rotate 90");
+        let (width, height) = dimensions(&page);
+        assert!(width < height);
+        assert!(page.warnings.is_empty());
+    }
+
+    /// Width and height of every page of a portrait, a landscape and a landscape page rotated
+    /// to portrait, turned to `orientation`.
+    fn orientations(orientation: Orientation) -> Vec<(u32, u32)> {
+        let pages = [
+            testing::TestPage::new(100, 200, [255, 255, 255]),
+            testing::TestPage::new(200, 100, [255, 255, 255]),
+            testing::TestPage {
+                extra: "/Rotate 90",
+                ..testing::TestPage::new(200, 100, [255, 255, 255])
+            },
+        ];
+        let settings = Settings {
+            page_orientation: orientation,
+            ..Settings::default()
+        };
+        let data = Arc::new(testing::pdf(&pages, &[]));
+        let converted = convert(data, "mixed", &settings).unwrap();
+        converted.pages.iter().map(dimensions).collect()
+    }
+
+    #[test]
+    fn mixed_orientations_are_turned_to_portrait() {
+        let sizes = orientations(Orientation::Portrait);
+        assert!(
+            sizes.iter().all(|(width, height)| width < height),
+            "{sizes:?}"
+        );
+    }
+
+    #[test]
+    fn mixed_orientations_are_turned_to_landscape() {
+        let sizes = orientations(Orientation::Landscape);
+        assert!(
+            sizes.iter().all(|(width, height)| width > height),
+            "{sizes:?}"
+        );
+    }
+
+    #[test]
+    fn matching_the_page_keeps_orientations() {
+        let sizes = orientations(Orientation::MatchPage);
+        let portrait: Vec<_> = sizes.iter().map(|(width, height)| width < height).collect();
+        assert_eq!(portrait, [true, false, true]);
+    }
+
+    #[test]
+    fn provenance_is_read_back_from_pngs() {
+        let settings = Settings {
+            embed_provenance: true,
+            ..Settings::default()
+        };
+        let data = Arc::new(testing::blank_pdf(2));
+        let converted = convert(data, "blank", &settings).unwrap();
+        let bytes = converted.pages[1].bytes(OutputFormat::Png).unwrap();
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+        let chunk = reader
+            .info()
+            .utf8_text
+            .iter()
+            .find(|chunk| chunk.keyword == PNG_PROVENANCE_KEYWORD)
+            .unwrap();
+        let provenance: serde_json::Value =
+            serde_json::from_str(&chunk.get_text().unwrap()).unwrap();
+        assert_eq!(provenance["page"], 2);
+        assert_eq!(provenance["format"], serde_json::json!(OutputFormat::Png));
+        assert_eq!(provenance["settings_hash"], settings.hash());
+        assert_eq!(provenance["settings"], serde_json::json!(settings));
+    }
+
+    #[test]
+    fn provenance_is_left_out_by_default() {
+        let data = Arc::new(testing::blank_pdf(1));
+        let converted = convert(data, "blank", &Settings::default()).unwrap();
+        let bytes = converted.pages[0].bytes(OutputFormat::Png).unwrap();
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+        assert!(reader.info().utf8_text.is_empty());
+    }
+
+    #[test]
+    fn long_documents_are_padded_to_sort() {
+        let data = Arc::new(testing::blank_pdf(1200));
+        let conversion = Conversion::new(data, "long", &Settings::default()).unwrap();
+        assert_eq!(conversion.name(1, None), "long-page-0001");
+        assert_eq!(conversion.name(1200, None), "long-page-1200");
+        let names: Vec<_> = (1..=1200).map(|page| conversion.name(page, None)).collect();
+        assert!(names.is_sorted());
+    }
+
+    #[test]
+    fn short_documents_are_padded_to_three_digits() {
+        let data = Arc::new(testing::blank_pdf(12));
+        let conversion = Conversion::new(data, "short", &Settings::default()).unwrap();
+        assert_eq!(conversion.name(1, None), "short-page-001");
+        assert_eq!(conversion.name(12, Some(13)), "short-page-012-013");
+    }
+}
@@ -0,0 +1,93 @@
+use hayro_interpret::hayro_syntax::LoadPdfError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConversionError {
+    /// The browser could not read the file, like when it was deleted or moved after it was
+    /// picked.
+    ReadFailed(String),
+    /// The file could not be parsed as a PDF document.
+    InvalidPdf,
+    /// The file starts like a PDF but its end is missing, usually from a broken download.
+    Incomplete,
+    /// The document is encrypted and could not be decrypted.
+    Encrypted,
+    /// The document needs a password to open, which hayro can't be given.
+    PasswordProtected,
+    /// A rendered page could not be encoded into an image.
+    EncodeFailed(String),
+    /// Reading or writing a zip archive failed.
+    ArchiveFailed(String),
+    /// A page was asked for that the document doesn't have.
+    NoSuchPage(usize),
+    /// A page range in the settings could not be parsed.
+    InvalidPageRange(String),
+    /// A custom page order is malformed or doesn't name every page exactly once.
+    InvalidPageOrder(String),
+    /// The list of export scales in the settings could not be parsed.
+    InvalidScales(String),
+    /// A session bundle is missing its settings or they could not be read.
+    InvalidSession(String),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadFailed(reason) => write!(f, "could not read file: {reason}"),
+            Self::InvalidPdf => write!(f, "not a valid PDF document"),
+            Self::Incomplete => write!(f, "file appears incomplete, try downloading it again"),
+            Self::Encrypted => write!(f, "document is encrypted"),
+            Self::PasswordProtected => write!(
+                f,
+                "document needs a password to open, which isn't supported yet"
+            ),
+            Self::EncodeFailed(reason) => write!(f, "failed to encode page: {reason}"),
+            Self::NoSuchPage(number) => write!(f, "document has no page {number}"),
+            Self::InvalidPageRange(range) => write!(f, "not a valid page range: \"{range}\""),
+            Self::InvalidPageOrder(reason) => write!(f, "not a valid page order: {reason}"),
+            Self::InvalidScales(scales) => write!(f, "not a valid list of scales: \"{scales}\""),
+            Self::ArchiveFailed(reason) => write!(f, "zip archive error: {reason}"),
+            Self::InvalidSession(reason) => write!(f, "not a valid session: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<LoadPdfError> for ConversionError {
+    fn from(err: LoadPdfError) -> Self {
+        match err {
+            // hayro doesn't export its decryption errors, only their names
+            LoadPdfError::Decryption(err) if format!("{err:?}") == "PasswordProtected" => {
+                Self::PasswordProtected
+            }
+            LoadPdfError::Decryption(_) => Self::Encrypted,
+            LoadPdfError::Invalid => Self::InvalidPdf,
+        }
+    }
+}
+
+impl From<zip::result::ZipError> for ConversionError {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::ArchiveFailed(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for ConversionError {
+    fn from(err: std::io::Error) -> Self {
+        Self::ArchiveFailed(err.to_string())
+    }
+}
+
+impl From<image::ImageError> for ConversionError {
+    fn from(err: image::ImageError) -> Self {
+        Self::EncodeFailed(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ConversionError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::InvalidSession(err.to_string())
+    }
+}
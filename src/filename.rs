@@ -0,0 +1,106 @@
+use crate::inspect::DocumentDates;
+
+/// Filename template used when none is configured, giving `report-page-001`. Templates can
+/// contain `{stem}`, `{page}`, `{created}`, `{modified}` and `{settingshash}`, see
+/// [`Settings`](crate::Settings).
+pub const DEFAULT_TEMPLATE: &str = "{stem}-page-{page}";
+
+/// `name` without its `.pdf` extension in any case, only taking off the last one so
+/// `a.pdf.pdf` becomes `a.pdf`.
+pub fn pdf_stem(name: &str) -> &str {
+    let split = name.len().saturating_sub(".pdf".len());
+    match name.get(split..) {
+        Some(extension) if extension.eq_ignore_ascii_case(".pdf") => &name[..split],
+        _ => name,
+    }
+}
+
+/// Expand a filename template for one page. The dates are empty when the document doesn't
+/// have them. `{page}` is appended if the template lacks it, so pages never overwrite each
+/// other.
+pub(crate) fn expand(
+    template: &str,
+    stem: &str,
+    page: &str,
+    dates: &DocumentDates,
+    settings_hash: &str,
+) -> String {
+    let template = if template.contains("{page}") {
+        template.to_string()
+    } else {
+        format!("{template}-{{page}}")
+    };
+    template
+        .replace("{stem}", stem)
+        .replace("{created}", dates.created.as_deref().unwrap_or_default())
+        .replace("{modified}", dates.modified.as_deref().unwrap_or_default())
+        .replace("{settingshash}", settings_hash)
+        .replace("{page}", page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdf_stem_takes_off_one_extension_in_any_case() {
+        assert_eq!(pdf_stem("report.pdf"), "report");
+        assert_eq!(pdf_stem("Report.PDF"), "Report");
+        assert_eq!(pdf_stem("scan.Pdf"), "scan");
+        assert_eq!(pdf_stem("a.pdf.pdf"), "a.pdf");
+        assert_eq!(pdf_stem("my.report.pdf"), "my.report");
+    }
+
+    #[test]
+    fn pdf_stem_keeps_names_without_the_extension() {
+        assert_eq!(pdf_stem("report"), "report");
+        assert_eq!(pdf_stem("report.pdf.txt"), "report.pdf.txt");
+        assert_eq!(pdf_stem("pdf"), "pdf");
+        assert_eq!(pdf_stem(".pdf"), "");
+        assert_eq!(pdf_stem(""), "");
+    }
+
+    #[test]
+    fn pdf_stem_handles_multibyte_names() {
+        assert_eq!(pdf_stem("résumé.pdf"), "résumé");
+        assert_eq!(pdf_stem("日本語.PDF"), "日本語");
+        // the last four bytes fall inside a character
+        assert_eq!(pdf_stem("日本"), "日本");
+        assert_eq!(pdf_stem("aö"), "aö");
+    }
+
+    #[test]
+    fn date_tokens_expand_to_the_document_dates() {
+        let dates = DocumentDates {
+            created: Some("2021-03-04".to_string()),
+            modified: Some("2022-05".to_string()),
+        };
+        let name = expand(
+            "{created}-{stem}-{modified}-{page}",
+            "report",
+            "001",
+            &dates,
+            "",
+        );
+        assert_eq!(name, "2021-03-04-report-2022-05-001");
+    }
+
+    #[test]
+    fn missing_dates_expand_to_nothing() {
+        let dates = DocumentDates::default();
+        let name = expand(
+            "{stem}{created}{modified}-{page}",
+            "report",
+            "001",
+            &dates,
+            "",
+        );
+        assert_eq!(name, "report-001");
+    }
+
+    #[test]
+    fn templates_without_a_page_get_one() {
+        let dates = DocumentDates::default();
+        assert_eq!(expand("{stem}", "report", "002", &dates, ""), "report-002");
+    }
+}
@@ -0,0 +1,71 @@
+use hayro_interpret::FontResolverFn;
+use hayro_interpret::font::{FontQuery, StandardFont};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A TrueType or OpenType font drawn in place of fonts a document uses without embedding them,
+/// instead of the standard font hayro picks by their name or style.
+#[derive(Clone)]
+pub struct SubstituteFont {
+    /// Filename of the font, to show which one is used.
+    pub name: String,
+    data: Arc<Vec<u8>>,
+}
+
+impl SubstituteFont {
+    /// The font in `data`, if it looks like a TrueType or OpenType font. Collections aren't
+    /// supported.
+    pub fn new(name: &str, data: Vec<u8>) -> Option<Self> {
+        let tag = data.get(..4)?;
+        matches!(tag, [0, 1, 0, 0] | b"OTTO" | b"true").then(|| Self {
+            name: name.to_string(),
+            data: Arc::new(data),
+        })
+    }
+
+    /// The font file as it was picked.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+// the font data is too large to print and to compare
+impl fmt::Debug for SubstituteFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubstituteFont")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for SubstituteFont {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+/// A font resolver answering queries for fonts that aren't embedded with `font`, and adding the
+/// name of every font it stands in for to `substituted`. hayro asks for one of the standard
+/// fonts whenever a font isn't embedded, so those are what get named. Symbol and Zapf Dingbats
+/// are still resolved by `fallback`, as their glyphs are pictures rather than letters.
+pub(crate) fn resolver(
+    font: SubstituteFont,
+    fallback: FontResolverFn,
+    substituted: Arc<Mutex<BTreeSet<String>>>,
+) -> FontResolverFn {
+    Arc::new(move |query| {
+        let name = match query {
+            FontQuery::Standard(StandardFont::Symbol | StandardFont::ZapfDingBats) => {
+                return fallback(query);
+            }
+            FontQuery::Standard(standard) => format!("{standard:?}"),
+            FontQuery::Fallback(missing) => [&missing.post_script_name, &missing.font_name]
+                .into_iter()
+                .find_map(Option::clone)
+                .unwrap_or_else(|| "an unnamed font".to_string()),
+        };
+        substituted.lock().unwrap().insert(name);
+        Some((font.data.clone(), 0))
+    })
+}
@@ -0,0 +1,105 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+
+/// An image format pages can be encoded into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    /// Lossless, as `image` can't encode lossy WebP.
+    Webp,
+}
+
+/// How hard PNGs are compressed, trading encoding time for file size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Balanced,
+    /// Noticeably slower for slightly smaller files.
+    High,
+}
+
+impl PngCompression {
+    pub const ALL: [Self; 3] = [Self::Fast, Self::Balanced, Self::High];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fast => "Fast",
+            Self::Balanced => "Balanced",
+            Self::High => "Smallest",
+        }
+    }
+
+    /// The next faster setting, if there is one.
+    pub(crate) fn faster(self) -> Option<Self> {
+        match self {
+            Self::Fast => None,
+            Self::Balanced => Some(Self::Fast),
+            Self::High => Some(Self::Balanced),
+        }
+    }
+
+    pub(crate) fn png(self) -> png::Compression {
+        match self {
+            Self::Fast => png::Compression::Fast,
+            Self::Balanced => png::Compression::Balanced,
+            Self::High => png::Compression::High,
+        }
+    }
+}
+
+impl OutputFormat {
+    pub const ALL: [Self; 3] = [Self::Png, Self::Jpeg, Self::Webp];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub(crate) fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Png => ImageFormat::Png,
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+
+    /// Color channels of images in this format, 4 with alpha for PNG and WebP and 3 for JPEG.
+    pub fn channels(self) -> u8 {
+        match self {
+            Self::Png | Self::Webp => 4,
+            Self::Jpeg => 3,
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    /// A base64 `data:` URI of `bytes` encoded in this format, for embedding in HTML or CSS.
+    pub fn data_uri(self, bytes: &[u8]) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.mime_type(),
+            STANDARD.encode(bytes)
+        )
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Webp => "WebP",
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::RenderedPage;
+use serde::Serialize;
+use std::borrow::Cow;
+
+const PRESENTATION_CONTEXT: &str = "http://iiif.io/api/presentation/3/context.json";
+
+/// A label without a language, which IIIF writes as `{"none": [text]}`.
+#[derive(Serialize)]
+struct Label {
+    none: [String; 1],
+}
+
+impl Label {
+    fn new(text: impl Into<String>) -> Self {
+        Self {
+            none: [text.into()],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    #[serde(rename = "@context")]
+    context: &'static str,
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    label: Label,
+    items: Vec<Canvas>,
+}
+
+#[derive(Serialize)]
+struct Canvas {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    label: Label,
+    width: u32,
+    height: u32,
+    items: [AnnotationPage; 1],
+}
+
+#[derive(Serialize)]
+struct AnnotationPage {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    items: [Annotation; 1],
+}
+
+#[derive(Serialize)]
+struct Annotation {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    motivation: &'static str,
+    body: Image,
+    target: String,
+}
+
+#[derive(Serialize)]
+struct Image {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    format: &'static str,
+    width: u32,
+    height: u32,
+    service: [ImageService; 1],
+}
+
+#[derive(Serialize)]
+struct ImageService {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    profile: &'static str,
+}
+
+/// Every page of a document with a IIIF Presentation 3 manifest, as `(filename, bytes)`
+/// entries for a zip: `{stem}/manifest.json` and the page images in `{stem}/images/`. Each page
+/// is a canvas of its size in pixels, painted by its image. Everything is named with absolute
+/// URLs under `base_url`, as IIIF requires, so extracting the zip into the folder served at
+/// `base_url` makes the manifest work as is. The images point at level 0 image services under
+/// `{stem}/iiif/`, which are only stubs for a IIIF image server to fill in.
+pub fn iiif<'a>(
+    stem: &str,
+    pages: &'a [RenderedPage],
+    base_url: &str,
+) -> Vec<(String, Cow<'a, [u8]>)> {
+    let base = format!("{}/{}", base_url.trim_end_matches('/'), url_escape(stem));
+    let mut entries = Vec::new();
+    let mut canvases = Vec::new();
+    for page in pages {
+        let Some((format, bytes)) = page.first_encoded() else {
+            continue;
+        };
+        let filename = page.filename(format);
+        let (width, height) = page.dimensions;
+        let canvas = format!("{base}/canvas/{}", page.number);
+        canvases.push(Canvas {
+            id: canvas.clone(),
+            kind: "Canvas",
+            label: Label::new(page.label()),
+            width,
+            height,
+            items: [AnnotationPage {
+                id: format!("{base}/page/{}", page.number),
+                kind: "AnnotationPage",
+                items: [Annotation {
+                    id: format!("{base}/annotation/{}", page.number),
+                    kind: "Annotation",
+                    motivation: "painting",
+                    body: Image {
+                        id: format!("{base}/images/{}", url_escape(&filename)),
+                        kind: "Image",
+                        format: format.mime_type(),
+                        width,
+                        height,
+                        service: [ImageService {
+                            id: format!("{base}/iiif/{}", url_escape(&filename)),
+                            kind: "ImageService3",
+                            profile: "level0",
+                        }],
+                    },
+                    target: canvas,
+                }],
+            }],
+        });
+        entries.push((format!("{stem}/images/{filename}"), Cow::Borrowed(bytes)));
+    }
+    let manifest = Manifest {
+        context: PRESENTATION_CONTEXT,
+        id: format!("{base}/manifest.json"),
+        kind: "Manifest",
+        label: Label::new(stem),
+        items: canvases,
+    };
+    let json = serde_json::to_string_pretty(&manifest).expect("IIIF manifest serializes to JSON");
+    entries.insert(
+        0,
+        (
+            format!("{stem}/manifest.json"),
+            Cow::Owned(json.into_bytes()),
+        ),
+    );
+    entries
+}
+
+/// `text` as a URL path segment, with everything but unreserved characters percent-encoded.
+fn url_escape(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
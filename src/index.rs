@@ -0,0 +1,114 @@
+use crate::RenderedPage;
+use crate::{ConversionError, OutputFormat, thumbnail};
+use std::borrow::Cow;
+
+const THUMBNAIL_SIZE: u32 = 200;
+
+/// Styles and navigation of [`web_page`]: previous and next buttons and a page number to jump
+/// to, scrolling to the page sections.
+const WEB_PAGE_HEAD: &str = "<style>
+body { margin: 0; background: #555; font-family: sans-serif; }
+nav { position: sticky; top: 0; display: flex; gap: 1rem; justify-content: center; padding: 0.5rem; background: #222; color: #eee; }
+section { display: flex; justify-content: center; padding: 1rem; }
+img { max-width: 100%; height: auto; background: white; box-shadow: 0 0 0.5rem black; }
+</style>
+<script>
+function sections() { return [...document.querySelectorAll(\"section\")]; }
+function current() {
+  const all = sections();
+  const index = all.findIndex(section => section.getBoundingClientRect().bottom > 60);
+  return index < 0 ? all.length - 1 : index;
+}
+function go(index) {
+  const all = sections();
+  all[Math.max(0, Math.min(index, all.length - 1))]?.scrollIntoView();
+}
+</script>
+";
+
+/// A self-contained `index.html` listing every document with its first page embedded as a
+/// thumbnail, so recipients of a combined archive can browse it.
+pub fn index_html(documents: &[(&str, &[RenderedPage])]) -> Result<String, ConversionError> {
+    let mut html = String::from(
+        "<!doctype html>\n<html lang=\"en\">\n<head><meta charset=\"utf-8\" /><title>Index</title></head>\n<body>\n",
+    );
+    for (stem, pages) in documents {
+        let stem = escape(stem);
+        html += &format!(
+            "<figure>\n<figcaption>{stem} ({} pages)</figcaption>\n",
+            pages.len()
+        );
+        if let Some(first) = pages.first() {
+            let uri = OutputFormat::Png.data_uri(&thumbnail(first, THUMBNAIL_SIZE)?);
+            html += &format!("<img src=\"{uri}\" alt=\"{stem}, page 1\" />\n");
+        }
+        html += "</figure>\n";
+    }
+    html += "</body>\n</html>\n";
+    Ok(html)
+}
+
+/// An HTML snippet of `<img>` tags with every page of a document embedded as a data URI, to
+/// paste into a CMS or open as a page of its own.
+pub fn pages_html(stem: &str, pages: &[RenderedPage]) -> String {
+    let stem = escape(stem);
+    let mut html = String::new();
+    for page in pages {
+        if let Some((format, bytes)) = page.first_encoded() {
+            let (width, height) = page.dimensions;
+            html += &format!(
+                "<img src=\"{}\" width=\"{width}\" height=\"{height}\" alt=\"{stem}, {}\" />\n",
+                format.data_uri(bytes),
+                page.label().to_lowercase()
+            );
+        }
+    }
+    html
+}
+
+/// A standalone web page showing every page of a document in one long scroll, as
+/// `(filename, bytes)` entries for a zip: an `index.html` loading each page lazily from a
+/// `pages/` folder next to it, so large documents open quickly and everything works offline
+/// once extracted.
+pub fn web_page<'a>(stem: &str, pages: &'a [RenderedPage]) -> Vec<(String, Cow<'a, [u8]>)> {
+    let stem = escape(stem);
+    let mut html = format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\" />\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n\
+         <title>{stem}</title>\n{WEB_PAGE_HEAD}</head>\n<body>\n<nav>\n\
+         <button onclick=\"go(current() - 1)\">Previous</button>\n\
+         <label>Page <input type=\"number\" min=\"1\" max=\"{}\" size=\"4\" \
+         onchange=\"go(this.valueAsNumber - 1)\" /> of {}</label>\n\
+         <button onclick=\"go(current() + 1)\">Next</button>\n</nav>\n",
+        pages.len(),
+        pages.len()
+    );
+    let mut entries = Vec::new();
+    for page in pages {
+        let Some((format, bytes)) = page.first_encoded() else {
+            continue;
+        };
+        let filename = format!("pages/{}", page.filename(format));
+        let (width, height) = page.dimensions;
+        // the first page is shown straight away, the rest once they are scrolled near
+        let loading = if entries.is_empty() { "eager" } else { "lazy" };
+        html += &format!(
+            "<section id=\"page-{}\">\n<img src=\"{}\" width=\"{width}\" height=\"{height}\" \
+             loading=\"{loading}\" alt=\"{stem}, {}\" />\n</section>\n",
+            page.number,
+            escape(&filename),
+            page.label().to_lowercase()
+        );
+        entries.push((filename, Cow::Borrowed(bytes)));
+    }
+    html += "</body>\n</html>\n";
+    entries.insert(0, ("index.html".to_string(), Cow::Owned(html.into_bytes())));
+    entries
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
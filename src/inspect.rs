@@ -0,0 +1,798 @@
+use crate::{ConversionError, Crop, Metadata};
+use hayro::Pdf;
+use hayro_interpret::hayro_syntax::content::ops::TypedInstruction;
+use hayro_interpret::hayro_syntax::object::dict::keys::{
+    A, ANNOTS, AUTHOR, BASE_STATE, BM, COLORSPACE, COMPATIBLE, CREATION_DATE, D, DEST, DESTS,
+    DEVICE_CMYK, EF, EMBEDDED_FILES, EXT_G_STATE, F, FT, GROUP, ICC_BASED, K, KEYWORDS, KIDS,
+    MOD_DATE, N, NAME, NAMES, NORMAL, OCGS, OCPROPERTIES, OFF, ON, PARENT, PRODUCER, RECT,
+    RESOURCES, ROTATE, S, SUBTYPE, TITLE, UF, URI, V, XOBJECT,
+};
+use hayro_interpret::hayro_syntax::object::{
+    self, Array, Dict, Name, Object, ObjectIdentifier, Rect, Stream,
+};
+use hayro_interpret::hayro_syntax::page::{Page, Rotation};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+
+/// How many pages [`document_kind`] looks at, the rest of a document is usually the same.
+const SAMPLED_PAGES: usize = 5;
+/// Text rendering mode that neither fills nor strokes glyphs, used for the OCR layer of scans.
+const INVISIBLE_TEXT: i64 = 3;
+/// Annotation subtype of links.
+const LINK: &[u8] = b"Link";
+/// Action type of links to a place in the same document.
+const GO_TO: &[u8] = b"GoTo";
+/// How far from the end of a complete PDF its `startxref` and `%%EOF` are at most. Some
+/// programs append junk after them, the spec allows the header to be as far into the file.
+const STRUCTURE_WINDOW: usize = 1024;
+/// How many object numbers in a row [`objects`] finds nothing at before it gives up on the
+/// rest, for documents whose objects aren't all of generation 0.
+const MISSING_OBJECTS: i32 = 1024;
+
+/// Fail fast on files that were cut off, like by a broken download, before parsing them: a
+/// file starting like a PDF whose end has neither `startxref` nor `%%EOF`. Parsing those can
+/// get partway before failing in confusing ways, or render some pages and drop the rest.
+pub(crate) fn check_complete(data: &[u8]) -> Result<(), ConversionError> {
+    let contains = |haystack: &[u8], needle: &[u8]| {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    };
+    let head = &data[..data.len().min(STRUCTURE_WINDOW)];
+    let tail = &data[data.len().saturating_sub(STRUCTURE_WINDOW)..];
+    if !contains(head, b"%PDF-") {
+        return Err(ConversionError::InvalidPdf);
+    }
+    if !contains(tail, b"startxref") && !contains(tail, b"%%EOF") {
+        return Err(ConversionError::Incomplete);
+    }
+    Ok(())
+}
+
+/// Whether a document consists of scanned pages or was created digitally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentKind {
+    /// Pages are images, possibly with invisible OCR text on top.
+    Scanned,
+    /// Pages are made of text and vector graphics.
+    BornDigital,
+}
+
+/// Guess the kind of document from the operators its first pages use.
+pub(crate) fn document_kind(pdf: &Pdf) -> DocumentKind {
+    let sampled = pdf.pages().iter().take(SAMPLED_PAGES).collect::<Vec<_>>();
+    let scanned = sampled
+        .iter()
+        .filter(|page| {
+            let mut images = 0;
+            let mut visible_text = 0;
+            // nested `q`/`Q` aren't tracked, OCR layers set the mode once for the whole page
+            let mut text_mode = 0;
+            for op in page.typed_operations() {
+                match op {
+                    TypedInstruction::XObject(_) | TypedInstruction::InlineImage(_) => images += 1,
+                    TypedInstruction::TextRenderingMode(mode) => text_mode = mode.0.as_i64(),
+                    TypedInstruction::ShowText(_)
+                    | TypedInstruction::ShowTexts(_)
+                    | TypedInstruction::NextLineAndShowText(_)
+                    | TypedInstruction::ShowTextWithParameters(_)
+                        if text_mode != INVISIBLE_TEXT =>
+                    {
+                        visible_text += 1
+                    }
+                    _ => {}
+                }
+            }
+            images > 0 && visible_text == 0
+        })
+        .count();
+    if scanned * 2 > sampled.len() {
+        DocumentKind::Scanned
+    } else {
+        DocumentKind::BornDigital
+    }
+}
+
+/// Whether the document paints anything in CMYK. hayro always converts that to RGB.
+/// Every object defined in the document. hayro's `Pdf::objects` starts over at the first object
+/// on every step and never ends, so objects are looked up by number instead, until as many were
+/// found as it has. Only objects of generation 0 are found, which nearly all are.
+fn objects(pdf: &Pdf) -> impl Iterator<Item = Object<'_>> {
+    let count = pdf.len();
+    let (mut number, mut found, mut missing) = (0, 0, 0);
+    std::iter::from_fn(move || {
+        while found < count && missing < MISSING_OBJECTS {
+            number += 1;
+            match pdf.xref().get::<Object>(ObjectIdentifier::new(number, 0)) {
+                Some(object) => {
+                    found += 1;
+                    missing = 0;
+                    return Some(object);
+                }
+                None => missing += 1,
+            }
+        }
+        None
+    })
+}
+
+pub(crate) fn uses_cmyk(pdf: &Pdf) -> bool {
+    let cmyk_operators = pdf.pages().iter().any(|page| {
+        page.typed_operations().any(|op| {
+            matches!(
+                op,
+                TypedInstruction::StrokeColorCmyk(_) | TypedInstruction::NonStrokeColorCmyk(_)
+            )
+        })
+    });
+    cmyk_operators
+        || objects(pdf).any(|object| {
+            let dict = match &object {
+                Object::Dict(dict) => dict,
+                Object::Stream(stream) => stream.dict(),
+                _ => return false,
+            };
+            match dict.get::<Object>(COLORSPACE) {
+                // a resource dictionary maps names to color spaces
+                Some(Object::Dict(spaces)) => spaces
+                    .keys()
+                    .any(|name| spaces.get::<Object>(name).is_some_and(|cs| is_cmyk(&cs))),
+                Some(color_space) => is_cmyk(&color_space),
+                None => false,
+            }
+        })
+}
+
+/// An optional content group, a layer of content that PDF viewers let users show or hide.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    /// Shown in the document's default configuration, which is how hayro renders it.
+    pub visible: bool,
+}
+
+/// The layers of the document in the order it lists them.
+pub(crate) fn layers(pdf: &Pdf) -> Vec<Layer> {
+    let xref = pdf.xref();
+    let Some(properties) = xref
+        .get::<Dict>(xref.root_id())
+        .and_then(|catalog| catalog.get::<Dict>(OCPROPERTIES))
+    else {
+        return Vec::new();
+    };
+    let groups = |array: Option<Array>| -> Vec<ObjectIdentifier> {
+        array
+            .into_iter()
+            .flat_map(|array| array.raw_iter())
+            .filter_map(|item| Some(item.as_obj_ref()?.into()))
+            .collect()
+    };
+    let all = groups(properties.get::<Array>(OCGS));
+    let config = properties.get::<Dict>(D);
+    let list = |key| groups(config.as_ref().and_then(|config| config.get::<Array>(key)));
+    // every group starts out as `BaseState` and is then switched by the `ON` and `OFF` lists
+    let mut hidden: HashSet<_> = match config.as_ref().and_then(|c| c.get::<Name>(BASE_STATE)) {
+        Some(state) if &*state == OFF => all.iter().copied().collect(),
+        _ => HashSet::new(),
+    };
+    for id in list(ON) {
+        hidden.remove(&id);
+    }
+    hidden.extend(list(OFF));
+    all.into_iter()
+        .map(|id| Layer {
+            name: xref
+                .get::<Dict>(id)
+                .and_then(|group| group.get::<object::String>(NAME))
+                .map(|name| text_string(&name.get()))
+                .unwrap_or_default(),
+            visible: !hidden.contains(&id),
+        })
+        .collect()
+}
+
+/// What searching a document for `settings.search` found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchResult {
+    /// This many of the selected pages contain the query, only they were rendered.
+    Matched(usize),
+    /// The document has no text to search, so no pages were rendered.
+    NoText,
+}
+
+/// Numbers of the pages whose text contains `query`, ignoring case, or `None` if the document
+/// has no text at all, like scans without OCR. Text is read without its fonts, taking every
+/// byte of a string as a Latin-1 character. That is right for most simple fonts, but misses
+/// text in fonts with custom encodings and in CID fonts, common for CJK documents.
+pub(crate) fn search(pdf: &Pdf, query: &str) -> Option<HashSet<usize>> {
+    let query = query.to_lowercase();
+    let mut has_text = false;
+    let mut matches = HashSet::new();
+    for (index, page) in pdf.pages().iter().enumerate() {
+        let text: String = page
+            .typed_operations()
+            .filter_map(|op| shown_text(&op))
+            .collect();
+        has_text |= !text.is_empty();
+        if text.to_lowercase().contains(&query) {
+            matches.insert(index + 1);
+        }
+    }
+    has_text.then_some(matches)
+}
+
+/// The text a text-showing operator shows, read as [`search`] describes.
+fn shown_text(op: &TypedInstruction) -> Option<String> {
+    let mut text = match op {
+        TypedInstruction::ShowText(show) => show.0.get().to_vec(),
+        TypedInstruction::NextLineAndShowText(show) => show.0.get().to_vec(),
+        TypedInstruction::ShowTextWithParameters(show) => show.2.get().to_vec(),
+        TypedInstruction::ShowTexts(show) => show
+            .0
+            .iter::<Object>()
+            .filter_map(|item| match item {
+                Object::String(string) => Some(string.get().to_vec()),
+                _ => None,
+            })
+            .flatten()
+            .collect(),
+        _ => return None,
+    };
+    // two-byte encodings of ASCII text have a zero in every other byte
+    text.retain(|&byte| byte != 0);
+    Some(text.iter().map(|&byte| char::from(byte)).collect())
+}
+
+/// Text shown by a single operator on a page, with where it starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextRun {
+    /// The page the text is on.
+    pub page: usize,
+    /// Read without fonts, so only right for simple fonts with a standard encoding.
+    pub text: String,
+    /// Where the text starts, in points from the bottom-left corner of the page like the
+    /// rectangles of [`Link`]s. Glyph widths aren't known, so this is where the text was last
+    /// positioned, and text shown right after other text on a line starts where that did.
+    pub x: f32,
+    pub y: f32,
+    /// Font size in points, including any scaling of the page.
+    pub size: f32,
+    /// The text was positioned anew, usually on a new line, rather than following the text
+    /// before it.
+    pub new_line: bool,
+}
+
+/// An affine transform as the six numbers PDFs write them as.
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `first` followed by `then`.
+fn multiply(first: Matrix, then: Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = first;
+    let [a2, b2, c2, d2, e2, f2] = then;
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+/// The text runs of the page with the 1-indexed `number`, in the order they are drawn. Text in
+/// form XObjects isn't included, the same as for [`search`].
+pub(crate) fn text_runs(page: &Page, number: usize) -> Vec<TextRun> {
+    let mut runs = Vec::new();
+    // the transform, font size and leading, which `q` saves and `Q` restores
+    let mut state = (IDENTITY, 0.0, 0.0);
+    let mut saved = Vec::new();
+    let (mut line, mut text_matrix, mut new_line) = (IDENTITY, IDENTITY, true);
+    for op in page.typed_operations() {
+        let (transform, size, leading) = &mut state;
+        let mut next_line = |line: &mut Matrix, x: f32, y: f32| {
+            *line = multiply([1.0, 0.0, 0.0, 1.0, x, y], *line);
+            new_line = true;
+            *line
+        };
+        match &op {
+            TypedInstruction::SaveState(_) => saved.push(state),
+            TypedInstruction::RestoreState(_) => state = saved.pop().unwrap_or(state),
+            TypedInstruction::Transform(m) => {
+                let m = [m.0, m.1, m.2, m.3, m.4, m.5].map(|n| n.as_f32());
+                *transform = multiply(m, *transform);
+            }
+            TypedInstruction::TextFont(font) => *size = font.1.as_f32(),
+            TypedInstruction::TextLeading(l) => *leading = l.0.as_f32(),
+            TypedInstruction::BeginText(_) => {
+                (line, text_matrix, new_line) = (IDENTITY, IDENTITY, true);
+            }
+            TypedInstruction::SetTextMatrix(m) => {
+                line = [m.0, m.1, m.2, m.3, m.4, m.5].map(|n| n.as_f32());
+                (text_matrix, new_line) = (line, true);
+            }
+            TypedInstruction::NextLine(move_to) => {
+                text_matrix = next_line(&mut line, move_to.0.as_f32(), move_to.1.as_f32());
+            }
+            TypedInstruction::NextLineAndSetLeading(move_to) => {
+                *leading = -move_to.1.as_f32();
+                text_matrix = next_line(&mut line, move_to.0.as_f32(), move_to.1.as_f32());
+            }
+            TypedInstruction::NextLineUsingLeading(_)
+            | TypedInstruction::NextLineAndShowText(_)
+            | TypedInstruction::ShowTextWithParameters(_) => {
+                text_matrix = next_line(&mut line, 0.0, -*leading);
+            }
+            _ => {}
+        }
+        let Some(text) = shown_text(&op).filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        let [_, _, c, d, x, y] = multiply(text_matrix, state.0);
+        runs.push(TextRun {
+            page: number,
+            text,
+            x,
+            y,
+            size: state.1 * c.hypot(d),
+            new_line,
+        });
+        new_line = false;
+    }
+    runs
+}
+
+/// Widths of glyphs in ems are guessed as this, since fonts aren't read.
+const GLYPH_WIDTH: f32 = 0.5;
+/// How far glyphs reach above and below the baseline, in ems.
+const ASCENT: f32 = 0.8;
+const DESCENT: f32 = 0.2;
+
+/// The region of the page its [`text_runs`] cover, as rendered by hayro before any turns.
+/// Glyph widths and heights are guessed, so the region is only about as tight as the text.
+/// `None` if the page has no text.
+pub(crate) fn text_crop(page: &Page) -> Option<Crop> {
+    let [a, b, c, d, e, f] = page.initial_transform(true).as_coeffs();
+    let (width, height) = page.render_dimensions();
+    let runs = text_runs(page, 0);
+    if runs.is_empty() {
+        return None;
+    }
+    let (mut left, mut top, mut right, mut bottom) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    // runs that follow others on a line start where the line did, so they are laid end to end
+    let mut line = 0.0;
+    for run in runs {
+        if run.new_line {
+            line = 0.0;
+        }
+        let start = run.x + line;
+        line += run.text.chars().count() as f32 * run.size * GLYPH_WIDTH;
+        let corners = [
+            (start, run.y - run.size * DESCENT),
+            (run.x + line, run.y + run.size * ASCENT),
+        ];
+        for (x, y) in corners {
+            let (x, y) = (f64::from(x), f64::from(y));
+            let (x, y) = ((a * x + c * y + e) as f32, (b * x + d * y + f) as f32);
+            (left, top) = (left.min(x), top.min(y));
+            (right, bottom) = (right.max(x), bottom.max(y));
+        }
+    }
+    Crop::between(
+        (left / width, top / height),
+        (right / width, bottom / height),
+    )
+}
+
+/// A file embedded in a document, like the XML of a ZUGFeRD or Factur-X invoice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// The original filename, without any folders.
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// The files in the document's `EmbeddedFiles` name tree, in the order of their names. Entries
+/// whose data can't be decoded are skipped, and files named like an earlier one get their
+/// position prefixed.
+pub(crate) fn attachments(pdf: &Pdf) -> Vec<Attachment> {
+    let xref = pdf.xref();
+    let Some(tree) = xref
+        .get::<Dict>(xref.root_id())
+        .and_then(|catalog| catalog.get::<Dict>(NAMES))
+        .and_then(|names| names.get::<Dict>(EMBEDDED_FILES))
+    else {
+        return Vec::new();
+    };
+    let mut attachments = Vec::new();
+    // the tree maps names to file specifications
+    for (key, spec) in name_tree(tree) {
+        let Object::Dict(spec) = spec else {
+            continue;
+        };
+        let Some(data) = spec
+            .get::<Dict>(EF)
+            .and_then(|files| files.get::<Stream>(UF).or_else(|| files.get::<Stream>(F)))
+            .and_then(|stream| stream.decoded().ok())
+        else {
+            continue;
+        };
+        let name = spec
+            .get::<object::String>(UF)
+            .or_else(|| spec.get::<object::String>(F))
+            .unwrap_or(key);
+        let name = text_string(&name.get());
+        // specifications can hold a path, which must not escape the folder it's put into
+        let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+        let number = attachments.len() + 1;
+        let name = if matches!(name, "" | "." | "..") {
+            format!("attachment-{number}")
+        } else if attachments
+            .iter()
+            .any(|other: &Attachment| other.name == name)
+        {
+            // zips can't hold two files of the same name
+            format!("{number}-{name}")
+        } else {
+            name.to_string()
+        };
+        attachments.push(Attachment { name, data });
+    }
+    attachments
+}
+
+/// The entries of a name tree in order, as pairs of a name and its value.
+fn name_tree(tree: Dict<'_>) -> Vec<(object::String<'_>, Object<'_>)> {
+    let mut entries = Vec::new();
+    let mut nodes = vec![tree];
+    // the leaves hold their pairs in `Names`
+    while let Some(node) = nodes.pop() {
+        // kids are pushed in reverse to be visited in order
+        let kids: Vec<_> = node
+            .get::<Array>(KIDS)
+            .into_iter()
+            .flat_map(|kids| kids.iter::<Dict>().collect::<Vec<_>>())
+            .collect();
+        nodes.extend(kids.into_iter().rev());
+        let Some(names) = node.get::<Array>(NAMES) else {
+            continue;
+        };
+        let mut pairs = names.flex_iter();
+        while let (Some(key), Some(value)) =
+            (pairs.next::<object::String>(), pairs.next::<Object>())
+        {
+            entries.push((key, value));
+        }
+    }
+    entries
+}
+
+/// A link annotation on a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    /// The page the link is on.
+    pub page: usize,
+    /// The area that can be clicked, as `[left, bottom, right, top]` in points from the
+    /// bottom-left corner of the page, like in the PDF.
+    pub rect: [f32; 4],
+    pub target: LinkTarget,
+}
+
+/// Where a [`Link`] goes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LinkTarget {
+    /// A URI, like a web address or `mailto:`.
+    External(String),
+    /// A page of the same document, `None` if its destination can't be found.
+    Internal(Option<usize>),
+}
+
+/// The links on every page by page number, leaving out pages without any. Links running
+/// JavaScript, opening other files and the like aren't listed.
+pub(crate) fn links(pdf: &Pdf) -> HashMap<usize, Vec<Link>> {
+    let xref = pdf.xref();
+    let catalog = xref.get::<Dict>(xref.root_id());
+    // destinations point at page objects, and named ones at either lookup
+    let page_numbers: HashMap<ObjectIdentifier, usize> = pdf
+        .pages()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, page)| Some((page.raw().obj_id()?, index + 1)))
+        .collect();
+    let old_names = catalog
+        .as_ref()
+        .and_then(|catalog| catalog.get::<Dict>(DESTS));
+    let names: HashMap<Vec<u8>, Object> = catalog
+        .as_ref()
+        .and_then(|catalog| catalog.get::<Dict>(NAMES))
+        .and_then(|names| names.get::<Dict>(DESTS))
+        .map(name_tree)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, destination)| (name.get().to_vec(), destination))
+        .collect();
+    let target_page = |destination: Object| {
+        let destination = match destination {
+            Object::Name(name) => old_names.as_ref()?.get::<Object>(name.deref())?,
+            Object::String(name) => names.get(name.get().as_ref())?.clone(),
+            destination => destination,
+        };
+        // an explicit destination is an array starting with the page, possibly within a dict
+        let array = match destination {
+            Object::Array(array) => array,
+            Object::Dict(dict) => dict.get::<Array>(D)?,
+            _ => return None,
+        };
+        let page = array.raw_iter().next()?.as_obj_ref()?;
+        page_numbers.get(&page.into()).copied()
+    };
+
+    let mut links = HashMap::new();
+    for (index, page) in pdf.pages().iter().enumerate() {
+        let annotations = page.raw().get::<Array>(ANNOTS);
+        let page_links: Vec<_> = annotations
+            .iter()
+            .flat_map(|annotations| annotations.iter::<Dict>())
+            .filter(|annotation| annotation.get::<Name>(SUBTYPE).is_some_and(|s| &*s == LINK))
+            .filter_map(|annotation| {
+                let rect = annotation.get::<Rect>(RECT)?;
+                let target = match annotation.get::<Dict>(A) {
+                    Some(action) => match action.get::<Name>(S)?.deref() {
+                        URI => LinkTarget::External(
+                            String::from_utf8_lossy(&action.get::<object::String>(URI)?.get())
+                                .into_owned(),
+                        ),
+                        GO_TO => LinkTarget::Internal(target_page(action.get::<Object>(D)?)),
+                        _ => return None,
+                    },
+                    None => LinkTarget::Internal(target_page(annotation.get::<Object>(DEST)?)),
+                };
+                Some(Link {
+                    page: index + 1,
+                    rect: [rect.x0, rect.y0, rect.x1, rect.y1].map(|side| side as f32),
+                    target,
+                })
+            })
+            .collect();
+        if !page_links.is_empty() {
+            links.insert(index + 1, page_links);
+        }
+    }
+    links
+}
+
+/// A `/Rotate` of the page, its own or inherited, that hayro doesn't apply like PDF viewers
+/// do, with how many degrees to turn the rendered page clockwise to make up for it. Viewers
+/// count negative rotations back from 360, which hayro ignores, and ignore rotations that
+/// aren't a multiple of 90, which hayro may replace with an inherited one.
+pub(crate) fn unusual_rotation(page: &Page) -> Option<(f64, u32)> {
+    let mut dict = Some(page.raw().clone());
+    let degrees = std::iter::from_fn(|| {
+        let current = dict.take()?;
+        dict = current.get::<Dict>(PARENT);
+        Some(current)
+    })
+    .find_map(|dict| dict.get::<f64>(ROTATE))?;
+    let whole = degrees.fract() == 0.0 && degrees % 90.0 == 0.0;
+    if whole && degrees >= 0.0 {
+        return None;
+    }
+    let wanted = if whole {
+        (degrees as i64).rem_euclid(360) as u32
+    } else {
+        0
+    };
+    let applied = match page.rotation() {
+        Rotation::None => 0,
+        Rotation::Horizontal => 90,
+        Rotation::Flipped => 180,
+        Rotation::FlippedHorizontal => 270,
+    };
+    Some((degrees, (wanted + 360 - applied) % 360))
+}
+
+/// Whether the page, or a form it draws, uses a blend mode other than `Normal` or a knockout
+/// group. hayro paints both as if they were normal transparency.
+pub(crate) fn uses_unsupported_blending(page: &Page) -> bool {
+    let resources = page.resources();
+    let mut pending = vec![(resources.ext_g_states.clone(), resources.x_objects.clone())];
+    // forms drawn by several pages or forms are only looked at once
+    let mut seen = HashSet::new();
+    while let Some((states, forms)) = pending.pop() {
+        let blends = states.keys().any(|name| {
+            states
+                .get::<Dict>(name)
+                .and_then(|state| state.get::<Object>(BM))
+                .is_some_and(|mode| !is_normal_blend(&mode))
+        });
+        if blends {
+            return true;
+        }
+        for name in forms.keys() {
+            if let Some(id) = forms.get_ref(name.deref()).map(ObjectIdentifier::from)
+                && !seen.insert(id)
+            {
+                continue;
+            }
+            let Some(form) = forms.get::<Stream>(name) else {
+                continue;
+            };
+            let dict = form.dict();
+            if dict
+                .get::<Dict>(GROUP)
+                .is_some_and(|group| group.get::<bool>(K) == Some(true))
+            {
+                return true;
+            }
+            if let Some(resources) = dict.get::<Dict>(RESOURCES) {
+                pending.push((
+                    resources.get::<Dict>(EXT_G_STATE).unwrap_or_default(),
+                    resources.get::<Dict>(XOBJECT).unwrap_or_default(),
+                ));
+            }
+        }
+    }
+    false
+}
+
+/// Whether a `/BM` paints like `Normal`, either itself or as the first mode of a list, which
+/// is the one viewers fall back to.
+fn is_normal_blend(mode: &Object) -> bool {
+    let name = match mode {
+        Object::Name(name) => Some(name.clone()),
+        Object::Array(modes) => modes.iter::<Name>().next(),
+        _ => None,
+    };
+    name.is_none_or(|name| name.deref() == NORMAL || name.deref() == COMPATIBLE)
+}
+
+/// Whether the document has form fields with a value. hayro doesn't render annotations, so
+/// the values are missing from the rendered pages.
+pub(crate) fn has_form_values(pdf: &Pdf) -> bool {
+    objects(pdf).any(|object| match &object {
+        Object::Dict(field) => {
+            field.get::<Object>(FT).is_some() && field.get::<Object>(V).is_some()
+        }
+        _ => false,
+    })
+}
+
+/// Creation and modification dates from the document information dictionary, as ISO 8601
+/// dates.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DocumentDates {
+    pub(crate) created: Option<String>,
+    pub(crate) modified: Option<String>,
+}
+
+pub(crate) fn document_dates(pdf: &Pdf) -> DocumentDates {
+    let Some(info) = info_dictionary(pdf) else {
+        return DocumentDates::default();
+    };
+    let date = |key| iso_date(&info.get::<object::String>(key)?.get());
+    DocumentDates {
+        created: date(CREATION_DATE),
+        modified: date(MOD_DATE),
+    }
+}
+
+/// Title, author and keywords from the document information dictionary, empty where missing.
+/// PDFs have no field for rights, leaving it empty.
+pub(crate) fn document_metadata(pdf: &Pdf) -> Metadata {
+    let Some(info) = info_dictionary(pdf) else {
+        return Metadata::default();
+    };
+    let text = |key| {
+        info.get::<object::String>(key)
+            .map(|string| text_string(&string.get()))
+            .unwrap_or_default()
+    };
+    Metadata {
+        title: text(TITLE),
+        creator: text(AUTHOR),
+        rights: String::new(),
+        keywords: text(KEYWORDS),
+    }
+}
+
+/// The document information dictionary. hayro doesn't expose the trailer pointing to it, so
+/// this looks for a dictionary with its keys, minding that outline items have titles too.
+fn info_dictionary(pdf: &Pdf) -> Option<Dict<'_>> {
+    objects(pdf).find_map(|object| match object {
+        Object::Dict(dict)
+            if [CREATION_DATE, MOD_DATE, PRODUCER, AUTHOR, KEYWORDS]
+                .into_iter()
+                .any(|key| dict.contains_key(key))
+                || (dict.contains_key(TITLE) && !dict.contains_key(PARENT)) =>
+        {
+            Some(dict)
+        }
+        _ => None,
+    })
+}
+
+/// Decode a PDF text string, which is UTF-16 if it starts with a byte order mark. Otherwise it
+/// is PDFDocEncoding, which matches Latin-1 for all but a few rarely used characters.
+fn text_string(raw: &[u8]) -> String {
+    match raw.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => raw.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
+
+/// Turn a PDF date like `D:20240131120000+01'00'` into `2024-01-31`. Months and days are
+/// optional in PDF dates and left out here as well when they are missing.
+fn iso_date(raw: &[u8]) -> Option<String> {
+    let raw = std::str::from_utf8(raw).ok()?;
+    let digits = raw.strip_prefix("D:").unwrap_or(raw);
+    let digits: String = digits
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .take(8)
+        .collect();
+    match digits.len() {
+        4 => Some(digits),
+        6 => Some(format!("{}-{}", &digits[..4], &digits[4..])),
+        8 => Some(format!(
+            "{}-{}-{}",
+            &digits[..4],
+            &digits[4..6],
+            &digits[6..]
+        )),
+        _ => None,
+    }
+}
+
+fn is_cmyk(color_space: &Object) -> bool {
+    match color_space {
+        Object::Name(name) => name.deref() == DEVICE_CMYK,
+        Object::Array(array) => {
+            let mut items = array.iter::<Object>();
+            match items.next() {
+                Some(Object::Name(name)) if name.deref() == ICC_BASED => items
+                    .next()
+                    .and_then(|profile| match profile {
+                        Object::Stream(stream) => Some(stream),
+                        _ => None,
+                    })
+                    .is_some_and(|profile: Stream| profile.dict().get::<u8>(N) == Some(4)),
+                // alternate spaces of Indexed, Separation and DeviceN color spaces
+                _ => items
+                    .any(|item| matches!(item, Object::Name(name) if name.deref() == DEVICE_CMYK)),
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestPage};
+    use std::sync::Arc;
+
+    fn parse(objects: &[&str]) -> Pdf {
+        let data = testing::pdf(&[TestPage::new(100, 100, [255, 255, 255])], objects);
+        Pdf::new(Arc::new(data)).unwrap()
+    }
+
+    #[test]
+    fn filled_fields_have_form_values() {
+        let pdf = parse(&["<< /FT /Tx /T (name) /V (Ada) >>"]);
+        assert!(has_form_values(&pdf));
+    }
+
+    #[test]
+    fn empty_fields_have_no_form_values() {
+        assert!(!has_form_values(&parse(&["<< /FT /Tx /T (name) >>"])));
+        assert!(!has_form_values(&parse(&[])));
+    }
+}
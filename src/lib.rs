@@ -0,0 +1,59 @@
+mod apng;
+mod archive;
+mod assemble;
+mod bindings;
+mod convert;
+mod error;
+mod filename;
+mod font;
+mod format;
+mod iiif;
+mod index;
+mod inspect;
+mod paper;
+mod pdfa;
+mod postprocess;
+mod provenance;
+mod range;
+mod session;
+mod settings;
+mod sprite;
+mod stitch;
+mod svg;
+#[cfg(test)]
+mod testing;
+mod thumbnail;
+mod tile;
+mod worker;
+mod xmp;
+
+pub use apng::apng;
+pub use archive::{
+    MAX_FILE_SIZE, ZipStream, document_entries, formats_entries, preview_entry, unzip_pdfs,
+    zip_document, zip_entries, zip_formats, zip_interleaved, zip_parts,
+};
+pub use assemble::{assemble_pdf, rasterized_pdf};
+pub use bindings::convert_batch;
+pub use convert::{
+    Conversion, Converted, RenderedPage, convert, convert_with_progress, render_page,
+    render_pdf_to_images, render_preview,
+};
+pub use error::ConversionError;
+pub use filename::{DEFAULT_TEMPLATE, pdf_stem};
+pub use font::SubstituteFont;
+pub use format::{OutputFormat, PngCompression};
+pub use iiif::iiif;
+pub use index::{index_html, pages_html, web_page};
+pub use inspect::{Attachment, DocumentKind, Layer, Link, LinkTarget, SearchResult, TextRun};
+pub use paper::{Orientation, PaperSize};
+pub use pdfa::pdfa;
+pub use postprocess::Crop;
+pub use range::PageOrder;
+pub use session::{Session, SessionDocument, export_session, import_session};
+pub use settings::{Duplicates, Settings, parse_hex_color, to_hex_color};
+pub use sprite::sprite_sheets;
+pub use stitch::{Strip, stitch_documents, stitch_pages};
+pub use svg::svg_pages;
+pub use thumbnail::{contact_sheet, thumbnail};
+pub use worker::{ConvertWorker, WorkerRequest, WorkerUpdate};
+pub use xmp::Metadata;
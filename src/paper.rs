@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// Standard paper sizes pages can be fitted onto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaperSize {
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PaperSize {
+    pub const ALL: [Self; 4] = [Self::A4, Self::A5, Self::Letter, Self::Legal];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::A5 => "A5",
+            Self::Letter => "Letter",
+            Self::Legal => "Legal",
+        }
+    }
+
+    /// Portrait width and height in inches.
+    fn inches(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (210.0 / 25.4, 297.0 / 25.4),
+            Self::A5 => (148.0 / 25.4, 210.0 / 25.4),
+            Self::Letter => (8.5, 11.0),
+            Self::Legal => (8.5, 14.0),
+        }
+    }
+
+    /// Canvas size in pixels at `dpi`, oriented for a page of the given dimensions.
+    pub fn canvas(self, dpi: f32, orientation: Orientation, page: (f32, f32)) -> (u32, u32) {
+        let (short, long) = self.inches();
+        let landscape = match orientation {
+            Orientation::MatchPage => page.0 > page.1,
+            Orientation::Portrait => false,
+            Orientation::Landscape => true,
+        };
+        let (width, height) = if landscape {
+            (long, short)
+        } else {
+            (short, long)
+        };
+        ((width * dpi).round() as u32, (height * dpi).round() as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orientation {
+    /// Landscape pages go onto landscape paper, everything else onto portrait paper.
+    MatchPage,
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub const ALL: [Self; 3] = [Self::MatchPage, Self::Portrait, Self::Landscape];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::MatchPage => "Match page",
+            Self::Portrait => "Portrait",
+            Self::Landscape => "Landscape",
+        }
+    }
+}
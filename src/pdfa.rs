@@ -0,0 +1,238 @@
+use crate::{ConversionError, Metadata, RenderedPage, Settings};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+
+/// Resolution of PDF user space, in which page sizes are given.
+pub(crate) const POINTS_PER_INCH: f32 = 72.0;
+/// Name of the output condition the embedded profile describes.
+const OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+/// Entries of the tone curves in the embedded profile.
+const CURVE_POINTS: usize = 1024;
+
+/// A PDF/A-2b document with every page as an image at its physical size, for archives that
+/// require the format. Pages are embedded losslessly under an sRGB output intent, and its XMP
+/// metadata holds the title and other fields from `settings.xmp`, the title defaulting to
+/// `stem`.
+pub fn pdfa(
+    stem: &str,
+    pages: &[RenderedPage],
+    settings: &Settings,
+) -> Result<Vec<u8>, ConversionError> {
+    let metadata = settings.xmp.or(&Metadata {
+        title: stem.to_string(),
+        ..Metadata::default()
+    });
+    let mut pdf = Writer::default();
+    // both are written last, once the pages are known
+    let catalog = pdf.reserve();
+    let tree = pdf.reserve();
+
+    let xmp = metadata.pdfa_packet();
+    let metadata_id = pdf.add(
+        format!("<< /Type /Metadata /Subtype /XML /Length {} >>", xmp.len()),
+        Some(xmp.as_bytes()),
+    );
+    let profile = srgb_profile();
+    let profile_id = pdf.add(
+        format!("<< /N 3 /Length {} >>", profile.len()),
+        Some(&profile),
+    );
+
+    let mut kids = Vec::new();
+    for page in pages {
+        let Some((_, bytes)) = page.first_encoded() else {
+            continue;
+        };
+        let rgb = image::load_from_memory(bytes)?.to_rgb8();
+        let compressed = deflate(rgb.as_raw())?;
+        let image_id = pdf.add(
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+                 /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>",
+                rgb.width(),
+                rgb.height(),
+                compressed.len()
+            ),
+            Some(&compressed),
+        );
+        let points = |pixels: u32| pixels as f32 * POINTS_PER_INCH / page.dpi;
+        let (width, height) = (points(rgb.width()), points(rgb.height()));
+        let content = format!("q {width:.2} 0 0 {height:.2} 0 0 cm /Im0 Do Q");
+        let content_id = pdf.add(
+            format!("<< /Length {} >>", content.len()),
+            Some(content.as_bytes()),
+        );
+        kids.push(pdf.add(
+            format!(
+                "<< /Type /Page /Parent {tree} 0 R /MediaBox [0 0 {width:.2} {height:.2}] \
+                 /Resources << /XObject << /Im0 {image_id} 0 R >> >> /Contents {content_id} 0 R >>"
+            ),
+            None,
+        ));
+    }
+
+    let kids: Vec<_> = kids.iter().map(|id| format!("{id} 0 R")).collect();
+    pdf.set(
+        tree,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+        None,
+    );
+    pdf.set(
+        catalog,
+        format!(
+            "<< /Type /Catalog /Pages {tree} 0 R /Metadata {metadata_id} 0 R /OutputIntents \
+             [<< /Type /OutputIntent /S /GTS_PDFA1 /OutputConditionIdentifier ({OUTPUT_CONDITION}) \
+             /DestOutputProfile {profile_id} 0 R >>] >>"
+        ),
+        None,
+    );
+    Ok(pdf.finish(catalog))
+}
+
+/// `data` compressed for a `/FlateDecode` stream.
+pub(crate) fn deflate(data: &[u8]) -> Result<Vec<u8>, ConversionError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|()| encoder.finish())
+        .map_err(|err| ConversionError::EncodeFailed(err.to_string()))
+}
+
+/// Assembles the objects of a PDF and the cross-reference table locating them.
+#[derive(Default)]
+pub(crate) struct Writer {
+    /// Objects in order of their number, starting at 1, `None` while only reserved.
+    objects: Vec<Option<Vec<u8>>>,
+}
+
+impl Writer {
+    /// The number of an object that is [set](Self::set) later.
+    pub(crate) fn reserve(&mut self) -> usize {
+        self.objects.push(None);
+        self.objects.len()
+    }
+
+    /// Add an object with `dict`, or a stream if there is `data`, returning its number.
+    pub(crate) fn add(&mut self, dict: String, data: Option<&[u8]>) -> usize {
+        let id = self.reserve();
+        self.set(id, dict, data);
+        id
+    }
+
+    pub(crate) fn set(&mut self, id: usize, dict: String, data: Option<&[u8]>) {
+        let mut object = format!("{id} 0 obj\n{dict}\n").into_bytes();
+        if let Some(data) = data {
+            object.extend_from_slice(b"stream\n");
+            object.extend_from_slice(data);
+            object.extend_from_slice(b"\nendstream\n");
+        }
+        object.extend_from_slice(b"endobj\n");
+        self.objects[id - 1] = Some(object);
+    }
+
+    pub(crate) fn finish(self, root: usize) -> Vec<u8> {
+        // the comment of bytes above 127 marks the file as binary, which PDF/A requires
+        let mut pdf = b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::new();
+        for object in &self.objects {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(object.as_deref().expect("reserved objects are set"));
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f\r\n", offsets.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n\r\n").as_bytes());
+        }
+        // PDF/A wants a file identifier, derived from the contents so it is reproducible
+        let id: String = Sha256::digest(&pdf)[..16]
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root {root} 0 R /ID [<{id}> <{id}>] >>\nstartxref\n{xref}\n%%EOF\n",
+                self.objects.len() + 1
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+}
+
+/// A version 2 ICC profile of sRGB, relative to the D50 white point as ICC profiles are.
+fn srgb_profile() -> Vec<u8> {
+    fn fixed(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+    let xyz = |[x, y, z]: [f64; 3]| {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in [x, y, z] {
+            tag.extend_from_slice(&fixed(value));
+        }
+        tag
+    };
+    let mut description = b"desc\0\0\0\0".to_vec();
+    description.extend_from_slice(&(OUTPUT_CONDITION.len() as u32 + 1).to_be_bytes());
+    description.extend_from_slice(OUTPUT_CONDITION.as_bytes());
+    // the terminating NUL, then empty Unicode and ScriptCode descriptions
+    description.extend_from_slice(&[0; 1 + 4 + 4 + 2 + 1 + 67]);
+    let mut copyright = b"text\0\0\0\0No copyright, use freely".to_vec();
+    copyright.push(0);
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&(CURVE_POINTS as u32).to_be_bytes());
+    for point in 0..CURVE_POINTS {
+        let encoded = point as f64 / (CURVE_POINTS - 1) as f64;
+        let linear = if encoded <= 0.04045 {
+            encoded / 12.92
+        } else {
+            ((encoded + 0.055) / 1.055).powf(2.4)
+        };
+        curve.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+    let d50 = [0.9642, 1.0, 0.8249];
+    // the sRGB primaries adapted to D50
+    let tags = [
+        (b"desc", description),
+        (b"cprt", copyright),
+        (b"wtpt", xyz(d50)),
+        (b"rXYZ", xyz([0.4361, 0.2225, 0.0139])),
+        (b"gXYZ", xyz([0.3851, 0.7169, 0.0971])),
+        (b"bXYZ", xyz([0.1431, 0.0606, 0.7141])),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    let mut data = Vec::new();
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let data_start = 128 + 4 + 12 * tags.len();
+    for (signature, tag) in &tags {
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        // tags start on 4-byte boundaries
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let mut header = vec![0; 128];
+    let size = (header.len() + table.len() + data.len()) as u32;
+    header[0..4].copy_from_slice(&size.to_be_bytes());
+    header[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+    header[12..16].copy_from_slice(b"mntr");
+    header[16..20].copy_from_slice(b"RGB ");
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+    for (index, value) in d50.into_iter().enumerate() {
+        header[68 + 4 * index..72 + 4 * index].copy_from_slice(&fixed(value));
+    }
+    [header, table, data].concat()
+}
@@ -0,0 +1,209 @@
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+use serde::{Deserialize, Serialize};
+
+/// A region of a page, in fractions of its width and height from its top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Crop {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Crop {
+    /// The region between two opposite corners, in fractions of the page, clamped to the
+    /// page. `None` if it has no area.
+    pub fn between((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> Option<Self> {
+        let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+        let (y1, y2) = (y1.clamp(0.0, 1.0), y2.clamp(0.0, 1.0));
+        let crop = Self {
+            x: x1.min(x2),
+            y: y1.min(y2),
+            width: (x1 - x2).abs(),
+            height: (y1 - y2).abs(),
+        };
+        (crop.width > 0.0 && crop.height > 0.0).then_some(crop)
+    }
+
+    /// Size of the region on a page of `(width, height)`.
+    pub(crate) fn of(self, (width, height): (f32, f32)) -> (f32, f32) {
+        (width * self.width, height * self.height)
+    }
+
+    /// The same region once the page is turned clockwise by `degrees`, a multiple of 90.
+    pub(crate) fn turned(self, degrees: u32) -> Self {
+        let Self {
+            x,
+            y,
+            width,
+            height,
+        } = self;
+        match degrees % 360 {
+            90 => Self {
+                x: 1.0 - y - height,
+                y: x,
+                width: height,
+                height: width,
+            },
+            180 => Self {
+                x: 1.0 - x - width,
+                y: 1.0 - y - height,
+                width,
+                height,
+            },
+            270 => Self {
+                x: y,
+                y: 1.0 - x - width,
+                width: height,
+                height: width,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// The `crop` region of `image`, at least a pixel.
+pub(crate) fn crop(image: &RgbaImage, crop: Crop) -> RgbaImage {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let x = ((crop.x * width) as u32).min(image.width().saturating_sub(1));
+    let y = ((crop.y * height) as u32).min(image.height().saturating_sub(1));
+    let crop_width = ((crop.width * width).round() as u32).max(1);
+    let crop_height = ((crop.height * height).round() as u32).max(1);
+    imageops::crop_imm(image, x, y, crop_width, crop_height).to_image()
+}
+
+/// `image` blurred by `radius` pixels ahead of scaling it down, see `Settings::anti_moire`.
+pub(crate) fn soften(image: &RgbaImage, radius: f32) -> RgbaImage {
+    imageops::blur(image, radius.max(0.1))
+}
+
+/// `image` in shades of gray, see `Settings::grayscale`. Alpha is only kept if `has_alpha`, so
+/// opaque pages are encoded with a single channel.
+pub(crate) fn grayscale(image: RgbaImage, has_alpha: bool) -> DynamicImage {
+    let image = DynamicImage::ImageRgba8(image);
+    if has_alpha {
+        DynamicImage::ImageLumaA8(image.to_luma_alpha8())
+    } else {
+        DynamicImage::ImageLuma8(image.to_luma8())
+    }
+}
+
+/// Whether `pixel` is close enough to white to count as background, at most `tolerance` below
+/// full brightness.
+fn is_background(Rgba([r, g, b, _]): Rgba<u8>, tolerance: u8) -> bool {
+    let luma = (u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000;
+    luma >= u32::from(255 - tolerance)
+}
+
+/// Whether `image` is nothing but background, see [`is_background`].
+pub(crate) fn is_blank(image: &RgbaImage, tolerance: u8) -> bool {
+    image.pixels().all(|pixel| is_background(*pixel, tolerance))
+}
+
+/// Crop the background margins around the content of `image`, keeping `padding` pixels of them.
+/// Blank images are kept whole.
+pub(crate) fn trim(image: &RgbaImage, tolerance: u8, padding: u32) -> RgbaImage {
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if !is_background(*pixel, tolerance) {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+    }
+    if left > right {
+        return image.clone();
+    }
+    let (left, top) = (left.saturating_sub(padding), top.saturating_sub(padding));
+    let right = right.saturating_add(padding).min(image.width() - 1);
+    let bottom = bottom.saturating_add(padding).min(image.height() - 1);
+    imageops::crop_imm(image, left, top, right - left + 1, bottom - top + 1).to_image()
+}
+
+/// Paint the outermost `thickness` pixels of `image` with `color`.
+pub(crate) fn draw_border(image: &mut RgbaImage, thickness: u32, [r, g, b]: [u8; 3]) {
+    let (width, height) = image.dimensions();
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        if x < thickness
+            || y < thickness
+            || x >= width.saturating_sub(thickness)
+            || y >= height.saturating_sub(thickness)
+        {
+            *pixel = Rgba([r, g, b, 255]);
+        }
+    }
+}
+
+/// Center `image` on a white canvas of the given size.
+pub(crate) fn letterbox(image: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+    let x = (width as i64 - image.width() as i64) / 2;
+    let y = (height as i64 - image.height() as i64) / 2;
+    imageops::overlay(&mut canvas, image, x, y);
+    canvas
+}
+
+/// Place `left` and `right` next to each other on a white canvas, vertically centered.
+pub(crate) fn side_by_side(left: &RgbaImage, right: &RgbaImage) -> RgbaImage {
+    let height = left.height().max(right.height());
+    let mut canvas = RgbaImage::from_pixel(
+        left.width() + right.width(),
+        height,
+        Rgba([255, 255, 255, 255]),
+    );
+    imageops::overlay(
+        &mut canvas,
+        left,
+        0,
+        i64::from((height - left.height()) / 2),
+    );
+    imageops::overlay(
+        &mut canvas,
+        right,
+        i64::from(left.width()),
+        i64::from((height - right.height()) / 2),
+    );
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scanned looking page: off-white with a dark square at (20, 30) of 10 × 5 pixels.
+    fn scan() -> RgbaImage {
+        let mut image = RgbaImage::from_pixel(100, 80, Rgba([245, 242, 236, 255]));
+        for y in 30..35 {
+            for x in 20..30 {
+                image.put_pixel(x, y, Rgba([20, 20, 20, 255]));
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn off_white_counts_as_blank_within_the_tolerance() {
+        let page = RgbaImage::from_pixel(10, 10, Rgba([245, 242, 236, 255]));
+        assert!(is_blank(&page, 16));
+        assert!(!is_blank(&page, 8));
+        assert!(!is_blank(&scan(), 16));
+    }
+
+    #[test]
+    fn trim_crops_to_the_content_and_padding() {
+        assert_eq!(trim(&scan(), 16, 0).dimensions(), (10, 5));
+        assert_eq!(trim(&scan(), 16, 4).dimensions(), (18, 13));
+    }
+
+    #[test]
+    fn trim_keeps_blank_pages_whole() {
+        let page = RgbaImage::from_pixel(10, 10, Rgba([245, 242, 236, 255]));
+        assert_eq!(trim(&page, 16, 0).dimensions(), (10, 10));
+    }
+
+    #[test]
+    fn trim_keeps_off_white_outside_the_tolerance() {
+        assert_eq!(trim(&scan(), 4, 0).dimensions(), (100, 80));
+    }
+}
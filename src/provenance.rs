@@ -0,0 +1,41 @@
+use crate::xmp::insert_jpeg_segment;
+use crate::{ConversionError, OutputFormat, PngCompression, Settings};
+use serde::Serialize;
+
+/// Keyword of the PNG `iTXt` chunk holding the provenance.
+pub(crate) const PNG_PROVENANCE_KEYWORD: &str = "pdf-to-whatever";
+/// Marker of a JPEG comment segment.
+const JPEG_COMMENT: u8 = 0xFE;
+
+/// How an image was made, embedded into it as JSON with [`Settings::embed_provenance`].
+#[derive(Serialize)]
+pub(crate) struct Provenance<'a> {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub page: usize,
+    /// The right-hand page, for spreads.
+    pub spread_with: Option<usize>,
+    pub format: OutputFormat,
+    /// Pixels per PDF point.
+    pub scale: f32,
+    pub jpeg_quality: Option<u8>,
+    /// What the PNG was actually compressed with, which can be faster than asked when the
+    /// compression time limit was hit.
+    pub png_compression: Option<PngCompression>,
+    /// See [`Settings::hash`].
+    pub settings_hash: &'a str,
+    pub settings: &'a Settings,
+}
+
+impl Provenance<'_> {
+    pub(crate) fn json(&self) -> String {
+        serde_json::to_string(self).expect("provenance serializes to JSON")
+    }
+}
+
+/// Put the provenance `json` into `jpeg` as a comment segment.
+pub(crate) fn insert_into_jpeg(jpeg: &mut Vec<u8>, json: &str) -> Result<(), ConversionError> {
+    insert_jpeg_segment(jpeg, JPEG_COMMENT, json.as_bytes()).ok_or_else(|| {
+        ConversionError::EncodeFailed("the settings are too large for a JPEG comment".to_string())
+    })
+}
@@ -0,0 +1,220 @@
+use crate::ConversionError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The order pages are rendered and archived in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PageOrder {
+    Document,
+    /// Last page first, e.g. for documents scanned back to front.
+    Reverse,
+    /// The order given in `Settings::custom_order`.
+    Custom,
+}
+
+impl PageOrder {
+    pub const ALL: [Self; 3] = [Self::Document, Self::Reverse, Self::Custom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Document => "As in the document",
+            Self::Reverse => "Reverse",
+            Self::Custom => "Custom",
+        }
+    }
+}
+
+/// Every page number of a document with `page_count` pages, in the order they are rendered.
+/// A custom order is written like page ranges, where a range may also run backwards as in
+/// `5-1`, and has to name every page exactly once.
+pub(crate) fn page_order(
+    order: PageOrder,
+    custom: &str,
+    page_count: usize,
+) -> Result<Vec<usize>, ConversionError> {
+    match order {
+        PageOrder::Document => Ok((1..=page_count).collect()),
+        PageOrder::Reverse => Ok((1..=page_count).rev().collect()),
+        PageOrder::Custom => {
+            let invalid = |reason: &str| {
+                ConversionError::InvalidPageOrder(format!("\"{}\" {reason}", custom.trim()))
+            };
+            let number = |number: &str| number.trim().parse::<usize>().ok();
+            let mut pages = Vec::new();
+            for part in custom.split(',').filter(|part| !part.trim().is_empty()) {
+                let (first, last) = match part.split_once('-') {
+                    Some((first, last)) => (number(first), number(last)),
+                    None => (number(part), number(part)),
+                };
+                let (Some(first), Some(last)) = (first, last) else {
+                    return Err(invalid("is not a list of pages"));
+                };
+                // checked before expanding, so a typo like `1-99999999999` doesn't take all
+                // the memory there is
+                if let Some(page) = [first, last]
+                    .into_iter()
+                    .find(|page| !(1..=page_count).contains(page))
+                {
+                    return Err(invalid(&format!("names page {page} of {page_count}")));
+                }
+                if first <= last {
+                    pages.extend(first..=last);
+                } else {
+                    pages.extend((last..=first).rev());
+                }
+            }
+            let mut seen = vec![false; page_count];
+            for &page in &pages {
+                let Some(seen) = page.checked_sub(1).and_then(|index| seen.get_mut(index)) else {
+                    return Err(invalid(&format!("names page {page} of {page_count}")));
+                };
+                if std::mem::replace(seen, true) {
+                    return Err(invalid(&format!("names page {page} twice")));
+                }
+            }
+            if let Some(missing) = seen.iter().position(|seen| !seen) {
+                return Err(invalid(&format!("leaves out page {}", missing + 1)));
+            }
+            Ok(pages)
+        }
+    }
+}
+
+/// The page numbers of a document with `page_count` pages to render: those in `include`, or
+/// every page if it is empty, minus those in `exclude`. Both are comma-separated page numbers
+/// and ranges like `1-20`, where a range without an end runs to the last page.
+pub(crate) fn select_pages(
+    include: &str,
+    exclude: &str,
+    page_count: usize,
+) -> Result<BTreeSet<usize>, ConversionError> {
+    let mut pages = if include.trim().is_empty() {
+        (1..=page_count).collect()
+    } else {
+        parse(include, page_count)?
+    };
+    for page in parse(exclude, page_count)? {
+        pages.remove(&page);
+    }
+    Ok(pages)
+}
+
+/// The highest page number written in a list of pages, which may be past the end of a
+/// document. Ranges without an end don't count.
+pub(crate) fn last_named(spec: &str) -> Option<usize> {
+    spec.split([',', '-'])
+        .filter_map(|number| number.trim().parse::<usize>().ok())
+        .max()
+}
+
+/// Pages beyond the end of the document are left out rather than rejected, so the same range
+/// can be used for documents of different lengths.
+fn parse(spec: &str, page_count: usize) -> Result<BTreeSet<usize>, ConversionError> {
+    let invalid = || ConversionError::InvalidPageRange(spec.trim().to_string());
+    let number = |number: &str| number.trim().parse::<usize>().ok().filter(|&n| n >= 1);
+    let mut pages = BTreeSet::new();
+    for part in spec.split(',').filter(|part| !part.trim().is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            // runs to the last page, or names none in documents shorter than its start
+            Some((first, last)) if last.trim().is_empty() => (
+                number(first),
+                number(first).map(|first| first.max(page_count)),
+            ),
+            Some((first, last)) => (number(first), number(last)),
+            None => (number(part), number(part)),
+        };
+        let (Some(first), Some(last)) = (first, last) else {
+            return Err(invalid());
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        pages.extend(first..=last.min(page_count));
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(include: &str, exclude: &str, page_count: usize) -> Vec<usize> {
+        select_pages(include, exclude, page_count)
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn excluded_pages_are_removed_from_the_included() {
+        let expected: Vec<_> = (1..=20)
+            .filter(|page| ![5, 9, 10, 11].contains(page))
+            .collect();
+        assert_eq!(pages("1-20", "5,9-11", 30), expected);
+    }
+
+    #[test]
+    fn empty_include_means_every_page() {
+        assert_eq!(pages("", "", 3), [1, 2, 3]);
+        assert_eq!(pages(" ", "2", 3), [1, 3]);
+    }
+
+    #[test]
+    fn ranges_stop_at_the_last_page() {
+        assert_eq!(pages("1-20", "", 10), (1..=10).collect::<Vec<_>>());
+        assert_eq!(pages("8-", "", 10), [8, 9, 10]);
+        assert_eq!(pages("12-", "", 10), [] as [usize; 0]);
+        assert_eq!(pages("3, 12", "", 10), [3]);
+    }
+
+    #[test]
+    fn reversed_ranges_are_rejected() {
+        assert!(select_pages("5-2", "", 10).is_err());
+        assert!(select_pages("12-10", "", 10).is_err());
+        assert!(select_pages("", "12-10", 10).is_err());
+    }
+
+    #[test]
+    fn malformed_ranges_are_rejected() {
+        for spec in ["a", "1-b", "0", "0-3", "1--3", "-3", "1.5"] {
+            assert!(
+                matches!(
+                    select_pages(spec, "", 10),
+                    Err(ConversionError::InvalidPageRange(_))
+                ),
+                "{spec} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn last_named_ignores_open_ends() {
+        assert_eq!(last_named("1-20, 25"), Some(25));
+        assert_eq!(last_named("3-"), Some(3));
+        assert_eq!(last_named(""), None);
+    }
+
+    #[test]
+    fn reverse_order_starts_at_the_last_page() {
+        let order = page_order(PageOrder::Reverse, "", 5).unwrap();
+        assert_eq!(order, [5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn custom_order_is_followed() {
+        let order = page_order(PageOrder::Custom, "3, 1-2, 5-4", 5).unwrap();
+        assert_eq!(order, [3, 1, 2, 5, 4]);
+    }
+
+    #[test]
+    fn custom_orders_name_every_page_once() {
+        let order = |custom| page_order(PageOrder::Custom, custom, 5).is_ok();
+        assert!(!order("1-4"));
+        assert!(!order("1-5, 2"));
+        assert!(!order("0-5"));
+        assert!(!order("1-6"));
+        assert!(!order("first"));
+        assert!(!order("1-99999999999"));
+        assert!(!order("99999999999-1"));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{ConversionError, RenderedPage, Settings, zip_entries};
+use std::borrow::Cow;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+const SETTINGS_FILE: &str = "settings.json";
+const SOURCES_DIR: &str = "sources/";
+const OUTPUTS_DIR: &str = "outputs/";
+
+/// A document to put into a session bundle.
+pub struct SessionDocument<'a> {
+    pub stem: &'a str,
+    /// The source PDF.
+    pub data: &'a [u8],
+    /// Rendered pages, empty for documents that failed to convert.
+    pub pages: &'a [RenderedPage],
+}
+
+/// The contents of a session bundle, see [`export_session`].
+pub struct Session {
+    pub settings: Settings,
+    /// `(stem, bytes)` of every source PDF.
+    pub documents: Vec<(String, Vec<u8>)>,
+}
+
+/// Bundle the settings and source PDFs of a session into one zip, so it can be reopened with
+/// [`import_session`]. The rendered images are only included with `settings.session_outputs`,
+/// importing always renders the documents again.
+pub fn export_session(
+    settings: &Settings,
+    documents: &[SessionDocument],
+) -> Result<Vec<u8>, ConversionError> {
+    let mut entries = vec![(
+        SETTINGS_FILE.to_string(),
+        Cow::Owned(serde_json::to_vec_pretty(settings)?),
+    )];
+    for document in documents {
+        entries.push((
+            format!("{SOURCES_DIR}{}.pdf", document.stem),
+            Cow::Borrowed(document.data),
+        ));
+        if !settings.session_outputs {
+            continue;
+        }
+        for page in document.pages {
+            for format in &settings.formats {
+                if let Some(bytes) = page.bytes(*format) {
+                    let filename = page.filename(*format);
+                    entries.push((format!("{OUTPUTS_DIR}{filename}"), Cow::Borrowed(bytes)));
+                }
+            }
+        }
+    }
+    zip_entries(entries, settings)
+}
+
+/// Read a bundle written by [`export_session`].
+pub fn import_session(data: &[u8]) -> Result<Session, ConversionError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let settings = match archive.by_name(SETTINGS_FILE) {
+        Ok(file) => serde_json::from_reader(file)?,
+        Err(ZipError::FileNotFound) => {
+            return Err(ConversionError::InvalidSession(format!(
+                "no {SETTINGS_FILE} in archive"
+            )));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let mut documents = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let Some(stem) = file
+            .name()
+            .strip_prefix(SOURCES_DIR)
+            .and_then(|name| name.strip_suffix(".pdf"))
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        documents.push((stem, bytes));
+    }
+    Ok(Session {
+        settings,
+        documents,
+    })
+}
@@ -0,0 +1,563 @@
+use crate::{
+    ConversionError, Crop, DEFAULT_TEMPLATE, Metadata, Orientation, OutputFormat, PageOrder,
+    PaperSize, PngCompression, SubstituteFont,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+
+/// User-adjustable options applied while converting a document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Formats every page is encoded into.
+    pub formats: BTreeSet<OutputFormat>,
+    /// Name of every page image without extension. `{stem}` and `{page}` are replaced by the
+    /// document's name and the page number, `{created}` and `{modified}` by the document's
+    /// dates as `YYYY-MM-DD`, or nothing if it has none, and `{settingshash}` by
+    /// [`Settings::hash`].
+    pub filename_template: String,
+    /// Name documents dropped inside folders after their path, like `reports-2024-summary`,
+    /// instead of only their own name.
+    pub folder_names: bool,
+    /// What to do with a file dropped again while it is still listed, recognized by its name
+    /// and size.
+    pub duplicates: Duplicates,
+    /// Record the render resolution in the image headers, so other programs place images at
+    /// their physical size.
+    pub dpi_metadata: bool,
+    /// Pages to render, like `1-20`, or every page if empty.
+    pub pages: String,
+    /// Pages to leave out of those in `pages`, like `5,9-11`.
+    pub exclude_pages: String,
+    /// Only render the first this many of the selected pages.
+    pub max_pages: Option<u32>,
+    /// Only render the pages whose text contains this, ignoring case, if not empty.
+    pub search: String,
+    pub page_order: PageOrder,
+    /// Page numbers and ranges in the order to render them, used with [`PageOrder::Custom`].
+    pub custom_order: String,
+    /// Embed XMP metadata into every image.
+    pub embed_xmp: bool,
+    /// Metadata to embed, empty fields are taken from the document where it has them.
+    pub xmp: Metadata,
+    /// Embed how every page image was made into it as JSON: the tool and its version, the page,
+    /// format, scale, JPEG quality or PNG compression, and these settings. PNGs get an `iTXt`
+    /// chunk with the keyword `pdf-to-whatever`, JPEGs a comment. Tiles and web copies are left
+    /// as they are.
+    pub embed_provenance: bool,
+    /// Leave out pages that are nothing but background.
+    pub skip_blank_pages: bool,
+    /// Number the files of the remaining pages contiguously instead of by their original
+    /// position, when blank pages were skipped.
+    pub renumber_after_blanks: bool,
+    /// Cut every page down to this region before anything else, like trimming margins. Target
+    /// sizes and paper are fitted to the region.
+    pub crop: Option<Crop>,
+    /// Cut every page down to where its text is, in place of `crop`. Pages without text are
+    /// kept whole.
+    pub crop_to_text: bool,
+    /// Crop the background margins around the content of every page.
+    pub trim_margins: bool,
+    /// Pixels of background to leave around the content when trimming margins, as far as the
+    /// page reaches.
+    pub trim_padding: u32,
+    /// How far below white a pixel can be and still count as background when skipping blank
+    /// pages and trimming margins, in luma from 0 to 255. Scans and JPEG artifacts are rarely
+    /// pure white.
+    pub background_tolerance: u8,
+    /// Split every page into square tiles for deep-zoom and map viewers, put into archives
+    /// with a `{stem}-tiles.json` describing the grid.
+    pub tiles: bool,
+    /// Side of a tile in pixels.
+    pub tile_size: u32,
+    /// Extra sizes to export every page at, like `1,2` for `@1x` and `@2x` images for
+    /// `srcset`. Empty for a single size.
+    pub scales: String,
+    /// Render every page at exactly this many pixels per PDF point, the unit of PDF page sizes,
+    /// instead of at `scales`. Target sizes, paper and shrinking large pages are ignored, but
+    /// pages are still scaled down where they would go past the size limits.
+    pub pixels_per_point: Option<f32>,
+    /// Round the render scale to the nearest whole number, or down where a page wouldn't fit
+    /// its paper or the size limits otherwise, and scale down to other export scales without
+    /// smoothing. Keeps screenshots and pixel grids crisp.
+    pub integer_scale: bool,
+    /// Render every page `supersample_factor` times larger and scale it down with Lanczos for
+    /// smoother text and lines. Rendering takes up to the factor squared as long and as much
+    /// memory, and the factor is lowered where the larger page would go past the size limits.
+    pub supersample: bool,
+    /// How many times larger to render pages with `supersample`, from 2 to 4. Values outside
+    /// that are taken as the nearest end.
+    pub supersample_factor: u32,
+    /// Blur pages by `anti_moire_radius` pixels before they are scaled down, when supersampling
+    /// and for export scales below the largest, against moiré in halftone-screened scans.
+    /// Slightly softens text and lines.
+    pub anti_moire: bool,
+    /// Standard deviation of the Gaussian blur, in pixels of the image being scaled down.
+    pub anti_moire_radius: f32,
+    /// Also make a small JPEG of every page for the web from the same rendering, put into a
+    /// `web/` folder next to the full-size images in `master/`.
+    pub web_copies: bool,
+    /// Longest side of the web copies in pixels.
+    pub web_max_size: u32,
+    /// JPEG quality of the web copies, from 1 to 100.
+    pub web_quality: u8,
+    pub png_compression: PngCompression,
+    /// Encode pages in shades of gray with a single channel, much smaller for scanned text.
+    pub grayscale: bool,
+    /// JPEG quality of the page images, from 1 to 100.
+    pub jpeg_quality: u8,
+    /// Largest size of every JPEG in kilobytes, searching every page for the best quality that
+    /// stays under it instead of using a fixed quality. 0 for a fixed quality.
+    pub jpeg_target_kb: u32,
+    /// Longest a single PNG may take to compress, in milliseconds. Once one takes longer, the
+    /// remaining pages are compressed a step faster. 0 for no limit.
+    pub compression_budget_ms: u32,
+    /// Let the page update and react to clicks between rendered pages, showing the progress of
+    /// every document, instead of freezing until a document is done. Converting takes a little
+    /// longer.
+    pub responsive_rendering: bool,
+    /// Convert every document in a web worker of its own, so the page keeps reacting even while
+    /// a single large page renders, and documents convert side by side. Takes precedence over
+    /// `responsive_rendering`, which only applies without workers.
+    pub background_workers: bool,
+    /// Once the pages of the documents dropped together add up to more pixels than
+    /// `pixel_budget_megapixels`, render the rest of them at half the scale, so many
+    /// documents at once don't crash the page. Only applies while keeping the page responsive
+    /// or converting in workers.
+    pub pixel_budget: bool,
+    /// Megapixels the pages of an upload can add up to, or estimated from the memory of the
+    /// device.
+    pub pixel_budget_megapixels: Option<u32>,
+    /// Show a notification once every file of an upload is done, while the page is in the
+    /// background.
+    pub notify_when_done: bool,
+    /// Keep results however long the page is left alone. Otherwise they are cleared once nothing
+    /// happened for `idle_clear_minutes` and nothing is being converted, to free their memory
+    /// on pages left open for days.
+    pub keep_results: bool,
+    pub idle_clear_minutes: u32,
+    /// Only render page 1, to check the other settings before converting everything.
+    pub first_page_only: bool,
+    /// Render on-screen previews at the screen's pixel ratio, so they are sharp on high-DPI
+    /// screens. Doesn't affect the downloaded images.
+    pub sharp_previews: bool,
+    /// Draw a solid frame around every page.
+    pub border: bool,
+    /// Width of the frame in pixels.
+    pub border_thickness: u32,
+    pub border_color: [u8; 3],
+    /// Combine facing pages (2 and 3, 4 and 5, ...) side by side into one image, as in a book.
+    pub spreads: bool,
+    /// In spread mode, keep page 1 on its own as the cover.
+    pub spread_cover: bool,
+    /// In spread mode, put the earlier page on the right, for documents read right to left like
+    /// Arabic, Hebrew and Japanese ones.
+    pub right_to_left: bool,
+    /// Scale every page to exactly this many pixels wide. With a target height as well, pages
+    /// are fitted within both. Ignored when fitting onto paper.
+    pub target_width: Option<u32>,
+    /// Scale every page to exactly this many pixels tall, see `target_width`.
+    pub target_height: Option<u32>,
+    /// Scale down pages that would come out wider than this many pixels, keeping their aspect
+    /// ratio, as a ceiling for posters and drawings at large scales. Unlike a target width it
+    /// leaves smaller pages alone.
+    pub max_width: Option<u32>,
+    /// Scale down pages that would come out taller than this many pixels, see `max_width`.
+    pub max_height: Option<u32>,
+    /// Scale down pages whose longest side is longer than this many points, like fold-out maps
+    /// among letter pages, to the size they would have if it were this long. Other pages keep
+    /// their scale. Ignored with a target width or height and when fitting onto paper.
+    pub large_page_threshold: Option<u32>,
+    /// Turn every page that is the other way round clockwise to this orientation, so slides
+    /// and scans mixing portrait and landscape pages all come out the same way.
+    /// [`Orientation::MatchPage`] keeps every page as it is.
+    pub page_orientation: Orientation,
+    /// Turn every page clockwise by this many degrees, 0, 90, 180 or 270, after turning it to
+    /// `page_orientation`, for scans that went in sideways or upside down.
+    pub rotation: u32,
+    /// Scale and letterbox every page onto a sheet of this size instead of using its own size.
+    pub paper_size: Option<PaperSize>,
+    pub paper_dpi: f32,
+    pub paper_orientation: Orientation,
+    /// Download every format of a document in one zip, with a folder per format, instead of a
+    /// zip per format.
+    pub single_archive: bool,
+    /// Download documents of a single page as a zip too, instead of as the image alone.
+    pub always_zip: bool,
+    /// Add a `sha256sum`-compatible listing of every image to each zip.
+    pub checksums: bool,
+    /// Add a `{stem}.csv` listing the size, channels and transparency of every image to each
+    /// zip.
+    pub manifest: bool,
+    /// Add a `manifest.json` to each zip naming the document and listing the file and pixel
+    /// size of every image in order, for scripts that shouldn't have to parse filenames.
+    pub json_manifest: bool,
+    /// Add a `{stem}-diagnostics.txt` to each zip, noting for every page the problems hayro
+    /// reported while rendering it, like unsupported fonts or images, and whether it had to be
+    /// rendered smaller than asked. For triaging documents that don't look right.
+    pub diagnostics: bool,
+    /// Add a `{stem}-links.json` to each zip, listing the web and in-document links on every
+    /// page that has any, with where on the page they are.
+    pub links: bool,
+    /// Add a `{stem}-text.json` to each zip with the text of every page and where each piece
+    /// of it starts, for building search indexes. Read without fonts, like for `search`.
+    pub text_positions: bool,
+    /// Write loose images into a folder picked through the File System Access API instead of
+    /// downloading a zip, where the browser supports it.
+    pub save_to_folder: bool,
+    /// Write the zip of a single uploaded document into a file picked through the File System
+    /// Access API while it is converted, dropping every page once it is written. For documents
+    /// whose images don't fit in memory. Only the page images in the first of `formats` are
+    /// written, and the document can't be viewed or downloaded again afterwards.
+    pub stream_to_disk: bool,
+    /// Split the zip of a document into parts with at most this many megabytes (of 1024 ×
+    /// 1024 bytes) of files each, for places that limit the size of uploads.
+    pub split_size_mb: Option<u32>,
+    /// Add the files embedded in a document, like the XML of e-invoices, to its zips in an
+    /// `attachments/` folder.
+    pub attachments: bool,
+    /// Add the source PDF to the zip of every document as `source/{stem}.pdf`, for a package
+    /// holding both. Makes every archive larger by the size of the PDF.
+    pub include_source: bool,
+    /// Sort the files in every zip by name instead of by page, so archives of the same images
+    /// are byte-identical and diff well. Zips interleaving documents stay interleaved by page,
+    /// with the documents of every page sorted by name.
+    pub sorted_entries: bool,
+    /// Deflate the page images in these formats when adding them to zips, instead of storing
+    /// them as they are. On text documents this makes zips of PNGs about 5-10% smaller and of
+    /// JPEGs 13-30%, whose Huffman tables aren't optimized, for some 5-10 ms per page.
+    pub deflated_formats: BTreeSet<OutputFormat>,
+    /// Add an `index.html` with a thumbnail of every document to archives spanning several
+    /// documents.
+    pub index_page: bool,
+    /// Put a `_preview.png` contact sheet of the first page of every document first in
+    /// archives spanning several documents.
+    pub zip_preview: bool,
+    /// Also put the rendered images into exported sessions, not only the source documents.
+    pub session_outputs: bool,
+    /// URL the folders of IIIF exports are served under, which their manifests name everything
+    /// by.
+    pub iiif_base_url: String,
+    /// Longest side of a sprite sheet in pixels, more sheets are made once one is full.
+    pub sprite_max_size: u32,
+    /// Transparent pixels around every page on a sprite sheet, so neighbours don't bleed into
+    /// each other when sampled.
+    pub sprite_padding: u32,
+    /// How long every page of an animated PNG is shown, in milliseconds.
+    pub apng_delay_millis: u32,
+    /// Draw fonts the document doesn't embed with this font. It isn't saved with sessions, and
+    /// only its name is part of [`Settings::hash`].
+    #[serde(skip)]
+    pub substitute_font: Option<SubstituteFont>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            formats: BTreeSet::from(OutputFormat::ALL),
+            filename_template: DEFAULT_TEMPLATE.to_string(),
+            folder_names: true,
+            duplicates: Duplicates::Add,
+            dpi_metadata: true,
+            pages: String::new(),
+            exclude_pages: String::new(),
+            max_pages: None,
+            search: String::new(),
+            page_order: PageOrder::Document,
+            custom_order: String::new(),
+            embed_xmp: false,
+            embed_provenance: false,
+            xmp: Metadata::default(),
+            skip_blank_pages: false,
+            renumber_after_blanks: true,
+            crop: None,
+            crop_to_text: false,
+            trim_margins: false,
+            trim_padding: 0,
+            background_tolerance: 16,
+            tiles: false,
+            tile_size: 512,
+            scales: String::new(),
+            pixels_per_point: None,
+            integer_scale: false,
+            supersample: false,
+            supersample_factor: 2,
+            anti_moire: false,
+            anti_moire_radius: 1.0,
+            web_copies: false,
+            web_max_size: 1600,
+            web_quality: 70,
+            png_compression: PngCompression::Balanced,
+            grayscale: false,
+            jpeg_quality: 85,
+            jpeg_target_kb: 0,
+            compression_budget_ms: 2000,
+            responsive_rendering: true,
+            background_workers: true,
+            pixel_budget: true,
+            pixel_budget_megapixels: None,
+            notify_when_done: false,
+            keep_results: true,
+            idle_clear_minutes: 30,
+            first_page_only: false,
+            sharp_previews: true,
+            border: false,
+            border_thickness: 2,
+            border_color: [0, 0, 0],
+            spreads: false,
+            spread_cover: true,
+            right_to_left: false,
+            target_width: None,
+            target_height: None,
+            max_width: None,
+            max_height: None,
+            large_page_threshold: None,
+            page_orientation: Orientation::MatchPage,
+            rotation: 0,
+            paper_size: None,
+            paper_dpi: 150.0,
+            paper_orientation: Orientation::MatchPage,
+            single_archive: false,
+            always_zip: false,
+            checksums: false,
+            manifest: false,
+            json_manifest: false,
+            diagnostics: false,
+            links: false,
+            text_positions: false,
+            save_to_folder: false,
+            stream_to_disk: false,
+            split_size_mb: None,
+            attachments: false,
+            include_source: false,
+            sorted_entries: false,
+            deflated_formats: BTreeSet::from([OutputFormat::Png]),
+            index_page: false,
+            zip_preview: false,
+            session_outputs: false,
+            iiif_base_url: "http://localhost:8000".to_string(),
+            sprite_max_size: 4096,
+            sprite_padding: 2,
+            apng_delay_millis: 1000,
+            substitute_font: None,
+        }
+    }
+}
+
+/// What happens to a file dropped again, see `Settings::duplicates`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Duplicates {
+    /// Convert it again under a new name, like `report-2`.
+    Add,
+    /// Leave it out.
+    Skip,
+    /// Convert it again in place of the earlier one, cancelling that if it is still going.
+    Replace,
+}
+
+impl Duplicates {
+    pub const ALL: [Self; 3] = [Self::Add, Self::Skip, Self::Replace];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Add => "Add again",
+            Self::Skip => "Skip",
+            Self::Replace => "Replace",
+        }
+    }
+}
+
+/// Hex digits of the settings hash used in filenames.
+const HASH_LENGTH: usize = 8;
+/// The settings that change how page images look or what their files hold, which
+/// [`Settings::hash`] covers, in the order they are hashed. The rest only pick pages, name or
+/// package the images, or change how the app behaves.
+const HASHED_FIELDS: &[&str] = &[
+    "dpi_metadata",
+    "embed_xmp",
+    "xmp",
+    "embed_provenance",
+    "crop",
+    "crop_to_text",
+    "trim_margins",
+    "trim_padding",
+    "background_tolerance",
+    "tiles",
+    "tile_size",
+    "scales",
+    "pixels_per_point",
+    "integer_scale",
+    "supersample",
+    "supersample_factor",
+    "anti_moire",
+    "anti_moire_radius",
+    "web_copies",
+    "web_max_size",
+    "web_quality",
+    "png_compression",
+    "grayscale",
+    "jpeg_quality",
+    "jpeg_target_kb",
+    "compression_budget_ms",
+    "border",
+    "border_thickness",
+    "border_color",
+    "spreads",
+    "spread_cover",
+    "right_to_left",
+    "target_width",
+    "target_height",
+    "max_width",
+    "max_height",
+    "large_page_threshold",
+    "page_orientation",
+    "rotation",
+    "paper_size",
+    "paper_dpi",
+    "paper_orientation",
+];
+/// Smallest and largest export scale. Past 8x even small pages run into the size limits and
+/// get scaled down again, below 0.25x text becomes unreadable.
+const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=8.0;
+
+impl Settings {
+    /// The scales in `scales`, in the order given and without duplicates. A trailing `x` as in
+    /// `2x` is allowed, and every scale has to be from 0.25 to 8.
+    pub fn scale_list(&self) -> Result<Vec<f32>, ConversionError> {
+        let mut scales = Vec::new();
+        for part in self.scales.split(',').map(str::trim) {
+            if part.is_empty() {
+                continue;
+            }
+            let scale = part
+                .strip_suffix(['x', 'X'])
+                .unwrap_or(part)
+                .parse::<f32>()
+                .ok()
+                .filter(|scale| SCALE_RANGE.contains(scale))
+                .ok_or_else(|| ConversionError::InvalidScales(self.scales.trim().to_string()))?;
+            if !scales.contains(&scale) {
+                scales.push(scale);
+            }
+        }
+        Ok(scales)
+    }
+
+    /// A short hash that changes whenever a setting changing the page images does, so
+    /// re-exports that look different get different filenames: the settings in
+    /// `HASHED_FIELDS` and the name of `substitute_font`, not the font itself.
+    ///
+    /// It is the first 8 hex digits of the SHA-256 of a JSON array of their values in that
+    /// order, serialized compactly by `serde_json`. It stays the same across runs and machines,
+    /// but changes when settings are added to these.
+    pub fn hash(&self) -> String {
+        let serde_json::Value::Object(mut fields) =
+            serde_json::to_value(self).expect("settings serialize to JSON")
+        else {
+            unreachable!("settings serialize to a JSON object");
+        };
+        let values: Vec<_> = HASHED_FIELDS
+            .iter()
+            .map(|field| fields.remove(*field).expect("hashed fields are settings"))
+            .chain(
+                self.substitute_font
+                    .as_ref()
+                    .map(|font| font.name.clone().into()),
+            )
+            .collect();
+        let json = serde_json::to_vec(&values).expect("settings serialize to JSON");
+        let mut hash = format!("{:x}", Sha256::digest(json));
+        hash.truncate(HASH_LENGTH);
+        hash
+    }
+}
+
+/// Parse a `#rrggbb` color, as produced by `<input type="color">`.
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn to_hex_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_stays_the_same() {
+        assert_eq!(Settings::default().hash(), Settings::default().hash());
+        assert_eq!(Settings::default().hash().len(), HASH_LENGTH);
+    }
+
+    #[test]
+    fn hash_changes_with_the_page_images() {
+        let settings = Settings {
+            jpeg_quality: 50,
+            ..Settings::default()
+        };
+        assert_ne!(settings.hash(), Settings::default().hash());
+        let settings = Settings {
+            border: true,
+            ..Settings::default()
+        };
+        assert_ne!(settings.hash(), Settings::default().hash());
+    }
+
+    #[test]
+    fn hash_changes_with_the_substitute_font() {
+        let font = |name| SubstituteFont::new(name, b"OTTO".to_vec());
+        let settings = |name| Settings {
+            substitute_font: font(name),
+            ..Settings::default()
+        };
+        assert_ne!(settings("a.otf").hash(), Settings::default().hash());
+        assert_ne!(settings("a.otf").hash(), settings("b.otf").hash());
+    }
+
+    #[test]
+    fn hash_ignores_settings_not_changing_the_page_images() {
+        let settings = Settings {
+            formats: BTreeSet::from([OutputFormat::Png]),
+            notify_when_done: !Settings::default().notify_when_done,
+            background_workers: !Settings::default().background_workers,
+            sorted_entries: true,
+            split_size_mb: Some(10),
+            pages: "1-3".to_string(),
+            ..Settings::default()
+        };
+        assert_eq!(settings.hash(), Settings::default().hash());
+    }
+
+    #[test]
+    fn settings_round_trip_through_json() {
+        let settings = Settings {
+            jpeg_quality: 42,
+            pages: "2-5".to_string(),
+            split_size_mb: Some(3),
+            border_color: [1, 2, 3],
+            ..Settings::default()
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        let read: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, settings);
+        assert_eq!(read.hash(), settings.hash());
+    }
+
+    #[test]
+    fn missing_settings_take_their_defaults() {
+        let read: Settings = serde_json::from_str(r#"{"jpeg_quality": 42}"#).unwrap();
+        let expected = Settings {
+            jpeg_quality: 42,
+            ..Settings::default()
+        };
+        assert_eq!(read, expected);
+    }
+}
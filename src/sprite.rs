@@ -0,0 +1,158 @@
+use crate::convert::encode;
+use crate::{ConversionError, OutputFormat, PngCompression, RenderedPage};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage, imageops};
+use serde::Serialize;
+
+/// A page placed on a sheet, before the sheets are drawn.
+struct Placement {
+    page: usize,
+    image: DynamicImage,
+    sheet: usize,
+    x: u32,
+    y: u32,
+}
+
+#[derive(Serialize)]
+struct Layout {
+    sheets: Vec<SheetLayout>,
+    pages: Vec<PageLayout>,
+}
+
+#[derive(Serialize)]
+struct SheetLayout {
+    file: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize)]
+struct PageLayout {
+    page: usize,
+    /// Index into `sheets`.
+    sheet: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Every page of a document packed into PNG sprite sheets of at most `max_size` pixels square,
+/// with `padding` transparent pixels around every page, as `(filename, bytes)` entries. Pages
+/// are packed into rows, tallest first, and pages too large for an empty sheet are scaled down
+/// to fit. A `{stem}-sprite.png` is written if everything fits onto one sheet, otherwise
+/// `{stem}-sprite-1.png` and so on, followed by a `{stem}-sprite.json` mapping every page
+/// number to its sheet and rectangle.
+pub fn sprite_sheets(
+    stem: &str,
+    pages: &[RenderedPage],
+    max_size: u32,
+    padding: u32,
+) -> Result<Vec<(String, Vec<u8>)>, ConversionError> {
+    // room for at least one pixel of page besides the padding
+    let max_size = max_size.max(2 * padding + 1);
+    let room = max_size - 2 * padding;
+    let mut placements = pages
+        .iter()
+        .filter_map(|page| Some((page.number, page.first_encoded()?)))
+        .map(|(number, (format, bytes))| {
+            let image = image::load_from_memory_with_format(bytes, format.image_format())?;
+            let image = if image.width() > room || image.height() > room {
+                image.resize(room, room, imageops::FilterType::Triangle)
+            } else {
+                image
+            };
+            Ok(Placement {
+                page: number,
+                image,
+                sheet: 0,
+                x: 0,
+                y: 0,
+            })
+        })
+        .collect::<Result<Vec<_>, ConversionError>>()?;
+    if placements.is_empty() {
+        return Ok(Vec::new());
+    }
+    placements.sort_by_key(|placement| std::cmp::Reverse(placement.image.height()));
+
+    // the shelf being filled: its top, its height and where the next page goes
+    let (mut sheet, mut top, mut shelf, mut x) = (0, 0, 0, 0);
+    // the extent of every sheet, to crop them to what is used
+    let mut sizes = vec![(0, 0)];
+    for placement in &mut placements {
+        let (width, height) = placement.image.dimensions();
+        let (width, height) = (width + 2 * padding, height + 2 * padding);
+        if x + width > max_size {
+            (top, shelf, x) = (top + shelf, 0, 0);
+        }
+        if top + height > max_size {
+            (sheet, top, shelf, x) = (sheet + 1, 0, 0, 0);
+            sizes.push((0, 0));
+        }
+        (placement.sheet, placement.x, placement.y) = (sheet, x + padding, top + padding);
+        x += width;
+        shelf = shelf.max(height);
+        let size = &mut sizes[sheet];
+        *size = (size.0.max(x), size.1.max(top + height));
+    }
+    // back into page order for the layout
+    placements.sort_by_key(|placement| placement.page);
+
+    let name = |sheet: usize| {
+        if sizes.len() == 1 {
+            format!("{stem}-sprite.png")
+        } else {
+            format!("{stem}-sprite-{}.png", sheet + 1)
+        }
+    };
+    let mut entries = Vec::new();
+    for (index, &(width, height)) in sizes.iter().enumerate() {
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+        for placement in placements
+            .iter()
+            .filter(|placement| placement.sheet == index)
+        {
+            imageops::overlay(
+                &mut image,
+                &placement.image.to_rgba8(),
+                i64::from(placement.x),
+                i64::from(placement.y),
+            );
+        }
+        let bytes = encode(
+            &DynamicImage::ImageRgba8(image),
+            OutputFormat::Png,
+            None,
+            None,
+            PngCompression::default(),
+            None,
+        )?;
+        entries.push((name(index), bytes));
+    }
+
+    let layout = Layout {
+        sheets: sizes
+            .iter()
+            .enumerate()
+            .map(|(index, &(width, height))| SheetLayout {
+                file: name(index),
+                width,
+                height,
+            })
+            .collect(),
+        pages: placements
+            .iter()
+            .map(|placement| PageLayout {
+                page: placement.page,
+                sheet: placement.sheet,
+                x: placement.x,
+                y: placement.y,
+                width: placement.image.width(),
+                height: placement.image.height(),
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&layout).expect("sprite layout serializes to JSON");
+    entries.push((format!("{stem}-sprite.json"), json.into_bytes()));
+    Ok(entries)
+}
@@ -0,0 +1,85 @@
+use crate::convert::{MAX_PIXELS, encode};
+use crate::{ConversionError, OutputFormat, PngCompression, RenderedPage};
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+
+/// Tallest strip written, JPEG can't store images taller than 65535 pixels.
+const MAX_STRIP_HEIGHT: f32 = 65535.0;
+
+/// Pages stacked into one image, see [`stitch_pages`].
+pub struct Strip {
+    pub bytes: Vec<u8>,
+    /// Dimensions the strip would have had, when it was too large and was scaled down.
+    pub downscaled_from: Option<(u32, u32)>,
+}
+
+/// Stack every page of every document top to bottom into one image in `format`, in document
+/// order. Pages are left-aligned on a white background as wide as the widest page, and the
+/// whole strip is scaled down if it would get too tall or too large. Pages not rendered in
+/// `format` are left out.
+pub fn stitch_documents(
+    documents: &[(&str, &[RenderedPage])],
+    format: OutputFormat,
+) -> Result<Strip, ConversionError> {
+    let pages: Vec<_> = documents
+        .iter()
+        .flat_map(|(_, pages)| pages.iter())
+        .collect();
+    stitch_pages(&pages, format)
+}
+
+/// Stack `pages` top to bottom into one image in `format`, like [`stitch_documents`].
+pub fn stitch_pages(
+    pages: &[&RenderedPage],
+    format: OutputFormat,
+) -> Result<Strip, ConversionError> {
+    let pages = pages
+        .iter()
+        .filter_map(|page| page.bytes(format))
+        .map(|bytes| {
+            Ok(image::load_from_memory_with_format(
+                bytes,
+                format.image_format(),
+            )?)
+        })
+        .collect::<Result<Vec<DynamicImage>, ConversionError>>()?;
+
+    let width = pages.iter().map(DynamicImage::width).max().unwrap_or(0);
+    let height: u32 = pages.iter().map(DynamicImage::height).sum();
+    let factor = (MAX_STRIP_HEIGHT / height as f32)
+        .min((MAX_PIXELS / (width as f32 * height as f32)).sqrt())
+        .min(1.0);
+    let scaled = |length: u32| ((length as f32 * factor) as u32).max(1);
+
+    let mut strip = RgbaImage::from_pixel(
+        scaled(width),
+        pages.iter().map(|page| scaled(page.height())).sum(),
+        Rgba([255, 255, 255, 255]),
+    );
+    let mut y = 0;
+    for page in &pages {
+        let page = if factor < 1.0 {
+            imageops::resize(
+                page,
+                scaled(page.width()),
+                scaled(page.height()),
+                imageops::FilterType::Triangle,
+            )
+        } else {
+            page.to_rgba8()
+        };
+        imageops::overlay(&mut strip, &page, 0, y);
+        y += i64::from(page.height());
+    }
+
+    Ok(Strip {
+        bytes: encode(
+            &DynamicImage::ImageRgba8(strip),
+            format,
+            None,
+            None,
+            PngCompression::default(),
+            None,
+        )?,
+        downscaled_from: (factor < 1.0).then_some((width, height)),
+    })
+}
@@ -0,0 +1,30 @@
+use crate::RenderedPage;
+use crate::index::escape;
+use crate::pdfa::POINTS_PER_INCH;
+
+/// Every page of a document as an SVG of its own, as `(filename, bytes)` entries for a zip
+/// named like the page images. _hayro_ only renders pixels, so each SVG wraps the page image as
+/// a data URI rather than drawing its paths and text: it is as sharp as the image and no
+/// sharper, and its text can't be selected or searched. The SVG is the printed size of the page
+/// at the resolution it was rendered at, so it places properly in layout and drawing programs.
+pub fn svg_pages(pages: &[RenderedPage]) -> Vec<(String, Vec<u8>)> {
+    pages
+        .iter()
+        .filter_map(|page| {
+            let (format, bytes) = page.first_encoded()?;
+            let (width, height) = page.dimensions;
+            let points = |pixels: u32| pixels as f32 * POINTS_PER_INCH / page.dpi;
+            let svg = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}pt\" height=\"{}pt\" \
+                 viewBox=\"0 0 {width} {height}\">\n<title>{}</title>\n\
+                 <image width=\"{width}\" height=\"{height}\" href=\"{}\" />\n</svg>\n",
+                points(width),
+                points(height),
+                escape(&page.label()),
+                format.data_uri(bytes)
+            );
+            Some((format!("{}.svg", page.name), svg.into_bytes()))
+        })
+        .collect()
+}
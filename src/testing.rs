@@ -0,0 +1,72 @@
+use crate::pdfa::Writer;
+
+/// A page of a [`pdf`] made for tests, filled with one color so tests can tell pages apart in
+/// the rendered images.
+pub(crate) struct TestPage {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) color: [u8; 3],
+    /// More entries for the page dictionary, like `/Rotate 90`.
+    pub(crate) extra: &'static str,
+}
+
+impl TestPage {
+    pub(crate) fn new(width: u32, height: u32, color: [u8; 3]) -> Self {
+        Self {
+            width,
+            height,
+            color,
+            extra: "",
+        }
+    }
+}
+
+/// A PDF of `pages`, with `objects` added as they are, like an information dictionary.
+pub(crate) fn pdf(pages: &[TestPage], objects: &[&str]) -> Vec<u8> {
+    let mut pdf = Writer::default();
+    let catalog = pdf.reserve();
+    let tree = pdf.reserve();
+    let mut kids = Vec::new();
+    for page in pages {
+        let [r, g, b] = page.color.map(|channel| f32::from(channel) / 255.0);
+        let content = format!("{r} {g} {b} rg 0 0 {} {} re f", page.width, page.height);
+        let content_id = pdf.add(
+            format!("<< /Length {} >>", content.len()),
+            Some(content.as_bytes()),
+        );
+        kids.push(pdf.add(
+            format!(
+                "<< /Type /Page /Parent {tree} 0 R /MediaBox [0 0 {} {}] /Contents {content_id} 0 R {} >>",
+                page.width, page.height, page.extra
+            ),
+            None,
+        ));
+    }
+    for object in objects {
+        pdf.add(object.to_string(), None);
+    }
+    let kids: Vec<_> = kids.iter().map(|id| format!("{id} 0 R")).collect();
+    pdf.set(
+        tree,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        ),
+        None,
+    );
+    pdf.set(
+        catalog,
+        format!("<< /Type /Catalog /Pages {tree} 0 R >>"),
+        None,
+    );
+    pdf.finish(catalog)
+}
+
+/// A PDF of `count` white pages of 100 × 100 points.
+pub(crate) fn blank_pdf(count: usize) -> Vec<u8> {
+    let pages: Vec<_> = (0..count)
+        .map(|_| TestPage::new(100, 100, [255, 255, 255]))
+        .collect();
+    pdf(&pages, &[])
+}
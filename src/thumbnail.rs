@@ -0,0 +1,60 @@
+use crate::{ConversionError, RenderedPage};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba, RgbaImage, imageops};
+use std::io::Cursor;
+
+/// A small PNG of `page`, scaled down to fit within `max_size` pixels on both axes.
+pub fn thumbnail(page: &RenderedPage, max_size: u32) -> Result<Vec<u8>, ConversionError> {
+    let (format, bytes) = page
+        .first_encoded()
+        .ok_or_else(|| ConversionError::EncodeFailed("page has no images".to_string()))?;
+    let image = ImageReader::with_format(Cursor::new(bytes), format.image_format())
+        .decode()?
+        .thumbnail(max_size, max_size);
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// Longest side of every page on a contact sheet, in pixels.
+const CONTACT_CELL: u32 = 256;
+/// Transparent pixels between and around the pages of a contact sheet.
+const CONTACT_GAP: u32 = 16;
+
+/// A PNG with every one of `pages` scaled down and laid out in a grid as close to square as it
+/// gets, in order, each centered in its cell. Pages without any images are left out.
+pub fn contact_sheet(pages: &[&RenderedPage]) -> Result<Vec<u8>, ConversionError> {
+    let thumbnails = pages
+        .iter()
+        .filter_map(|page| page.first_encoded())
+        .map(|(format, bytes)| {
+            Ok(
+                ImageReader::with_format(Cursor::new(bytes), format.image_format())
+                    .decode()?
+                    .thumbnail(CONTACT_CELL, CONTACT_CELL)
+                    .to_rgba8(),
+            )
+        })
+        .collect::<Result<Vec<_>, ConversionError>>()?;
+    if thumbnails.is_empty() {
+        return Err(ConversionError::EncodeFailed(
+            "there are no pages for a contact sheet".to_string(),
+        ));
+    }
+    let columns = (thumbnails.len() as f32).sqrt().ceil() as u32;
+    let rows = (thumbnails.len() as u32).div_ceil(columns);
+    let step = CONTACT_CELL + CONTACT_GAP;
+    let mut sheet = RgbaImage::from_pixel(
+        columns * step + CONTACT_GAP,
+        rows * step + CONTACT_GAP,
+        Rgba([0, 0, 0, 0]),
+    );
+    for (index, thumbnail) in thumbnails.iter().enumerate() {
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        let x = CONTACT_GAP + column * step + (CONTACT_CELL - thumbnail.width()) / 2;
+        let y = CONTACT_GAP + row * step + (CONTACT_CELL - thumbnail.height()) / 2;
+        imageops::overlay(&mut sheet, thumbnail, i64::from(x), i64::from(y));
+    }
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(sheet).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
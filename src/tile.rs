@@ -0,0 +1,130 @@
+use crate::convert::encode_with_quality;
+use crate::{ConversionError, OutputFormat, PngCompression, RenderedPage};
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One piece of a page split into a grid, encoded into every enabled format.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Tile {
+    /// 0-indexed row and column in the grid.
+    pub(crate) row: u32,
+    pub(crate) column: u32,
+    /// Position and size in the page image, in pixels. Tiles along the right and bottom edges
+    /// are smaller when the page isn't a multiple of the tile size.
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) encoded: BTreeMap<OutputFormat, Vec<u8>>,
+}
+
+/// Split `image` into tiles of `size` pixels square, each encoded like the whole page.
+pub(crate) fn split(
+    image: &DynamicImage,
+    size: u32,
+    formats: impl IntoIterator<Item = OutputFormat> + Clone,
+    dpi: Option<f32>,
+    xmp: Option<&str>,
+    compression: PngCompression,
+    jpeg_quality: u8,
+) -> Result<Vec<Tile>, ConversionError> {
+    let size = size.max(1);
+    let (width, height) = image.dimensions();
+    let mut tiles = Vec::new();
+    for (row, y) in (0..height).step_by(size as usize).enumerate() {
+        for (column, x) in (0..width).step_by(size as usize).enumerate() {
+            let (tile_width, tile_height) = (size.min(width - x), size.min(height - y));
+            let tile = image.crop_imm(x, y, tile_width, tile_height);
+            let encoded = formats
+                .clone()
+                .into_iter()
+                .map(|format| {
+                    let bytes = encode_with_quality(
+                        &tile,
+                        format,
+                        dpi,
+                        xmp,
+                        compression,
+                        None,
+                        jpeg_quality,
+                    )?;
+                    Ok((format, bytes))
+                })
+                .collect::<Result<_, ConversionError>>()?;
+            tiles.push(Tile {
+                row: row as u32,
+                column: column as u32,
+                x,
+                y,
+                width: tile_width,
+                height: tile_height,
+                encoded,
+            });
+        }
+    }
+    Ok(tiles)
+}
+
+#[derive(Serialize)]
+struct Layout<'a> {
+    pages: Vec<PageLayout<'a>>,
+}
+
+#[derive(Serialize)]
+struct PageLayout<'a> {
+    page: usize,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    rows: u32,
+    columns: u32,
+    tiles: Vec<TileLayout<'a>>,
+}
+
+#[derive(Serialize)]
+struct TileLayout<'a> {
+    row: u32,
+    column: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    file: &'a str,
+}
+
+/// A JSON description of how the tiled pages in `pages` are put together, for viewers that
+/// load them as a grid. `files` holds the filenames of the tiles of every page, in order.
+pub(crate) fn layout_json(pages: &[RenderedPage], files: &[Vec<String>]) -> String {
+    let pages = pages
+        .iter()
+        .zip(files)
+        .filter(|(page, _)| !page.tiles().is_empty())
+        .map(|(page, files)| {
+            let tiles = page.tiles();
+            PageLayout {
+                page: page.number,
+                width: page.dimensions.0,
+                height: page.dimensions.1,
+                // the top left tile is never cut short by an edge
+                tile_size: tiles[0].width.max(tiles[0].height),
+                rows: tiles.iter().map(|tile| tile.row + 1).max().unwrap_or(0),
+                columns: tiles.iter().map(|tile| tile.column + 1).max().unwrap_or(0),
+                tiles: tiles
+                    .iter()
+                    .zip(files)
+                    .map(|(tile, file)| TileLayout {
+                        row: tile.row,
+                        column: tile.column,
+                        x: tile.x,
+                        y: tile.y,
+                        width: tile.width,
+                        height: tile.height,
+                        file,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&Layout { pages }).expect("tile layout serializes to JSON")
+}
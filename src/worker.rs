@@ -0,0 +1,163 @@
+use crate::{Conversion, ConversionError, Converted, Settings, SubstituteFont};
+use gloo::timers::callback::Timeout;
+use gloo::worker::{HandlerId, Worker, WorkerScope};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What the page asks of a [`ConvertWorker`].
+#[derive(Serialize, Deserialize)]
+pub enum WorkerRequest {
+    /// Convert the PDF in `data` like [`convert`](crate::convert) with `settings`, whose
+    /// substitute font is passed along separately as a name and its data, as settings are
+    /// serialized without it.
+    Convert {
+        stem: String,
+        data: Vec<u8>,
+        settings: Box<Settings>,
+        font: Option<(String, Vec<u8>)>,
+    },
+    /// Stop starting new pages, or carry on where the conversion stopped.
+    Pause(bool),
+    /// Scale the pages still to render by this on top of the settings, to stay within a pixel
+    /// budget, see [`Conversion::set_scale_factor`].
+    ScaleFactor(f32),
+}
+
+impl WorkerRequest {
+    /// The request to convert `data` with `settings`, including their substitute font.
+    pub fn convert(stem: &str, data: Vec<u8>, settings: &Settings) -> Self {
+        Self::Convert {
+            stem: stem.to_string(),
+            data,
+            settings: Box::new(settings.clone()),
+            font: settings
+                .substitute_font
+                .as_ref()
+                .map(|font| (font.name.clone(), font.data().to_vec())),
+        }
+    }
+}
+
+/// What a [`ConvertWorker`] tells the page about its conversion.
+#[derive(Serialize, Deserialize)]
+pub enum WorkerUpdate {
+    /// How many of its pages the document has done, how many it has in total, and how many
+    /// pixels the pages rendered so far have.
+    Progress(usize, usize, u64),
+    Done(Result<Converted, ConversionError>),
+}
+
+/// A conversion and whether its next page is already due.
+struct Task {
+    conversion: Conversion,
+    scheduled: bool,
+}
+
+/// Converts documents off the page, so it stays responsive however long a page takes to render.
+/// Every connected bridge converts one document at a time, a page per task of the worker so
+/// pausing and disconnecting get through in between. Disconnecting drops the conversion.
+#[derive(Default)]
+pub struct ConvertWorker {
+    tasks: HashMap<HandlerId, Task>,
+    paused: bool,
+}
+
+impl ConvertWorker {
+    /// Render the next page for `id` in a task of its own, unless that is already due.
+    fn schedule(&mut self, scope: &WorkerScope<Self>, id: HandlerId) {
+        let Some(task) = self.tasks.get_mut(&id) else {
+            return;
+        };
+        if task.scheduled {
+            return;
+        }
+        task.scheduled = true;
+        // a timeout rather than a message to itself, which would run before any from the page
+        let next = scope.callback(move |()| id);
+        Timeout::new(0, move || next(())).forget();
+    }
+}
+
+impl Worker for ConvertWorker {
+    /// Render the next page of the bridge.
+    type Message = HandlerId;
+    type Input = WorkerRequest;
+    type Output = WorkerUpdate;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, scope: &WorkerScope<Self>, id: HandlerId) {
+        let Some(task) = self.tasks.get_mut(&id) else {
+            return;
+        };
+        task.scheduled = false;
+        if self.paused {
+            return;
+        }
+        let conversion = &mut task.conversion;
+        if conversion.is_done() {
+            let task = self.tasks.remove(&id).expect("task was just looked up");
+            scope.respond(id, WorkerUpdate::Done(task.conversion.finish()));
+            return;
+        }
+        let (done, total) = conversion.progress();
+        scope.respond(
+            id,
+            WorkerUpdate::Progress(done, total, conversion.rendered_pixels()),
+        );
+        if let Err(err) = conversion.render_next() {
+            self.tasks.remove(&id);
+            scope.respond(id, WorkerUpdate::Done(Err(err)));
+            return;
+        }
+        self.schedule(scope, id);
+    }
+
+    fn received(&mut self, scope: &WorkerScope<Self>, request: WorkerRequest, id: HandlerId) {
+        match request {
+            WorkerRequest::Convert {
+                stem,
+                data,
+                mut settings,
+                font,
+            } => {
+                settings.substitute_font =
+                    font.and_then(|(name, data)| SubstituteFont::new(&name, data));
+                match Conversion::new(Arc::new(data), &stem, &settings) {
+                    Ok(conversion) => {
+                        self.tasks.insert(
+                            id,
+                            Task {
+                                conversion,
+                                scheduled: false,
+                            },
+                        );
+                        self.schedule(scope, id);
+                    }
+                    Err(err) => scope.respond(id, WorkerUpdate::Done(Err(err))),
+                }
+            }
+            WorkerRequest::Pause(paused) => {
+                self.paused = paused;
+                if !paused {
+                    let ids: Vec<_> = self.tasks.keys().copied().collect();
+                    for id in ids {
+                        self.schedule(scope, id);
+                    }
+                }
+            }
+            WorkerRequest::ScaleFactor(factor) => {
+                if let Some(task) = self.tasks.get_mut(&id) {
+                    task.conversion.set_scale_factor(factor);
+                }
+            }
+        }
+    }
+
+    fn disconnected(&mut self, _scope: &WorkerScope<Self>, id: HandlerId) {
+        self.tasks.remove(&id);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::ConversionError;
+use serde::{Deserialize, Serialize};
+
+/// Identifier starting the APP1 segment that holds XMP in a JPEG.
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Keyword of the PNG `iTXt` chunk that holds XMP.
+pub(crate) const PNG_XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+/// Descriptive metadata embedded into images as XMP, for asset management tools.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: String,
+    pub creator: String,
+    pub rights: String,
+    /// Comma-separated.
+    pub keywords: String,
+}
+
+impl Metadata {
+    /// These fields, with the empty ones taken from `fallback`.
+    pub(crate) fn or(&self, fallback: &Metadata) -> Metadata {
+        let pick = |field: &String, fallback: &String| {
+            if field.trim().is_empty() {
+                fallback.clone()
+            } else {
+                field.clone()
+            }
+        };
+        Metadata {
+            title: pick(&self.title, &fallback.title),
+            creator: pick(&self.creator, &fallback.creator),
+            rights: pick(&self.rights, &fallback.rights),
+            keywords: pick(&self.keywords, &fallback.keywords),
+        }
+    }
+
+    /// An XMP packet with the Dublin Core properties of the non-empty fields.
+    pub(crate) fn packet(&self) -> String {
+        self.packet_with("")
+    }
+
+    /// [`Self::packet`], also declaring conformance to PDF/A-2b as PDF/A documents have to.
+    pub(crate) fn pdfa_packet(&self) -> String {
+        self.packet_with(
+            "<rdf:Description rdf:about=\"\" xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\
+             <pdfaid:part>2</pdfaid:part><pdfaid:conformance>B</pdfaid:conformance>\
+             </rdf:Description>",
+        )
+    }
+
+    /// An XMP packet of the Dublin Core properties and the `extra` descriptions.
+    fn packet_with(&self, extra: &str) -> String {
+        let mut properties = String::new();
+        let alternative = |name: &str, value: &str| {
+            format!(
+                "<dc:{name}><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:{name}>",
+                escape(value.trim())
+            )
+        };
+        if !self.title.trim().is_empty() {
+            properties += &alternative("title", &self.title);
+        }
+        if !self.creator.trim().is_empty() {
+            properties += &format!(
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+                escape(self.creator.trim())
+            );
+        }
+        if !self.rights.trim().is_empty() {
+            properties += &alternative("rights", &self.rights);
+        }
+        let keywords: String = self
+            .keywords
+            .split(',')
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .map(|keyword| format!("<rdf:li>{}</rdf:li>", escape(keyword)))
+            .collect();
+        if !keywords.is_empty() {
+            properties += &format!("<dc:subject><rdf:Bag>{keywords}</rdf:Bag></dc:subject>");
+        }
+        format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+             <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\
+             {properties}\
+             </rdf:Description>{extra}</rdf:RDF></x:xmpmeta>\
+             <?xpacket end=\"r\"?>"
+        )
+    }
+}
+
+/// Put an XMP `packet` into `jpeg` as an APP1 segment.
+pub(crate) fn insert_into_jpeg(jpeg: &mut Vec<u8>, packet: &str) -> Result<(), ConversionError> {
+    let payload = [JPEG_XMP_HEADER, packet.as_bytes()].concat();
+    insert_jpeg_segment(jpeg, 0xE1, &payload).ok_or_else(|| {
+        ConversionError::EncodeFailed("XMP metadata is too large for a JPEG".to_string())
+    })
+}
+
+/// Put a segment with `marker` and `payload` into `jpeg`, after its JFIF header if it has one.
+/// `None` if the payload is too large for a segment.
+pub(crate) fn insert_jpeg_segment(jpeg: &mut Vec<u8>, marker: u8, payload: &[u8]) -> Option<()> {
+    // start of image, then possibly APP0 with its big-endian length
+    let mut offset = 2;
+    if jpeg.get(2..4) == Some(&[0xFF, 0xE0]) {
+        offset += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+    let length = u16::try_from(2 + payload.len()).ok()?;
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(payload);
+    jpeg.splice(offset..offset, segment);
+    Some(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,64 @@
+// Tests of `convertBatch` as a JavaScript host sees it, run against the `wasm-pack build
+// --target nodejs` output in `pkg/` with `node --test tests/`.
+
+import assert from "node:assert/strict";
+import { test } from "node:test";
+import { convertBatch } from "../pkg/pdf_to_whatever.js";
+
+/** A PDF of `pageCount` white pages of 100 × 100 points. */
+function blankPdf(pageCount) {
+  const pages = Array.from({ length: pageCount }, (_, index) => 3 + index);
+  const objects = [
+    "<< /Type /Catalog /Pages 2 0 R >>",
+    `<< /Type /Pages /Kids [${pages.map((id) => `${id} 0 R`).join(" ")}] /Count ${pageCount} >>`,
+    ...pages.map(() => "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] >>"),
+  ];
+  let pdf = "%PDF-1.7\n";
+  const offsets = objects.map((object, index) => {
+    const offset = pdf.length;
+    pdf += `${index + 1} 0 obj\n${object}\nendobj\n`;
+    return offset;
+  });
+  const xref = pdf.length;
+  pdf += `xref\n0 ${objects.length + 1}\n0000000000 65535 f \n`;
+  for (const offset of offsets) {
+    pdf += `${String(offset).padStart(10, "0")} 00000 n \n`;
+  }
+  pdf += `trailer\n<< /Size ${objects.length + 1} /Root 1 0 R >>\nstartxref\n${xref}\n%%EOF\n`;
+  return new TextEncoder().encode(pdf);
+}
+
+test("every file gets a zip or an error, in order", async () => {
+  const garbage = new TextEncoder().encode("not a pdf");
+  const results = await convertBatch([blankPdf(2), garbage, blankPdf(1)]);
+  assert.equal(results.length, 3);
+  assert.ok(results[0].zip instanceof Uint8Array);
+  // zips start with the local file header signature `PK\x03\x04`
+  assert.deepEqual([...results[0].zip.slice(0, 4)], [0x50, 0x4b, 0x03, 0x04]);
+  assert.equal(typeof results[1].error, "string");
+  assert.ok(results[2].zip instanceof Uint8Array);
+});
+
+test("settings are taken from an object, missing fields keeping their defaults", async () => {
+  const [result] = await convertBatch([blankPdf(1)], { formats: ["Jpeg"] });
+  assert.ok(result.zip instanceof Uint8Array);
+  const [rejected] = await convertBatch([blankPdf(1)], { formats: ["Gif"] }).then(
+    () => [],
+    (error) => [error],
+  );
+  assert.ok(rejected, "unknown formats are rejected");
+});
+
+test("progress is reported after every page of every file", async () => {
+  const reports = [];
+  await convertBatch([blankPdf(3), blankPdf(2)], undefined, (done, total, file) => {
+    reports.push([done, total, file]);
+  });
+  assert.deepEqual(reports, [
+    [1, 3, 0],
+    [2, 3, 0],
+    [3, 3, 0],
+    [1, 2, 1],
+    [2, 2, 1],
+  ]);
+});